@@ -0,0 +1,204 @@
+use crate::adfs::{
+    ADFS_SECTOR_BYTES, DIRECTORY_BYTES, Directory, DirectoryEntry, FreeSpaceMap, HUGO,
+    ROOT_DIRECTORY_SECTOR,
+};
+use anyhow::{Result, bail};
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom};
+
+// Read-only access to an ADFS old map disc image: .adf and .adl images hold
+// logical sectors in order so no interleaving is needed
+pub struct AdfsImage<R: Read + Seek> {
+    pub map: FreeSpaceMap,
+    reader: R,
+}
+
+// File or directory together with the names of the directories containing
+// it, starting below the root directory
+#[derive(Debug)]
+pub struct AdfsFile {
+    pub parents: Vec<String>,
+    pub entry: DirectoryEntry,
+}
+
+impl AdfsFile {
+    pub fn adfs_path(&self) -> String {
+        let mut s = String::from("$");
+        for name in self.parents.iter().chain([&self.entry.name]) {
+            s.push('.');
+            s.push_str(name);
+        }
+        s
+    }
+}
+
+impl<R: Read + Seek> AdfsImage<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut bytes = vec![0; ADFS_SECTOR_BYTES * 2];
+        read_sectors(&mut reader, 0, &mut bytes)?;
+        let map = FreeSpaceMap::from_bytes(&bytes)?;
+        Ok(Self { map, reader })
+    }
+
+    // Root directory signature at sector 2
+    pub fn probe(reader: &mut R) -> bool {
+        let mut bytes = vec![0; DIRECTORY_BYTES];
+        read_sectors(reader, ROOT_DIRECTORY_SECTOR, &mut bytes).is_ok()
+            && bytes[1..5] == HUGO[..]
+            && bytes[0x4fb..0x4ff] == HUGO[..]
+    }
+
+    pub fn read_directory(&mut self, sector: u32) -> Result<Directory> {
+        let mut bytes = vec![0; DIRECTORY_BYTES];
+        read_sectors(&mut self.reader, sector, &mut bytes)?;
+        Directory::from_bytes(&bytes)
+    }
+
+    pub fn read_file(&mut self, entry: &DirectoryEntry) -> Result<Vec<u8>> {
+        let mut bytes = vec![0; usize::try_from(entry.length)?];
+        read_sectors(&mut self.reader, entry.start_sector, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    // Every file and directory on the disc, with each directory followed by
+    // its contents
+    pub fn walk(&mut self) -> Result<Vec<AdfsFile>> {
+        let mut files = Vec::new();
+        let mut visited = HashSet::from([ROOT_DIRECTORY_SECTOR]);
+        self.walk_directory(ROOT_DIRECTORY_SECTOR, &[], &mut visited, &mut files)?;
+        Ok(files)
+    }
+
+    fn walk_directory(
+        &mut self,
+        sector: u32,
+        parents: &[String],
+        visited: &mut HashSet<u32>,
+        files: &mut Vec<AdfsFile>,
+    ) -> Result<()> {
+        let directory = self.read_directory(sector)?;
+        for entry in directory.entries {
+            let file = AdfsFile {
+                parents: parents.to_vec(),
+                entry,
+            };
+            if !file.entry.is_directory {
+                files.push(file);
+                continue;
+            }
+
+            if !visited.insert(file.entry.start_sector) {
+                bail!("directory {path} forms a loop", path = file.adfs_path())
+            }
+
+            let start_sector = file.entry.start_sector;
+            let mut child_parents = parents.to_vec();
+            child_parents.push(file.entry.name.clone());
+            files.push(file);
+            self.walk_directory(start_sector, &child_parents, visited, files)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_sectors<R: Read + Seek>(reader: &mut R, sector: u32, buffer: &mut [u8]) -> Result<()> {
+    reader.seek(SeekFrom::Start(
+        u64::from(sector) * ADFS_SECTOR_BYTES as u64,
+    ))?;
+    reader.read_exact(buffer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adfs::{ADFS_SECTOR_BYTES, AdfsImage, HUGO, map_checksum};
+    use crate::dfs::BootOption;
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    fn make_directory(bytes: &mut [u8], sector: usize, entries: &[(&str, u32, u32, bool)]) {
+        let offset = sector * ADFS_SECTOR_BYTES;
+        let directory = &mut bytes[offset..offset + 0x500];
+        directory[1..5].copy_from_slice(HUGO);
+        directory[0x4fb..0x4ff].copy_from_slice(HUGO);
+        directory[0x4d9..0x4e0].copy_from_slice(b"Title\r\0");
+        for (i, &(name, length, start_sector, is_directory)) in entries.iter().enumerate() {
+            let entry = &mut directory[5 + i * 26..5 + (i + 1) * 26];
+            entry[..name.len()].copy_from_slice(name.as_bytes());
+            if name.len() < 10 {
+                entry[name.len()] = b'\r';
+            }
+            entry[0] |= 0x80;
+            entry[2] |= 0x80;
+            if is_directory {
+                entry[3] |= 0x80;
+            } else {
+                entry[1] |= 0x80;
+            }
+            entry[0x0a..0x0e].copy_from_slice(&0xffff_1900u32.to_le_bytes());
+            entry[0x12..0x16].copy_from_slice(&length.to_le_bytes());
+            entry[0x16..0x19].copy_from_slice(&start_sector.to_le_bytes()[..3]);
+        }
+    }
+
+    fn make_image() -> Vec<u8> {
+        let mut bytes = vec![0u8; 640 * ADFS_SECTOR_BYTES];
+        bytes[0] = 0x14;
+        bytes[ADFS_SECTOR_BYTES] = 0x6c;
+        bytes[ADFS_SECTOR_BYTES + 1] = 0x02;
+        bytes[0xfc..0xff].copy_from_slice(&[0x80, 0x02, 0x00]);
+        bytes[0xf7..0xfc].copy_from_slice(b"AE  S");
+        bytes[ADFS_SECTOR_BYTES + 0xf6..ADFS_SECTOR_BYTES + 0xfb].copy_from_slice(b"DSEE ");
+        bytes[ADFS_SECTOR_BYTES + 0xfd] = 3;
+        bytes[ADFS_SECTOR_BYTES + 0xfe] = 3;
+        for sector in 0..2 {
+            let offset = sector * ADFS_SECTOR_BYTES;
+            bytes[offset + 255] = map_checksum(&bytes[offset..offset + ADFS_SECTOR_BYTES]);
+        }
+
+        make_directory(
+            &mut bytes,
+            2,
+            &[("!BOOT", 5, 7, false), ("GAMES", 0x500, 8, true)],
+        );
+        make_directory(&mut bytes, 8, &[("Elite/Dat", 3, 13, false)]);
+        bytes[7 * ADFS_SECTOR_BYTES..7 * ADFS_SECTOR_BYTES + 5].copy_from_slice(b"*RUN\r");
+        bytes[13 * ADFS_SECTOR_BYTES..13 * ADFS_SECTOR_BYTES + 3].copy_from_slice(b"abc");
+        bytes
+    }
+
+    #[test]
+    fn walk() -> Result<()> {
+        let mut reader = Cursor::new(make_image());
+        assert!(AdfsImage::probe(&mut reader));
+
+        let mut image = AdfsImage::new(reader)?;
+        assert_eq!("ADES", image.map.disc_name);
+        assert_eq!(640, image.map.total_sectors);
+        assert_eq!(BootOption::Exec, image.map.boot_option);
+        assert_eq!(0x26c, image.map.free_sectors());
+
+        let files = image.walk()?;
+        assert_eq!(
+            vec![
+                ("$.!BOOT".to_string(), "LWR".to_string()),
+                ("$.GAMES".to_string(), "DLR".to_string()),
+                ("$.GAMES.Elite/Dat".to_string(), "LWR".to_string()),
+            ],
+            files
+                .iter()
+                .map(|file| (file.adfs_path(), file.entry.attributes()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0xffff_1900, files[2].entry.load_address);
+        assert_eq!(b"abc".to_vec(), image.read_file(&files[2].entry)?);
+        Ok(())
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let mut bytes = make_image();
+        bytes[0x10] ^= 1;
+        assert!(AdfsImage::new(Cursor::new(bytes)).is_err());
+    }
+}
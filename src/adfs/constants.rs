@@ -0,0 +1,14 @@
+pub const ADFS_SECTOR_BYTES: usize = 256;
+
+pub const ADFS_SECTORS_PER_TRACK: usize = 16;
+
+pub const ROOT_DIRECTORY_SECTOR: u32 = 2;
+
+// Old map directories occupy five sectors
+pub const DIRECTORY_BYTES: usize = 0x500;
+
+pub const DIRECTORY_ENTRY_BYTES: usize = 26;
+
+pub const MAX_DIRECTORY_ENTRIES: usize = 47;
+
+pub const HUGO: &[u8; 4] = b"Hugo";
@@ -0,0 +1,46 @@
+use crate::adfs::{
+    DIRECTORY_BYTES, DIRECTORY_ENTRY_BYTES, DirectoryEntry, HUGO, MAX_DIRECTORY_ENTRIES, read_title,
+};
+use anyhow::{Result, bail};
+
+// Old map directory of five sectors holding up to 47 entries in alphabetical
+// order, terminated by an entry starting with a zero byte
+// &000     : master sequence number
+// &001-&004: "Hugo"
+// &4CC-&4D5: directory name
+// &4D6-&4D8: start sector of parent directory
+// &4D9-&4EB: directory title
+// &4FA     : master sequence number
+// &4FB-&4FE: "Hugo"
+#[derive(Debug)]
+pub struct Directory {
+    pub title: String,
+    pub entries: Vec<DirectoryEntry>,
+}
+
+impl Directory {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < DIRECTORY_BYTES {
+            bail!("directory truncated")
+        }
+
+        if bytes[1..5] != HUGO[..] || bytes[0x4fb..0x4ff] != HUGO[..] {
+            bail!("directory does not have old map signature")
+        }
+
+        if bytes[0] != bytes[0x4fa] {
+            bail!("directory is broken: sequence numbers do not match")
+        }
+
+        let entries = bytes[5..5 + MAX_DIRECTORY_ENTRIES * DIRECTORY_ENTRY_BYTES]
+            .chunks_exact(DIRECTORY_ENTRY_BYTES)
+            .take_while(|entry_bytes| entry_bytes[0] != 0)
+            .map(DirectoryEntry::from_bytes)
+            .collect();
+
+        Ok(Self {
+            title: read_title(&bytes[0x4d9..0x4ec]),
+            entries,
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use crate::adfs::{read_name, read_u24, read_u32};
+
+// 26-byte entry in an old map directory: attributes are held in the top bit
+// of the first bytes of the name
+// &00-&09: name and attributes (R, W, L, D and E in bytes 0 to 4)
+// &0A-&0D: load address
+// &0E-&11: execution address
+// &12-&15: length
+// &16-&18: start sector
+// &19    : sequence number
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug)]
+pub struct DirectoryEntry {
+    pub name: String,
+    pub readable: bool,
+    pub writable: bool,
+    pub locked: bool,
+    pub is_directory: bool,
+    pub execute_only: bool,
+    pub load_address: u32,
+    pub execution_address: u32,
+    pub length: u32,
+    pub start_sector: u32,
+}
+
+impl DirectoryEntry {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let flag = |i: usize| bytes[i] & 0x80 != 0;
+        Self {
+            name: read_name(&bytes[0..10]),
+            readable: flag(0),
+            writable: flag(1),
+            locked: flag(2),
+            is_directory: flag(3),
+            execute_only: flag(4),
+            load_address: read_u32(&bytes[0x0a..]),
+            execution_address: read_u32(&bytes[0x0e..]),
+            length: read_u32(&bytes[0x12..]),
+            start_sector: read_u24(&bytes[0x16..]),
+        }
+    }
+
+    // Attributes in the order shown by *CAT
+    pub fn attributes(&self) -> String {
+        [
+            (self.is_directory, 'D'),
+            (self.locked, 'L'),
+            (self.writable, 'W'),
+            (self.readable, 'R'),
+            (self.execute_only, 'E'),
+        ]
+        .into_iter()
+        .filter_map(|(set, c)| set.then_some(c))
+        .collect()
+    }
+}
@@ -0,0 +1,71 @@
+use crate::adfs::{ADFS_SECTOR_BYTES, map_checksum, read_name, read_u24};
+use crate::dfs::BootOption;
+use anyhow::{Result, bail};
+
+// Old map free space map in sectors 0 and 1: start sectors of each run of
+// free space are listed in sector 0 and their lengths in sector 1, three
+// bytes each, followed by details of the disc
+// &F6 in sector 1 onwards and &F7 in sector 0 onwards: disc name,
+// interleaved between the two sectors
+// &FC-&FE in sector 0: total number of sectors on the disc
+// &FB-&FC in sector 1: disc identifier
+// &FD in sector 1: boot option
+// &FE in sector 1: end of free space list (three times the number of runs)
+#[derive(Debug)]
+pub struct FreeSpaceMap {
+    pub disc_name: String,
+    pub total_sectors: u32,
+    pub boot_option: BootOption,
+    pub free_space: Vec<FreeSpace>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FreeSpace {
+    pub start_sector: u32,
+    pub sector_count: u32,
+}
+
+impl FreeSpaceMap {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < ADFS_SECTOR_BYTES * 2 {
+            bail!("free space map truncated")
+        }
+
+        let (sector_0, sector_1) = bytes[..ADFS_SECTOR_BYTES * 2].split_at(ADFS_SECTOR_BYTES);
+        for (i, sector) in [sector_0, sector_1].iter().enumerate() {
+            if map_checksum(sector) != sector[255] {
+                bail!("invalid checksum in sector {i} of free space map")
+            }
+        }
+
+        let end = usize::from(sector_1[0xfe]);
+        if end % 3 != 0 || end > 0xf6 {
+            bail!("invalid end of free space list &{end:02X}")
+        }
+
+        let free_space = (0..end)
+            .step_by(3)
+            .map(|i| FreeSpace {
+                start_sector: read_u24(&sector_0[i..]),
+                sector_count: read_u24(&sector_1[i..]),
+            })
+            .collect();
+
+        let disc_name = read_name(
+            &(0..5)
+                .flat_map(|i| [sector_0[0xf7 + i], sector_1[0xf6 + i]])
+                .collect::<Vec<_>>(),
+        );
+
+        Ok(Self {
+            disc_name,
+            total_sectors: read_u24(&sector_0[0xfc..]),
+            boot_option: BootOption::from_option_bits(sector_1[0xfd]),
+            free_space,
+        })
+    }
+
+    pub fn free_sectors(&self) -> u32 {
+        self.free_space.iter().map(|f| f.sector_count).sum()
+    }
+}
@@ -0,0 +1,13 @@
+mod adfs_image;
+mod constants;
+mod directory;
+mod directory_entry;
+mod free_space_map;
+mod util;
+
+pub use adfs_image::*;
+pub use constants::*;
+pub use directory::*;
+pub use directory_entry::*;
+pub use free_space_map::*;
+pub use util::*;
@@ -0,0 +1,45 @@
+pub fn read_u24(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+}
+
+pub fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// Names are terminated by a control character or padded with spaces and may
+// carry attribute flags in the top bit of each byte
+pub fn read_name<'a, I>(bytes: I) -> String
+where
+    I: IntoIterator<Item = &'a u8>,
+{
+    bytes
+        .into_iter()
+        .map(|&b| b & 0x7f)
+        .take_while(|&b| b > b' ')
+        .map(char::from)
+        .collect()
+}
+
+// Directory titles may contain spaces
+pub fn read_title(bytes: &[u8]) -> String {
+    let s = bytes
+        .iter()
+        .map(|&b| b & 0x7f)
+        .take_while(|&b| b >= b' ')
+        .map(char::from)
+        .collect::<String>();
+    s.trim_end().to_string()
+}
+
+// Sum of bytes 254 down to 0 with end-around carry, stored in byte 255 of
+// each free space map sector
+pub fn map_checksum(sector: &[u8]) -> u8 {
+    let mut sum = 255u32;
+    for &b in sector[..255].iter().rev() {
+        if sum > 255 {
+            sum = (sum & 0xff) + 1;
+        }
+        sum += u32::from(b);
+    }
+    sum.to_le_bytes()[0]
+}
@@ -1,5 +1,9 @@
-use crate::dfs::DiscSize;
-use clap::{Parser, Subcommand};
+use crate::bbc_basic::MAX_LINE_NUMBER;
+use crate::commands::{ShowFormat, ShowStyle};
+use crate::dfs::{Address, BootOption, DfsPath, DiscSize, Side};
+use crate::image_format::ImageFormat;
+use crate::mmb::MMB_SLOT_COUNT;
+use clap::{ArgGroup, Parser, Subcommand};
 use path_absolutize::Absolutize;
 use std::path::PathBuf;
 use std::result::Result as StdResult;
@@ -17,6 +21,150 @@ pub struct Args {
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
+    #[command(
+        name = "access",
+        about = "Lock or unlock a file in a disc image",
+        group(ArgGroup::new("access").required(true).args(["lock", "unlock"]))
+    )]
+    Access {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        image_path: PathBuf,
+
+        #[arg(help = "DFS file name", required = true, value_parser = parse_dfs_path)]
+        name: DfsPath,
+
+        #[arg(
+            help = "Lock file",
+            long = "lock",
+            short = 'l',
+            default_value_t = false
+        )]
+        lock: bool,
+
+        #[arg(
+            help = "Unlock file",
+            long = "unlock",
+            short = 'u',
+            default_value_t = false
+        )]
+        unlock: bool,
+
+        #[arg(help = "Disc side", long = "side", short = 's', default_value = "0", value_parser = parse_side)]
+        side: Side,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(name = "add", about = "Add a file to a disc image")]
+    Add {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        image_path: PathBuf,
+
+        #[arg(help = "Path to file to add", required = true, value_parser = parse_absolute_path)]
+        path: PathBuf,
+
+        #[arg(help = "DFS file name (inferred from file name or .inf file if omitted)", long = "name", short = 'n', value_parser = parse_dfs_path)]
+        name: Option<DfsPath>,
+
+        #[arg(help = "Load address (hex)", long = "load", value_parser = parse_address)]
+        load_address: Option<Address>,
+
+        #[arg(help = "Execution address (hex)", long = "exec", value_parser = parse_address)]
+        execution_address: Option<Address>,
+
+        #[arg(
+            help = "Lock file",
+            long = "locked",
+            short = 'l',
+            default_value_t = false
+        )]
+        locked: bool,
+
+        #[arg(help = "Disc side", long = "side", short = 's', default_value = "0", value_parser = parse_side)]
+        side: Side,
+
+        #[arg(
+            help = "Replace file if it already exists",
+            long = "overwrite",
+            short = 'f',
+            default_value_t = false
+        )]
+        overwrite: bool,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(name = "check", about = "Check integrity of disc image catalogue")]
+    Check {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        path: PathBuf,
+
+        #[arg(help = "Output report as JSON", long = "json", default_value_t = false)]
+        json: bool,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(
+        name = "compact",
+        about = "Move files in a disc image together to make free space contiguous"
+    )]
+    Compact {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        image_path: PathBuf,
+
+        #[arg(help = "Disc side", long = "side", short = 's', default_value = "0", value_parser = parse_side)]
+        side: Side,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(
+        name = "crunch",
+        about = "Shrink BBC BASIC program by removing REMs and spaces and merging lines"
+    )]
+    Crunch {
+        #[arg(help = "Path to BBC BASIC file", required = true, value_parser = parse_absolute_path)]
+        path: PathBuf,
+
+        #[arg(help = "Path to BBC BASIC output file: input file is replaced if omitted", long = "output", short = 'o', value_parser = parse_absolute_path)]
+        output_path: Option<PathBuf>,
+
+        #[arg(
+            help = "Overwrite output file if it already exists",
+            long = "overwrite",
+            short = 'f',
+            default_value_t = false
+        )]
+        overwrite: bool,
+
+        #[arg(
+            help = "Shorten variable, procedure and function names",
+            long = "shorten-names",
+            default_value_t = false
+        )]
+        shorten_names: bool,
+    },
+
+    #[command(name = "delete", about = "Delete a file from a disc image")]
+    Delete {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        image_path: PathBuf,
+
+        #[arg(help = "DFS file name", required = true, value_parser = parse_dfs_path)]
+        name: DfsPath,
+
+        #[arg(help = "Disc side", long = "side", short = 's', default_value = "0", value_parser = parse_side)]
+        side: Side,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
     #[command(name = "detokenize", about = "Detokenize BBC BASIC program")]
     Detokenize {
         #[arg(help = "Path to BBC BASIC file", required = true, value_parser = parse_absolute_path)]
@@ -42,6 +190,21 @@ pub enum Command {
         lossless: bool,
     },
 
+    #[command(
+        name = "diff",
+        about = "Compare catalogues and file contents of two disc images or extracted manifests"
+    )]
+    Diff {
+        #[arg(help = "Path to first disc image, manifest or extracted directory", required = true, value_parser = parse_absolute_path)]
+        path_a: PathBuf,
+
+        #[arg(help = "Path to second disc image, manifest or extracted directory", required = true, value_parser = parse_absolute_path)]
+        path_b: PathBuf,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs) of both disc images: detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
     #[command(name = "extract", about = "Extract files and metadata from disc image")]
     Extract {
         #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
@@ -80,6 +243,23 @@ pub enum Command {
             default_value_t = false
         )]
         inf: bool,
+
+        #[arg(
+            help = "Preserve start sectors, catalogue order and unreferenced data so that make can rebuild a byte-identical image",
+            long = "preserve",
+            short = 'p',
+            default_value_t = false
+        )]
+        preserve: bool,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(name = "lint", about = "Check BBC BASIC program for mistakes")]
+    Lint {
+        #[arg(help = "Path to BBC BASIC file", required = true, value_parser = parse_absolute_path)]
+        path: PathBuf,
     },
 
     #[command(name = "make", about = "Make disc image from files and metadata")]
@@ -100,6 +280,9 @@ pub enum Command {
             default_value_t = false
         )]
         overwrite: bool,
+
+        #[arg(help = "Boot option (0-3 or none, load, run, exec)", long = "boot", short = 'b', value_parser = parse_boot_option)]
+        boot_option: Option<BootOption>,
     },
 
     #[command(
@@ -122,6 +305,27 @@ pub enum Command {
         overwrite: bool,
     },
 
+    #[command(
+        name = "map",
+        about = "Show which file owns each sector of a disc image and where free space is"
+    )]
+    Map {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        path: PathBuf,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(
+        name = "mmb",
+        about = "Manage discs in an MMFS multi-disc BEEB.MMB file"
+    )]
+    Mmb {
+        #[command(subcommand)]
+        command: MmbCommand,
+    },
+
     #[command(name = "new", about = "Create a new, empty disc image file")]
     New {
         #[arg(help = "Path to output disc image file", value_parser = parse_absolute_path)]
@@ -137,12 +341,118 @@ pub enum Command {
             default_value_t = false
         )]
         overwrite: bool,
+
+        #[arg(help = "Boot option (0-3 or none, load, run, exec)", long = "boot", short = 'b', value_parser = parse_boot_option)]
+        boot_option: Option<BootOption>,
+
+        #[arg(help = "Disc image format (ssd or dsd): taken from output file extension if omitted, otherwise ssd", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(name = "rename", about = "Rename a file in a disc image")]
+    Rename {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        image_path: PathBuf,
+
+        #[arg(help = "DFS file name", required = true, value_parser = parse_dfs_path)]
+        name: DfsPath,
+
+        #[arg(help = "New DFS file name", required = true, value_parser = parse_dfs_path)]
+        new_name: DfsPath,
+
+        #[arg(help = "Disc side", long = "side", short = 's', default_value = "0", value_parser = parse_side)]
+        side: Side,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(
+        name = "setaddr",
+        about = "Set load and/or execution address of a file in a disc image",
+        group(ArgGroup::new("addresses").required(true).multiple(true).args(["load_address", "execution_address"]))
+    )]
+    SetAddr {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        image_path: PathBuf,
+
+        #[arg(help = "DFS file name", required = true, value_parser = parse_dfs_path)]
+        name: DfsPath,
+
+        #[arg(help = "Load address (hex)", long = "load", value_parser = parse_address)]
+        load_address: Option<Address>,
+
+        #[arg(help = "Execution address (hex)", long = "exec", value_parser = parse_address)]
+        execution_address: Option<Address>,
+
+        #[arg(help = "Disc side", long = "side", short = 's', default_value = "0", value_parser = parse_side)]
+        side: Side,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(
+        name = "renumber",
+        about = "Renumber BBC BASIC program, updating GOTO, GOSUB etc."
+    )]
+    Renumber {
+        #[arg(help = "Path to BBC BASIC file", required = true, value_parser = parse_absolute_path)]
+        path: PathBuf,
+
+        #[arg(help = "Path to BBC BASIC output file: input file is replaced if omitted", long = "output", short = 'o', value_parser = parse_absolute_path)]
+        output_path: Option<PathBuf>,
+
+        #[arg(
+            help = "Overwrite output file if it already exists",
+            long = "overwrite",
+            short = 'f',
+            default_value_t = false
+        )]
+        overwrite: bool,
+
+        #[arg(help = "First new line number", long = "start", default_value_t = 10, value_parser = parse_line_number)]
+        start: u16,
+
+        #[arg(help = "Increment between new line numbers", long = "step", default_value_t = 10, value_parser = parse_line_number)]
+        step: u16,
+
+        #[arg(help = "Renumber only from this line onwards", long = "from", default_value_t = 0, value_parser = parse_line_number)]
+        from: u16,
     },
 
     #[command(name = "show", about = "Show catalogue")]
     Show {
         #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
         path: PathBuf,
+
+        #[arg(
+            help = "Show damaged or copy-protected catalogues as well as possible and list problems found",
+            long = "lenient",
+            default_value_t = false
+        )]
+        lenient: bool,
+
+        #[arg(help = "Output format (json, csv or inf): human-readable listing if omitted (--format selects the disc image format)", long = "output-format", value_parser = parse_show_format)]
+        output_format: Option<ShowFormat>,
+
+        #[arg(help = "Mimic Acorn DFS output (cat for *CAT or info for *INFO *.*)", long = "style", conflicts_with = "output_format", value_parser = parse_show_style)]
+        style: Option<ShowStyle>,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
+    },
+
+    #[command(
+        name = "textconv",
+        about = "Print a text rendering of a disc image for use as a git textconv filter"
+    )]
+    Textconv {
+        #[arg(help = "Path to disc image file", required = true, value_parser = parse_absolute_path)]
+        path: PathBuf,
+
+        #[arg(help = "Disc image format (ssd, dsd, dds, ddd or adfs): detected from content if omitted", long = "format", value_parser = parse_image_format)]
+        format: Option<ImageFormat>,
     },
 
     #[command(name = "tokenize", about = "Tokenize BBC BASIC program")]
@@ -161,6 +471,122 @@ pub enum Command {
         )]
         overwrite: bool,
     },
+
+    #[command(
+        name = "xref",
+        about = "List variables, procedures, functions and line numbers used by BBC BASIC program"
+    )]
+    Xref {
+        #[arg(help = "Path to BBC BASIC file", required = true, value_parser = parse_absolute_path)]
+        path: PathBuf,
+
+        #[arg(help = "Output report as JSON", long = "json", default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MmbCommand {
+    #[command(name = "list", about = "List discs in an MMB file")]
+    List {
+        #[arg(help = "Path to MMB file", required = true, value_parser = parse_absolute_path)]
+        mmb_path: PathBuf,
+    },
+
+    #[command(
+        name = "extract",
+        about = "Extract files and metadata from a disc in an MMB file"
+    )]
+    Extract {
+        #[arg(help = "Path to MMB file", required = true, value_parser = parse_absolute_path)]
+        mmb_path: PathBuf,
+
+        #[arg(help = "Slot number (0-510)", required = true, value_parser = parse_slot)]
+        slot: u16,
+
+        #[arg(help = "Path to output directory", required = true, value_parser = parse_absolute_path)]
+        output_dir: PathBuf,
+
+        #[arg(
+            help = "Overwrite output files if they already exist",
+            long = "overwrite",
+            short = 'f',
+            default_value_t = false
+        )]
+        overwrite: bool,
+
+        #[arg(
+            help = "Don't detokenize BASIC programs",
+            short = 'n',
+            default_value_t = false
+        )]
+        no_detokenize: bool,
+
+        #[arg(
+            help = "Output BBC BASIC in non-printable lossless format preserving all control characters etc.",
+            long = "lossless",
+            short = 'l',
+            default_value_t = false
+        )]
+        lossless: bool,
+
+        #[arg(
+            help = "Generate .inf files instead of storing metadata in manifest",
+            long = "inf",
+            short = 'i',
+            default_value_t = false
+        )]
+        inf: bool,
+    },
+
+    #[command(
+        name = "insert",
+        about = "Insert an .ssd disc image into an MMB file, creating the MMB file if it does not exist"
+    )]
+    Insert {
+        #[arg(help = "Path to MMB file", required = true, value_parser = parse_absolute_path)]
+        mmb_path: PathBuf,
+
+        #[arg(help = "Path to .ssd disc image file", required = true, value_parser = parse_absolute_path)]
+        image_path: PathBuf,
+
+        #[arg(help = "Slot number (0-510): first free slot if omitted", long = "slot", value_parser = parse_slot)]
+        slot: Option<u16>,
+
+        #[arg(
+            help = "Replace disc if slot is already in use",
+            long = "overwrite",
+            short = 'f',
+            default_value_t = false
+        )]
+        overwrite: bool,
+    },
+
+    #[command(name = "remove", about = "Remove a disc from an MMB file")]
+    Remove {
+        #[arg(help = "Path to MMB file", required = true, value_parser = parse_absolute_path)]
+        mmb_path: PathBuf,
+
+        #[arg(help = "Slot number (0-510)", required = true, value_parser = parse_slot)]
+        slot: u16,
+    },
+
+    #[command(name = "lock", about = "Lock or unlock a disc in an MMB file")]
+    Lock {
+        #[arg(help = "Path to MMB file", required = true, value_parser = parse_absolute_path)]
+        mmb_path: PathBuf,
+
+        #[arg(help = "Slot number (0-510)", required = true, value_parser = parse_slot)]
+        slot: u16,
+
+        #[arg(
+            help = "Unlock disc instead of locking it",
+            long = "unlock",
+            short = 'u',
+            default_value_t = false
+        )]
+        unlock: bool,
+    },
 }
 
 fn parse_absolute_path(s: &str) -> StdResult<PathBuf, String> {
@@ -176,3 +602,55 @@ fn parse_disc_size(s: &str) -> StdResult<DiscSize, String> {
         .try_into()
         .map_err(|_| String::from("invalid disc size"))
 }
+
+fn parse_image_format(s: &str) -> StdResult<ImageFormat, String> {
+    s.parse().map_err(|_| String::from("invalid image format"))
+}
+
+fn parse_show_format(s: &str) -> StdResult<ShowFormat, String> {
+    s.parse().map_err(|_| String::from("invalid output format"))
+}
+
+fn parse_show_style(s: &str) -> StdResult<ShowStyle, String> {
+    s.parse().map_err(|_| String::from("invalid style"))
+}
+
+fn parse_boot_option(s: &str) -> StdResult<BootOption, String> {
+    s.parse().map_err(|_| String::from("invalid boot option"))
+}
+
+fn parse_dfs_path(s: &str) -> StdResult<DfsPath, String> {
+    s.parse().map_err(|_| String::from("invalid DFS file name"))
+}
+
+fn parse_side(s: &str) -> StdResult<Side, String> {
+    s.parse::<u8>()
+        .map_err(|_| String::from("invalid side"))?
+        .try_into()
+        .map_err(|_| String::from("invalid side"))
+}
+
+fn parse_address(s: &str) -> StdResult<Address, String> {
+    let digits = s
+        .strip_prefix('&')
+        .or_else(|| s.strip_prefix("0x"))
+        .unwrap_or(s);
+    u32::from_str_radix(digits, 16)
+        .map_err(|_| String::from("invalid address"))?
+        .try_into()
+        .map_err(|_| String::from("invalid address"))
+}
+
+fn parse_slot(s: &str) -> StdResult<u16, String> {
+    match s.parse::<u16>() {
+        Ok(slot) if slot < MMB_SLOT_COUNT => Ok(slot),
+        _ => Err(String::from("invalid slot number")),
+    }
+}
+
+fn parse_line_number(s: &str) -> StdResult<u16, String> {
+    match s.parse::<u16>() {
+        Ok(line_number) if line_number <= MAX_LINE_NUMBER => Ok(line_number),
+        _ => Err(String::from("invalid line number")),
+    }
+}
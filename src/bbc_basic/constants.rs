@@ -1,5 +1,6 @@
 use crate::line_ending::CR;
 use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
 use std::sync::LazyLock;
 
 pub const TOKEN_MASK: u8 = 0x80;
@@ -8,8 +9,63 @@ pub const END_MARKER: [u8; 2] = [CR, 0xff];
 
 pub const REM_TOKEN: u8 = 0xf4;
 
+pub const ELSE_TOKEN: u8 = 0x8b;
+
+pub const THEN_TOKEN: u8 = 0x8c;
+
 pub const LINE_NUMBER_TOKEN: u8 = 0x8d;
 
+// PTR, PAGE, TIME, LOMEM and HIMEM have one token when read, as in
+// PRINT TIME, and another one 0x40 higher when assigned to at the start of a
+// statement, as in TIME=0
+pub const PSEUDO_VARIABLE_TOKENS: RangeInclusive<u8> = 0x8f..=0x93;
+
+pub const PSEUDO_VARIABLE_STATEMENT_OFFSET: u8 = 0x40;
+
+pub const ERL_TOKEN: u8 = 0x9e;
+
+pub const EVAL_TOKEN: u8 = 0xa0;
+
+pub const FN_TOKEN: u8 = 0xa4;
+
+pub const TO_TOKEN: u8 = 0xb8;
+
+pub const DATA_TOKEN: u8 = 0xdc;
+
+pub const DEF_TOKEN: u8 = 0xdd;
+
+pub const DIM_TOKEN: u8 = 0xde;
+
+pub const ENDPROC_TOKEN: u8 = 0xe1;
+
+pub const FOR_TOKEN: u8 = 0xe3;
+
+pub const GOSUB_TOKEN: u8 = 0xe4;
+
+pub const GOTO_TOKEN: u8 = 0xe5;
+
+pub const IF_TOKEN: u8 = 0xe7;
+
+pub const INPUT_TOKEN: u8 = 0xe8;
+
+pub const LET_TOKEN: u8 = 0xe9;
+
+pub const LOCAL_TOKEN: u8 = 0xea;
+
+pub const NEXT_TOKEN: u8 = 0xed;
+
+pub const ON_TOKEN: u8 = 0xee;
+
+pub const PROC_TOKEN: u8 = 0xf2;
+
+pub const READ_TOKEN: u8 = 0xf3;
+
+pub const REPEAT_TOKEN: u8 = 0xf5;
+
+pub const RESTORE_TOKEN: u8 = 0xf7;
+
+pub const UNTIL_TOKEN: u8 = 0xfd;
+
 // Note: this lists all BBC BASIC V tokens even though we only
 // care about BBC BASIC II!
 pub const KEYWORD_TOKENS: [(&str, u8); 128] = [
@@ -25,8 +81,8 @@ pub const KEYWORD_TOKENS: [(&str, u8); 128] = [
     ("STEP", 0x88),
     ("SPC", 0x89),
     ("TAB(", 0x8a),
-    ("ELSE", 0x8b),
-    ("THEN", 0x8c),
+    ("ELSE", ELSE_TOKEN),
+    ("THEN", THEN_TOKEN),
     ("<line>", LINE_NUMBER_TOKEN), // Not really a keyword
     ("OPENIN", 0x8e),
     ("PTR", 0x8f),
@@ -44,13 +100,13 @@ pub const KEYWORD_TOKENS: [(&str, u8); 128] = [
     ("COS", 0x9b),
     ("COUNT", 0x9c),
     ("DEG", 0x9d),
-    ("ERL", 0x9e),
+    ("ERL", ERL_TOKEN),
     ("ERR", 0x9f),
-    ("EVAL", 0xa0),
+    ("EVAL", EVAL_TOKEN),
     ("EXP", 0xa1),
     ("EXT", 0xa2),
     ("FALSE", 0xa3),
-    ("FN", 0xa4),
+    ("FN", FN_TOKEN),
     ("GET", 0xa5),
     ("INKEY", 0xa6),
     ("INSTR(", 0xa7),
@@ -70,7 +126,7 @@ pub const KEYWORD_TOKENS: [(&str, u8); 128] = [
     ("SIN", 0xb5),
     ("SQR", 0xb6),
     ("TAN", 0xb7),
-    ("TO", 0xb8),
+    ("TO", TO_TOKEN),
     ("TRUE", 0xb9),
     ("USR", 0xba),
     ("VAL", 0xbb),
@@ -106,40 +162,40 @@ pub const KEYWORD_TOKENS: [(&str, u8); 128] = [
     ("CLOSE", 0xd9),
     ("CLG", 0xda),
     ("CLS", 0xdb),
-    ("DATA", 0xdc),
-    ("DEF", 0xdd),
-    ("DIM", 0xde),
+    ("DATA", DATA_TOKEN),
+    ("DEF", DEF_TOKEN),
+    ("DIM", DIM_TOKEN),
     ("DRAW", 0xdf),
     ("END", 0xe0),
-    ("ENDPROC", 0xe1),
+    ("ENDPROC", ENDPROC_TOKEN),
     ("ENVELOPE", 0xe2),
-    ("FOR", 0xe3),
-    ("GOSUB", 0xe4),
-    ("GOTO", 0xe5),
+    ("FOR", FOR_TOKEN),
+    ("GOSUB", GOSUB_TOKEN),
+    ("GOTO", GOTO_TOKEN),
     ("GCOL", 0xe6),
-    ("IF", 0xe7),
-    ("INPUT", 0xe8),
-    ("LET", 0xe9),
-    ("LOCAL", 0xea),
+    ("IF", IF_TOKEN),
+    ("INPUT", INPUT_TOKEN),
+    ("LET", LET_TOKEN),
+    ("LOCAL", LOCAL_TOKEN),
     ("MODE", 0xeb),
     ("MOVE", 0xec),
-    ("NEXT", 0xed),
-    ("ON", 0xee),
+    ("NEXT", NEXT_TOKEN),
+    ("ON", ON_TOKEN),
     ("VDU", 0xef),
     ("PLOT", 0xf0),
     ("PRINT", 0xf1),
-    ("PROC", 0xf2),
-    ("READ", 0xf3),
+    ("PROC", PROC_TOKEN),
+    ("READ", READ_TOKEN),
     ("REM", REM_TOKEN),
-    ("REPEAT", 0xf5),
+    ("REPEAT", REPEAT_TOKEN),
     ("REPORT", 0xf6),
-    ("RESTORE", 0xf7),
+    ("RESTORE", RESTORE_TOKEN),
     ("RETURN", 0xf8),
     ("RUN", 0xf9),
     ("STOP", 0xfa),
     ("COLOUR", 0xfb),
     ("TRACE", 0xfc),
-    ("UNTIL", 0xfd),
+    ("UNTIL", UNTIL_TOKEN),
     ("WIDTH", 0xfe),
     ("OSCLI", 0xff),
 ];
@@ -154,7 +210,7 @@ pub static KEYWORDS_BY_TOKEN: LazyLock<HashMap<u8, &str>> = LazyLock::new(|| {
         .collect()
 });
 
-const LINE_NUMBER_KEYWORDS: [&str; 4] = ["ELSE", "GOTO", "GOSUB", "THEN"];
+const LINE_NUMBER_KEYWORDS: [&str; 5] = ["ELSE", "GOTO", "GOSUB", "RESTORE", "THEN"];
 
 pub static LINE_NUMBER_TOKENS: LazyLock<HashSet<u8>> = LazyLock::new(|| {
     LINE_NUMBER_KEYWORDS
@@ -162,3 +218,29 @@ pub static LINE_NUMBER_TOKENS: LazyLock<HashSet<u8>> = LazyLock::new(|| {
         .into_iter()
         .collect()
 });
+
+// Keywords after which BASIC II tokenizes as though at the start of a
+// statement
+const STATEMENT_START_KEYWORDS: [&str; 4] = ["ELSE", "ERROR", "LET", "THEN"];
+
+pub static STATEMENT_START_TOKENS: LazyLock<HashSet<u8>> = LazyLock::new(|| {
+    STATEMENT_START_KEYWORDS
+        .map(|name| *KEYWORDS_BY_NAME.get(name).unwrap())
+        .into_iter()
+        .collect()
+});
+
+// Keywords after which BASIC II tokenizes as though in the middle of a
+// statement: keywords not listed here, such as REPEAT, leave that unchanged
+const MID_STATEMENT_KEYWORDS: [&str; 30] = [
+    "BPUT", "CALL", "CHAIN", "CLOSE", "COLOUR", "DIM", "DRAW", "ENVELOPE", "FOR", "GCOL", "GOSUB",
+    "GOTO", "IF", "INPUT", "LOCAL", "MODE", "MOVE", "NEXT", "ON", "OSCLI", "PLOT", "PRINT", "PROC",
+    "READ", "RESTORE", "SOUND", "TRACE", "UNTIL", "VDU", "WIDTH",
+];
+
+pub static MID_STATEMENT_TOKENS: LazyLock<HashSet<u8>> = LazyLock::new(|| {
+    MID_STATEMENT_KEYWORDS
+        .map(|name| *KEYWORDS_BY_NAME.get(name).unwrap())
+        .into_iter()
+        .collect()
+});
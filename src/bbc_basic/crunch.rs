@@ -0,0 +1,651 @@
+use crate::bbc_basic::{
+    DATA_TOKEN, DEF_TOKEN, ELSE_TOKEN, END_MARKER, ERL_TOKEN, EVAL_TOKEN, FN_TOKEN, GOSUB_TOKEN,
+    GOTO_TOKEN, IF_TOKEN, KEYWORDS_BY_NAME, KEYWORDS_BY_TOKEN, LINE_NUMBER_TOKEN, ON_TOKEN,
+    PROC_TOKEN, REM_TOKEN, RESTORE_TOKEN, THEN_TOKEN, TO_TOKEN, decode_line_number,
+    encode_line_number, is_token, split_lines,
+};
+use crate::line_ending::CR;
+use anyhow::{Result, bail};
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// Longest line content that fits in the one-byte line length
+const MAX_LINE_CONTENT: usize = 251;
+
+// Keywords that BASIC tokenizes even when followed directly by a letter or
+// digit, so that the space after them can go: others such as TIME or END are
+// left alone when followed by letters, e.g. TIMER is a variable
+const SPACE_FREE_KEYWORDS: [&str; 31] = [
+    "AND", "CALL", "CHAIN", "COLOUR", "DIM", "DIV", "DRAW", "ELSE", "ENVELOPE", "EOR", "FOR",
+    "GCOL", "GOSUB", "GOTO", "IF", "INPUT", "LOCAL", "MOD", "MODE", "MOVE", "NOT", "OR", "OSCLI",
+    "PLOT", "PRINT", "READ", "RESTORE", "SOUND", "STEP", "THEN", "TO",
+];
+
+static SPACE_FREE_TOKENS: LazyLock<HashSet<u8>> = LazyLock::new(|| {
+    SPACE_FREE_KEYWORDS
+        .map(|name| *KEYWORDS_BY_NAME.get(name).unwrap())
+        .into_iter()
+        .collect()
+});
+
+// Piece of a line: bytes inside strings, after REM or DATA, in * commands
+// and in assembler are never changed
+#[derive(Clone, Debug)]
+enum Part {
+    Byte(u8),
+    LineNumber(u16),
+    String(Vec<u8>),
+    Rem,
+    Data(Vec<u8>),
+    Star(Vec<u8>),
+    Assembler(Vec<u8>),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Name,
+    Number,
+    Quote,
+    Token(u8),
+    Other,
+}
+
+impl Part {
+    fn write_to(&self, output: &mut Vec<u8>) {
+        match self {
+            Self::Byte(b) => output.push(*b),
+            Self::LineNumber(line_number) => {
+                let (b0, b1, b2) = encode_line_number(*line_number);
+                output.extend([LINE_NUMBER_TOKEN, b0, b1, b2]);
+            }
+            Self::Rem => output.push(REM_TOKEN),
+            Self::String(bytes)
+            | Self::Data(bytes)
+            | Self::Star(bytes)
+            | Self::Assembler(bytes) => {
+                output.extend(bytes);
+            }
+        }
+    }
+
+    // How the start of the part reads when deciding whether a space before
+    // it can go
+    const fn leading_kind(&self) -> Kind {
+        match self {
+            Self::Byte(b) => byte_kind(*b),
+            Self::String(_) => Kind::Quote,
+            Self::Rem => Kind::Token(REM_TOKEN),
+            Self::Data(_) => Kind::Token(DATA_TOKEN),
+            Self::LineNumber(_) | Self::Star(_) | Self::Assembler(_) => Kind::Other,
+        }
+    }
+
+    const fn is_space(&self) -> bool {
+        matches!(self, Self::Byte(b' '))
+    }
+
+    const fn is_byte(&self, value: u8) -> bool {
+        matches!(self, Self::Byte(b) if *b == value)
+    }
+}
+
+const fn byte_kind(b: u8) -> Kind {
+    match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'_' | b'`' => Kind::Name,
+        b'0'..=b'9' | b'.' => Kind::Number,
+        b'"' => Kind::Quote,
+        _ if is_token(b) => Kind::Token(b),
+        _ => Kind::Other,
+    }
+}
+
+const fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'`'
+}
+
+struct Line {
+    line_number: u16,
+    parts: Vec<Part>,
+}
+
+impl Line {
+    fn contains(&self, token: u8) -> bool {
+        self.parts.iter().any(|part| part.is_byte(token))
+    }
+
+    fn has_assembler(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Assembler(_)))
+    }
+
+    // Statements after IF, ON, DEF, REM, DATA or a * command on the same
+    // line would change meaning if another line were appended
+    fn can_take_next(&self) -> bool {
+        !self.has_assembler()
+            && ![IF_TOKEN, ON_TOKEN, DEF_TOKEN]
+                .iter()
+                .any(|&token| self.contains(token))
+            && !self
+                .parts
+                .iter()
+                .any(|part| matches!(part, Part::Rem | Part::Data(_) | Part::Star(_)))
+    }
+
+    // DEF and DATA are only found at the start of a line
+    fn can_join_previous(&self) -> bool {
+        !self.has_assembler()
+            && !self.contains(DEF_TOKEN)
+            && !matches!(self.parts.first(), Some(Part::Data(_) | Part::Star(_)))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for part in &self.parts {
+            part.write_to(&mut bytes);
+        }
+        bytes
+    }
+}
+
+// Shrink a tokenized program without changing what it does: REM statements
+// and spaces BASIC does not need are removed and lines that are not the
+// target of GOTO, GOSUB, RESTORE etc. are appended to the line before where
+// possible. Variable, procedure and function names are optionally replaced
+// by the shortest names available
+pub fn crunch_program(bytes: &[u8], shorten_names: bool) -> Result<Vec<u8>> {
+    let mut in_assembler = false;
+    let mut lines = split_lines(bytes)?
+        .into_iter()
+        .map(|(line_number, content)| {
+            Ok(Line {
+                line_number,
+                parts: parse_parts(content, &mut in_assembler)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Computed GOTOs etc. may jump to any line, so no line can be dropped or
+    // merged with another
+    let targets = find_targets(&lines);
+
+    if shorten_names {
+        if lines.iter().any(|line| line.contains(EVAL_TOKEN)) {
+            bail!("cannot shorten names in a program that uses EVAL")
+        }
+        if lines.iter().any(Line::has_assembler) {
+            bail!("cannot shorten names in a program containing assembler")
+        }
+        shorten(&mut lines);
+    }
+
+    for line in &mut lines {
+        line.parts = crunch_parts(&line.parts);
+    }
+
+    let mut crunched: Vec<Line> = Vec::with_capacity(lines.len());
+    for line in lines {
+        if let Some(targets) = &targets
+            && !targets.contains(&line.line_number)
+        {
+            if line.parts.is_empty() {
+                continue;
+            }
+            if let Some(previous) = crunched.last_mut()
+                && previous.can_take_next()
+                && line.can_join_previous()
+                && previous.to_bytes().len() + 1 + line.to_bytes().len() <= MAX_LINE_CONTENT
+            {
+                if !previous.parts.is_empty() {
+                    previous.parts.push(Part::Byte(b':'));
+                }
+                previous.parts.extend(line.parts);
+                continue;
+            }
+        }
+        crunched.push(line);
+    }
+
+    let mut output = Vec::with_capacity(bytes.len());
+    for line in &crunched {
+        let content = line.to_bytes();
+        output.push(CR);
+        output.extend(line.line_number.to_be_bytes());
+        output.push(u8::try_from(content.len() + 4)?);
+        output.extend(content);
+    }
+    output.extend(END_MARKER);
+    Ok(output)
+}
+
+fn parse_parts(content: &[u8], in_assembler: &mut bool) -> Result<Vec<Part>> {
+    let mut parts = Vec::new();
+    let mut statement_start = true;
+    let mut index = 0;
+    while index < content.len() {
+        if *in_assembler {
+            let end = content[index..]
+                .iter()
+                .position(|&b| b == b']')
+                .map_or(content.len(), |i| {
+                    *in_assembler = false;
+                    index + i + 1
+                });
+            parts.push(Part::Assembler(content[index..end].to_vec()));
+            index = end;
+            continue;
+        }
+
+        let b = content[index];
+        match b {
+            b'"' => {
+                let end = content[index + 1..]
+                    .iter()
+                    .position(|&b| b == b'"')
+                    .map_or(content.len(), |i| index + i + 2);
+                parts.push(Part::String(content[index..end].to_vec()));
+                index = end;
+                statement_start = false;
+                continue;
+            }
+            b'[' => {
+                *in_assembler = true;
+                continue;
+            }
+            b'*' if statement_start => {
+                parts.push(Part::Star(content[index..].to_vec()));
+                break;
+            }
+            REM_TOKEN => {
+                parts.push(Part::Rem);
+                break;
+            }
+            DATA_TOKEN => {
+                parts.push(Part::Data(content[index..].to_vec()));
+                break;
+            }
+            LINE_NUMBER_TOKEN => {
+                let Some(&[b0, b1, b2]) = content.get(index + 1..index + 4) else {
+                    bail!("truncated line number")
+                };
+                parts.push(Part::LineNumber(decode_line_number(b0, b1, b2)));
+                index += 4;
+                statement_start = false;
+                continue;
+            }
+            // A star command may follow THEN or ELSE as well as start a statement
+            b':' | THEN_TOKEN | ELSE_TOKEN => statement_start = true,
+            b' ' => {}
+            _ => statement_start = false,
+        }
+        parts.push(Part::Byte(b));
+        index += 1;
+    }
+    Ok(parts)
+}
+
+// Lines referred to by line number, or None if some targets cannot be known:
+// GOTO, GOSUB or RESTORE followed by an expression, an ON ... GOTO list
+// whose entries were not tokenized or use of ERL
+fn find_targets(lines: &[Line]) -> Option<HashSet<u16>> {
+    let mut targets = HashSet::new();
+    for line in lines {
+        let parts = line
+            .parts
+            .iter()
+            .filter(|part| !part.is_space())
+            .collect::<Vec<_>>();
+        for (i, part) in parts.iter().enumerate() {
+            match part {
+                Part::LineNumber(line_number) => {
+                    targets.insert(*line_number);
+                    if parts.get(i + 1).is_some_and(|next| next.is_byte(b','))
+                        && !matches!(parts.get(i + 2), Some(Part::LineNumber(_)))
+                    {
+                        return None;
+                    }
+                }
+                Part::Byte(ERL_TOKEN) => return None,
+                Part::Byte(GOTO_TOKEN | GOSUB_TOKEN)
+                    if !matches!(parts.get(i + 1), Some(Part::LineNumber(_))) =>
+                {
+                    return None;
+                }
+                Part::Byte(RESTORE_TOKEN) => match parts.get(i + 1) {
+                    None | Some(Part::LineNumber(_) | Part::Byte(b':')) => {}
+                    Some(Part::Byte(b)) if KEYWORDS_BY_TOKEN.get(b) == Some(&"ELSE") => {}
+                    _ => return None,
+                },
+                _ => {}
+            }
+        }
+    }
+    Some(targets)
+}
+
+// Drop REM statements, empty statements and spaces that do not separate two
+// things BASIC would otherwise read as one
+fn crunch_parts(parts: &[Part]) -> Vec<Part> {
+    let mut output: Vec<Part> = Vec::with_capacity(parts.len());
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            Part::Byte(b' ') => {
+                let Some(previous) = output.last() else {
+                    continue;
+                };
+                let Some(next) = parts[i + 1..].iter().find(|part| !part.is_space()) else {
+                    continue;
+                };
+                if !output.last().is_some_and(Part::is_space) && keep_space(previous, next) {
+                    output.push(part.clone());
+                }
+            }
+            Part::Byte(b':') => {
+                if !output.is_empty() && !output.last().is_some_and(|part| part.is_byte(b':')) {
+                    output.push(part.clone());
+                }
+            }
+            Part::Rem => {
+                while output.last().is_some_and(Part::is_space) {
+                    output.pop();
+                }
+                match output.last() {
+                    None => {}
+                    Some(last) if last.is_byte(b':') => {
+                        output.pop();
+                    }
+                    // REM after THEN or ELSE is a statement in its own right
+                    _ => output.push(Part::Rem),
+                }
+            }
+            _ => output.push(part.clone()),
+        }
+    }
+
+    while output
+        .last()
+        .is_some_and(|part| part.is_space() || part.is_byte(b':'))
+    {
+        output.pop();
+    }
+    output
+}
+
+fn keep_space(previous: &Part, next: &Part) -> bool {
+    let previous_kind = match previous {
+        Part::Byte(b) => byte_kind(*b),
+        Part::String(_) => Kind::Quote,
+        Part::LineNumber(_) => Kind::Number,
+        _ => Kind::Other,
+    };
+    let next_kind = next.leading_kind();
+    let keyword_starts_with_e = |token: u8| {
+        KEYWORDS_BY_TOKEN
+            .get(&token)
+            .is_some_and(|k| k.starts_with('E'))
+    };
+
+    match (previous_kind, next_kind) {
+        // Adjacent strings would read as one containing a quote
+        (Kind::Quote, Kind::Quote)
+        // Names and numbers would run together
+        | (Kind::Name | Kind::Number, Kind::Name | Kind::Number)
+        // A name running into a keyword would no longer be tokenized as one
+        // if the program were listed and typed back in
+        | (Kind::Name, Kind::Token(_)) => true,
+        // 1E would start an exponent
+        (Kind::Number, Kind::Token(token)) => keyword_starts_with_e(token),
+        // TO followed by P reads as TOP
+        (Kind::Token(TO_TOKEN), Kind::Name) => next.is_byte(b'P'),
+        (Kind::Token(token), Kind::Name | Kind::Number | Kind::Token(_)) => {
+            !SPACE_FREE_TOKENS.contains(&token)
+        }
+        _ => false,
+    }
+}
+
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+enum Namespace {
+    Variable,
+    Procedure,
+    Function,
+}
+
+// Where a name occurs in a line's parts
+struct NameRef {
+    start: usize,
+    end: usize,
+    namespace: Namespace,
+    name: Vec<u8>,
+}
+
+fn find_names(parts: &[Part]) -> Vec<NameRef> {
+    let byte_at = |i: usize| match parts.get(i) {
+        Some(Part::Byte(b)) => Some(*b),
+        _ => None,
+    };
+
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < parts.len() {
+        let Some(b) = byte_at(i) else {
+            i += 1;
+            continue;
+        };
+
+        let namespace = match i.checked_sub(1).and_then(byte_at) {
+            Some(PROC_TOKEN) => Some(Namespace::Procedure),
+            Some(FN_TOKEN) => Some(Namespace::Function),
+            _ => None,
+        };
+
+        if (namespace.is_some() && is_name_byte(b)) || byte_kind(b) == Kind::Name {
+            let start = i;
+            while byte_at(i).is_some_and(is_name_byte) {
+                i += 1;
+            }
+            let name = (start..i).filter_map(byte_at).collect::<Vec<_>>();
+
+            // Resident integer variables A% to Z% keep their values across
+            // CHAIN and TO followed by P reads as TOP
+            let resident = namespace.is_none()
+                && name.len() == 1
+                && name[0].is_ascii_uppercase()
+                && byte_at(i) == Some(b'%');
+            let top = start > 0 && byte_at(start - 1) == Some(TO_TOKEN) && name[0] == b'P';
+            if !resident && !top {
+                names.push(NameRef {
+                    start,
+                    end: i,
+                    namespace: namespace.unwrap_or(Namespace::Variable),
+                    name,
+                });
+            }
+        } else if b.is_ascii_digit() || b == b'.' {
+            // Numbers, including exponents such as 1E-3
+            while byte_at(i).is_some_and(|b| b.is_ascii_digit() || b == b'.') {
+                i += 1;
+            }
+            if byte_at(i) == Some(b'E') {
+                let sign = usize::from(matches!(byte_at(i + 1), Some(b'+' | b'-')));
+                if byte_at(i + 1 + sign).is_some_and(|b| b.is_ascii_digit()) {
+                    i += 1 + sign;
+                    while byte_at(i).is_some_and(|b| b.is_ascii_digit()) {
+                        i += 1;
+                    }
+                }
+            }
+        } else if b == b'&' {
+            i += 1;
+            while byte_at(i).is_some_and(|b| b.is_ascii_hexdigit()) {
+                i += 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    names
+}
+
+// Lower-case names cannot clash with keywords or resident integer variables
+fn short_name(mut index: usize) -> Vec<u8> {
+    let mut name = Vec::new();
+    loop {
+        name.insert(0, b'a' + u8::try_from(index % 26).unwrap());
+        if index < 26 {
+            return name;
+        }
+        index = index / 26 - 1;
+    }
+}
+
+// Most frequently used names get the shortest replacements, with names
+// shared between differently suffixed variables such as A, A$ and A%(
+fn shorten(lines: &mut [Line]) {
+    let mut counts: HashMap<(Namespace, Vec<u8>), usize> = HashMap::new();
+    let mut kept = HashSet::new();
+    for line in lines.iter() {
+        for name_ref in find_names(&line.parts) {
+            *counts
+                .entry((name_ref.namespace, name_ref.name))
+                .or_default() += 1;
+        }
+        // Names that begin after TO with P are left alone
+        for (i, part) in line.parts.iter().enumerate() {
+            if part.is_byte(TO_TOKEN)
+                && let Some(Part::Byte(b'P')) = line.parts.get(i + 1)
+            {
+                let name = line.parts[i + 1..]
+                    .iter()
+                    .map_while(|part| match part {
+                        Part::Byte(b) if is_name_byte(*b) => Some(*b),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                kept.insert(name);
+            }
+        }
+    }
+
+    let mut by_count = counts.into_iter().collect::<Vec<_>>();
+    by_count.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.1.cmp(&b.0.1)));
+
+    // Replacements never clash with existing names, so a name is left alone
+    // when no shorter replacement is available
+    let mut taken = by_count
+        .iter()
+        .map(|(key, _)| key.clone())
+        .collect::<HashSet<_>>();
+    taken.extend(kept.iter().map(|name| (Namespace::Variable, name.clone())));
+
+    let mut replacements = HashMap::new();
+    let mut next = HashMap::new();
+    for ((namespace, name), _) in by_count {
+        if namespace == Namespace::Variable && kept.contains(&name) {
+            continue;
+        }
+        let index = next.entry(namespace).or_insert(0);
+        while taken.contains(&(namespace, short_name(*index))) {
+            *index += 1;
+        }
+        let replacement = short_name(*index);
+        if replacement.len() < name.len() {
+            *index += 1;
+            replacements.insert((namespace, name), replacement);
+        }
+    }
+
+    for line in lines {
+        let mut parts = Vec::with_capacity(line.parts.len());
+        let mut index = 0;
+        for name_ref in find_names(&line.parts) {
+            parts.extend(line.parts[index..name_ref.start].iter().cloned());
+            match replacements.get(&(name_ref.namespace, name_ref.name)) {
+                Some(replacement) => parts.extend(replacement.iter().map(|&b| Part::Byte(b))),
+                None => parts.extend(line.parts[name_ref.start..name_ref.end].iter().cloned()),
+            }
+            index = name_ref.end;
+        }
+        parts.extend(line.parts[index..].iter().cloned());
+        line.parts = parts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bbc_basic::{crunch_program, detokenize_source, tokenize_source};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    fn crunch_source(source: &str, shorten_names: bool) -> Result<String> {
+        let mut bytes = Vec::new();
+        tokenize_source(&mut bytes, source.as_bytes())?;
+        let crunched = crunch_program(&bytes, shorten_names)?;
+        assert!(crunched.len() <= bytes.len());
+        let mut output = Vec::new();
+        detokenize_source(&mut output, &crunched, false)?;
+        Ok(String::from_utf8(output)?)
+    }
+
+    #[rstest]
+    // REM lines go unless they are jump targets
+    #[case(
+        "   20X=1:GOTO30\n   30PRINTX\n",
+        "10 REM Title\n20 X = 1 : REM set X\n25 GOTO 30\n30 REM Target\n40 PRINT X\n",
+        false
+    )]
+    // Spaces separating names, numbers and keywords stay
+    #[case(
+        "   10FORI=1TON STEP2:A=B ANDC:PRINT\"A\" \"B\";TIME DIV2:NEXT\n",
+        "10 FOR I = 1 TO N STEP 2\n20   A = B AND C\n30   PRINT \"A\" \"B\"; TIME DIV 2\n40 NEXT\n",
+        false
+    )]
+    // No line after IF, DEF or DATA or before DEF or DATA is merged
+    #[case(
+        "   10IFX THENY=1\n   20PRINT:END\n   30DEF PROCa\n   40ENDPROC\n   50DATA 1,2\n   60READA\n",
+        "10 IF X THEN Y=1\n20 PRINT\n25 END\n30 DEF PROCa\n40 ENDPROC\n50 DATA 1,2\n60 READ A\n",
+        false
+    )]
+    // Computed GOTO stops lines being merged
+    #[case("   10X=20\n   20GOTOX\n", "10 X = 20\n20 GOTO X\n", false)]
+    // Names are shortened, most used first, leaving resident integers alone
+    #[case(
+        "   10b=1:a$=\"total\":PROCa(b,A%):PRINTFNa(a$)\n",
+        "10 total=1:name$=\"total\":PROCshow(total,A%):PRINT FNtwice(name$)\n",
+        true
+    )]
+    fn crunch(
+        #[case] expected: &str,
+        #[case] source: &str,
+        #[case] shorten_names: bool,
+    ) -> Result<()> {
+        assert_eq!(expected, crunch_source(source, shorten_names)?);
+        Ok(())
+    }
+
+    #[rstest]
+    // Star commands after THEN or ELSE are left alone
+    #[case(
+        "   10IFflag THEN*DISC\n   20IFflag THENPRINTELSE*SAVE flag 1900 +100\n",
+        "10 IF flag THEN *DISC\n20 IF flag THEN PRINT ELSE *SAVE flag 1900 +100\n",
+        false
+    )]
+    #[case(
+        "   10IFa THEN*DISC\n   20IFa THENPRINTELSE*SAVE flag 1900 +100\n",
+        "10 IF flag THEN *DISC\n20 IF flag THEN PRINT ELSE *SAVE flag 1900 +100\n",
+        true
+    )]
+    fn star_after_then_or_else(
+        #[case] expected: &str,
+        #[case] source: &str,
+        #[case] shorten_names: bool,
+    ) -> Result<()> {
+        assert_eq!(expected, crunch_source(source, shorten_names)?);
+        Ok(())
+    }
+
+    #[test]
+    fn shorten_names_with_eval() {
+        assert!(crunch_source("10 X=EVAL(\"1\")\n", true).is_err());
+    }
+}
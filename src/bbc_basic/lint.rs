@@ -0,0 +1,318 @@
+use crate::bbc_basic::{
+    DATA_TOKEN, DEF_TOKEN, ELSE_TOKEN, ENDPROC_TOKEN, FN_TOKEN, FOR_TOKEN, KEYWORDS_BY_TOKEN,
+    LINE_NUMBER_TOKEN, LINE_NUMBER_TOKENS, MAX_LINE_NUMBER, NEXT_TOKEN, PROC_TOKEN, REM_TOKEN,
+    REPEAT_TOKEN, THEN_TOKEN, UNTIL_TOKEN, decode_line_number,
+};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::mem::take;
+
+// Longest line BASIC can store, including the CR, line number and length
+const MAX_LINE_BYTES: usize = 255;
+
+#[derive(Debug, PartialEq)]
+pub struct LintIssue {
+    pub line_number: u16,
+    pub message: String,
+}
+
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "line {line_number}: {message}",
+            line_number = self.line_number,
+            message = self.message
+        )
+    }
+}
+
+// A DEF PROC or DEF FN and the lines following it, or the main program
+#[derive(Default)]
+struct Block {
+    def: Option<(u16, String)>,
+    has_endproc: bool,
+    fors: Vec<u16>,
+    repeats: Vec<u16>,
+}
+
+// Check the lines of a tokenized program for mistakes BASIC only reports
+// when it runs into them
+pub fn lint_program(lines: &[(u16, &[u8])]) -> Vec<LintIssue> {
+    let mut linter = Linter {
+        line_numbers: lines.iter().map(|(line_number, _)| *line_number).collect(),
+        ..Linter::default()
+    };
+
+    let mut previous = None;
+    for (line_number, content) in lines {
+        let line_number = *line_number;
+        if line_number > MAX_LINE_NUMBER {
+            linter.issue(
+                line_number,
+                format!("line number is greater than {MAX_LINE_NUMBER}"),
+            );
+        }
+        if let Some(previous) = previous
+            && line_number <= previous
+        {
+            linter.issue(
+                line_number,
+                format!("line number is not greater than that of the line before ({previous})"),
+            );
+        }
+        previous = Some(line_number);
+
+        let len = content.len() + 4;
+        if len > MAX_LINE_BYTES {
+            linter.issue(
+                line_number,
+                format!("line is {len} bytes long: the maximum is {MAX_LINE_BYTES}"),
+            );
+        }
+
+        linter.lint_line(line_number, content);
+    }
+    linter.close_block();
+
+    for (line_number, name) in take(&mut linter.calls) {
+        if !linter.defined.contains(&name) {
+            linter.issue(line_number, format!("{name} has no DEF"));
+        }
+    }
+
+    let mut issues = linter.issues;
+    issues.sort_by_key(|issue| issue.line_number);
+    issues
+}
+
+#[derive(Default)]
+struct Linter {
+    line_numbers: HashSet<u16>,
+    // Names of procedures and functions defined anywhere in the program
+    defined: HashSet<String>,
+    calls: Vec<(u16, String)>,
+    block: Block,
+    in_assembler: bool,
+    issues: Vec<LintIssue>,
+}
+
+impl Linter {
+    fn issue(&mut self, line_number: u16, message: String) {
+        self.issues.push(LintIssue {
+            line_number,
+            message,
+        });
+    }
+
+    fn lint_line(&mut self, line_number: u16, content: &[u8]) {
+        let mut in_string = false;
+        let mut statement_start = true;
+        let mut keyword = None;
+        let mut index = 0;
+        while index < content.len() {
+            let b = content[index];
+            index += 1;
+
+            if self.in_assembler {
+                self.in_assembler = b != b']';
+                continue;
+            }
+            if in_string {
+                in_string = b != b'"';
+                continue;
+            }
+
+            match b {
+                b'"' => in_string = true,
+                b'[' => self.in_assembler = true,
+                b':' => {
+                    statement_start = true;
+                    keyword = None;
+                    continue;
+                }
+                b' ' => continue,
+                b'*' if statement_start => break,
+                REM_TOKEN | DATA_TOKEN => break,
+                LINE_NUMBER_TOKEN => {
+                    let Some(&[b0, b1, b2]) = content.get(index..index + 3) else {
+                        self.issue(line_number, String::from("truncated line number"));
+                        break;
+                    };
+                    index += 3;
+                    let target = decode_line_number(b0, b1, b2);
+                    if !self.line_numbers.contains(&target) {
+                        self.issue(
+                            line_number,
+                            format!(
+                                "{keyword} {target} refers to a line that does not exist",
+                                keyword = keyword.unwrap_or("line")
+                            ),
+                        );
+                    }
+                }
+                // Another program may have left later entries of an ON GOTO
+                // list as plain digits, which renumbering would not update
+                b'0'..=b'9' if keyword.is_some_and(|k| k != "DEF") => {
+                    let start = index - 1;
+                    while content.get(index).is_some_and(u8::is_ascii_digit) {
+                        index += 1;
+                    }
+                    self.issue(
+                        line_number,
+                        format!(
+                            "{keyword} {target} is not stored as a line number",
+                            keyword = keyword.unwrap_or("line"),
+                            target = String::from_utf8_lossy(&content[start..index])
+                        ),
+                    );
+                }
+                PROC_TOKEN | FN_TOKEN => {
+                    let start = index;
+                    while content
+                        .get(index)
+                        .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_' || *b == b'`')
+                    {
+                        index += 1;
+                    }
+                    let name = format!(
+                        "{keyword}{name}",
+                        keyword = if b == PROC_TOKEN { "PROC" } else { "FN" },
+                        name = String::from_utf8_lossy(&content[start..index])
+                    );
+                    if keyword == Some("DEF") {
+                        self.close_block();
+                        self.block.def = Some((line_number, name.clone()));
+                        self.defined.insert(name);
+                    } else {
+                        self.calls.push((line_number, name));
+                    }
+                }
+                ENDPROC_TOKEN => self.block.has_endproc = true,
+                FOR_TOKEN => self.block.fors.push(line_number),
+                NEXT_TOKEN => self.close_fors(line_number, &content[index..]),
+                REPEAT_TOKEN => self.block.repeats.push(line_number),
+                UNTIL_TOKEN if self.block.repeats.pop().is_none() => {
+                    self.issue(line_number, String::from("UNTIL without REPEAT"));
+                }
+                _ => {}
+            }
+
+            if LINE_NUMBER_TOKENS.contains(&b) || b == DEF_TOKEN {
+                keyword = KEYWORDS_BY_TOKEN.get(&b).copied();
+            } else if b != LINE_NUMBER_TOKEN && b != b',' {
+                keyword = None;
+            }
+            // A star command may follow THEN or ELSE as well as start a statement
+            statement_start = b == THEN_TOKEN || b == ELSE_TOKEN;
+        }
+
+        if in_string {
+            self.issue(line_number, String::from("unterminated string"));
+        }
+    }
+
+    // NEXT I,J closes two loops
+    fn close_fors(&mut self, line_number: u16, rest: &[u8]) {
+        let mut count = 1;
+        for &b in rest {
+            match b {
+                b',' => count += 1,
+                b':' => break,
+                _ if b >= 0x80 => break,
+                _ => {}
+            }
+        }
+        for _ in 0..count {
+            if self.block.fors.pop().is_none() {
+                self.issue(line_number, String::from("NEXT without FOR"));
+                break;
+            }
+        }
+    }
+
+    fn close_block(&mut self) {
+        let block = take(&mut self.block);
+        if let Some((line_number, name)) = &block.def
+            && name.starts_with("PROC")
+            && !block.has_endproc
+        {
+            self.issue(*line_number, format!("DEF {name} has no ENDPROC"));
+        }
+        for &line_number in &block.fors {
+            self.issue(line_number, String::from("FOR without NEXT"));
+        }
+        for &line_number in &block.repeats {
+            self.issue(line_number, String::from("REPEAT without UNTIL"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bbc_basic::{lint_program, tokenize_lines};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        Vec::new(),
+        "10 FOR I=1 TO 2:PRINT \"A\":NEXT\n20 GOSUB 30:END\n30 PROCa:RETURN\n40 DEF PROCa\n50 REPEAT UNTIL TRUE:ENDPROC\n"
+    )]
+    #[case(vec!["line 10: GOTO 30 refers to a line that does not exist"], "10 GOTO 30\n20 END\n")]
+    #[case(vec!["line 20: THEN 5 refers to a line that does not exist"], "10 END\n20 IF X THEN 5\n")]
+    #[case(vec!["line 10: GOTO 999 refers to a line that does not exist"], "10 ON X GOTO 10,999\n20 END\n")]
+    #[case(vec!["line 10: GOSUB 5 refers to a line that does not exist"], "10 ON X GOSUB 20, 5:PRINT 7\n20 RETURN\n")]
+    #[case(vec!["line 10: unterminated string"], "10 PRINT \"A\n")]
+    #[case(vec!["line 10: PROCmissing has no DEF", "line 10: FNother has no DEF"], "10 PROCmissing:X=FNother(1)\n")]
+    #[case(vec!["line 20: DEF PROCa has no ENDPROC"], "10 PROCa:END\n20 DEF PROCa\n30 PRINT\n40 DEF FNb=1\n")]
+    #[case(vec!["line 10: FOR without NEXT", "line 20: NEXT without FOR"], "10 FOR I=1 TO 2\n20 DEF PROCa:NEXT:ENDPROC\n")]
+    #[case(vec!["line 10: NEXT without FOR"], "10 FOR I=1 TO 2:FOR J=1 TO 2:NEXT J,I,K\n")]
+    #[case(vec!["line 10: REPEAT without UNTIL", "line 30: UNTIL without REPEAT"], "10 REPEAT\n20 DEF FNa\n30 UNTIL FALSE\n40 =1\n")]
+    #[case(vec!["line 10: line number is not greater than that of the line before (20)"], "20 END\n10 END\n")]
+    #[case(Vec::new(), "10 REM \"GOTO 1\n20 DATA \"A\n")]
+    #[case(
+        Vec::new(),
+        "10 IF X THEN *KEY0 \"LIST|M\n20 IF X THEN PRINT ELSE *KEY1 \"RUN|M\n"
+    )]
+    fn lint(#[case] expected: Vec<&str>, #[case] source: &str) -> Result<()> {
+        let lines = tokenize_lines(source.as_bytes())?;
+        let lines = lines
+            .iter()
+            .map(|(line_number, content)| (*line_number, content.as_slice()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expected,
+            lint_program(&lines)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn untokenized_line_number() {
+        // ON X GOTO 10,20 with only the first entry tokenized
+        let content = [
+            0xee, 0x20, 0x58, 0x20, 0xe5, 0x20, 0x8d, 0x54, 0x4a, 0x40, 0x2c, 0x32, 0x30,
+        ];
+        let issues = lint_program(&[(10, content.as_slice()), (20, &[0xe0])]);
+        assert_eq!(
+            vec!["line 10: GOTO 20 is not stored as a line number"],
+            issues.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn long_line() -> Result<()> {
+        let source = format!("10 PRINT \"{text}\"\n", text = "A".repeat(250));
+        let lines = tokenize_lines(source.as_bytes())?;
+        let issues = lint_program(&[(lines[0].0, lines[0].1.as_slice())]);
+        assert_eq!(
+            vec!["line 10: line is 259 bytes long: the maximum is 255"],
+            issues.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
@@ -1,13 +1,21 @@
 mod constants;
+mod crunch;
 mod detokenize;
 mod line_number;
+mod lint;
+mod renumber;
 mod token_generator;
 mod tokenize;
 mod util;
+mod xref;
 
 pub use constants::*;
+pub use crunch::*;
 pub use detokenize::*;
 pub use line_number::*;
+pub use lint::*;
+pub use renumber::*;
 pub use token_generator::*;
 pub use tokenize::*;
 pub use util::*;
+pub use xref::*;
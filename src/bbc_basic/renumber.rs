@@ -0,0 +1,151 @@
+use crate::bbc_basic::{
+    DATA_TOKEN, END_MARKER, LINE_NUMBER_TOKEN, REM_TOKEN, decode_line_number, encode_line_number,
+    split_lines,
+};
+use crate::line_ending::CR;
+use anyhow::{Result, anyhow, bail};
+use std::collections::HashMap;
+
+pub const MAX_LINE_NUMBER: u16 = 32767;
+
+// Renumber lines from line number `from` onwards starting at `start` in
+// increments of `step`, rewriting every line number reference (after GOTO,
+// GOSUB, THEN, ELSE, RESTORE etc.) to match
+pub fn renumber_program(bytes: &[u8], start: u16, step: u16, from: u16) -> Result<Vec<u8>> {
+    if step == 0 {
+        bail!("step must not be zero")
+    }
+
+    let lines = split_lines(bytes)?;
+
+    let mut new_numbers = HashMap::with_capacity(lines.len());
+    let mut previous = None;
+    let mut next = Some(start);
+    for (line_number, _) in &lines {
+        let new_number = if *line_number >= from {
+            let Some(new_number) = next.filter(|&n| n <= MAX_LINE_NUMBER) else {
+                bail!("line numbers would exceed {MAX_LINE_NUMBER}")
+            };
+            next = new_number.checked_add(step);
+            new_number
+        } else {
+            *line_number
+        };
+        if previous.is_some_and(|p| new_number <= p) {
+            bail!("renumbering would put line {line_number} out of order")
+        }
+        previous = Some(new_number);
+        new_numbers.insert(*line_number, new_number);
+    }
+
+    let mut output = Vec::with_capacity(bytes.len());
+    for (line_number, content) in &lines {
+        let new_number = new_numbers[line_number];
+        output.push(CR);
+        output.extend(new_number.to_be_bytes());
+        output.push(u8::try_from(content.len() + 4)?);
+        output.extend(
+            renumber_references(content, &new_numbers)
+                .map_err(|e| anyhow!("{e} at line {line_number}"))?,
+        );
+    }
+    output.extend(END_MARKER);
+    Ok(output)
+}
+
+// Line number tokens do not appear in strings, comments or DATA statements
+// and so those are copied unchanged
+fn renumber_references(content: &[u8], new_numbers: &HashMap<u16, u16>) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(content.len());
+    let mut in_string = false;
+    let mut index = 0;
+    while index < content.len() {
+        let b = content[index];
+        match b {
+            b'"' => in_string = !in_string,
+            REM_TOKEN | DATA_TOKEN if !in_string => {
+                output.extend(&content[index..]);
+                break;
+            }
+            LINE_NUMBER_TOKEN if !in_string => {
+                let Some(&[b0, b1, b2]) = content.get(index + 1..index + 4) else {
+                    bail!("truncated line number")
+                };
+                let line_number = decode_line_number(b0, b1, b2);
+                let Some(&new_number) = new_numbers.get(&line_number) else {
+                    bail!("reference to missing line {line_number}")
+                };
+                let (b0, b1, b2) = encode_line_number(new_number);
+                output.extend([LINE_NUMBER_TOKEN, b0, b1, b2]);
+                index += 4;
+                continue;
+            }
+            _ => {}
+        }
+        output.push(b);
+        index += 1;
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bbc_basic::{detokenize_source, renumber_program, tokenize_source};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    fn renumber(source: &str, start: u16, step: u16, from: u16) -> Result<String> {
+        let mut bytes = Vec::new();
+        tokenize_source(&mut bytes, source.as_bytes())?;
+        let bytes = renumber_program(&bytes, start, step, from)?;
+        let mut output = Vec::new();
+        detokenize_source(&mut output, &bytes, false)?;
+        Ok(String::from_utf8(output)?)
+    }
+
+    #[rstest]
+    #[case(
+        "  100 PRINT \"GOTO 1\"\n  110 GOTO 120\n  120 IF X THEN 100 ELSE 110\n  130 RESTORE 130:DATA 1\n",
+        "5 PRINT \"GOTO 1\"\n7 GOTO 9\n9 IF X THEN 5 ELSE 7\n11 RESTORE 11:DATA 1\n",
+        100,
+        10,
+        0
+    )]
+    #[case(
+        "    1 GOSUB 20\n   20 IF X GOTO 1 ELSE 24:REM GOTO\n   22 RETURN\n   24 END\n",
+        "1 GOSUB 5\n5 IF X GOTO 1 ELSE 7:REM GOTO\n6 RETURN\n7 END\n",
+        20,
+        2,
+        2
+    )]
+    #[case(
+        "  100 ON A% GOTO 120,130, 140\n  110 ON B% GOSUB 140,120:PRINT 130\n  120 END\n  130 END\n  140 RETURN\n",
+        "10 ON A% GOTO 70,80, 90\n60 ON B% GOSUB 90,70:PRINT 130\n70 END\n80 END\n90 RETURN\n",
+        100,
+        10,
+        0
+    )]
+    fn renumber_references(
+        #[case] expected: &str,
+        #[case] source: &str,
+        #[case] start: u16,
+        #[case] step: u16,
+        #[case] from: u16,
+    ) -> Result<()> {
+        assert_eq!(expected, renumber(source, start, step, from)?);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("10 GOTO 30\n20 END\n", 10, 10, 0)]
+    #[case("10 PRINT\n20 END\n", 5, 10, 20)]
+    #[case("10 PRINT\n20 END\n", 32760, 10, 0)]
+    fn renumber_fails(
+        #[case] source: &str,
+        #[case] start: u16,
+        #[case] step: u16,
+        #[case] from: u16,
+    ) {
+        assert!(renumber(source, start, step, from).is_err());
+    }
+}
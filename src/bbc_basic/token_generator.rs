@@ -11,12 +11,14 @@ pub struct TokenGenerator<'a> {
     iter: usize,
     output: Vec<u8>,
     state: TokenGeneratorState,
+    statement_start: bool,
 }
 
 impl<'a> TokenGenerator<'a> {
     pub const fn new(bytes: &'a [u8]) -> Self {
         Self {
             state: TokenGeneratorState::Other,
+            statement_start: true,
             bytes,
             len: bytes.len(),
             iter: 0,
@@ -32,6 +34,14 @@ impl<'a> TokenGenerator<'a> {
         self.state = value;
     }
 
+    pub const fn statement_start(&self) -> bool {
+        self.statement_start
+    }
+
+    pub const fn set_statement_start(&mut self, value: bool) {
+        self.statement_start = value;
+    }
+
     pub fn drain_output(self) -> Vec<u8> {
         self.output
     }
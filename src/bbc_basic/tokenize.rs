@@ -1,32 +1,36 @@
 use crate::bbc_basic::{
-    END_MARKER, KEYWORDS_BY_NAME, LINE_NUMBER_TOKEN, LINE_NUMBER_TOKENS, REM_TOKEN, TokenGenerator,
-    encode_line_number,
+    END_MARKER, KEYWORDS_BY_NAME, LINE_NUMBER_TOKEN, LINE_NUMBER_TOKENS, MID_STATEMENT_TOKENS,
+    PSEUDO_VARIABLE_STATEMENT_OFFSET, PSEUDO_VARIABLE_TOKENS, REM_TOKEN, STATEMENT_START_TOKENS,
+    TokenGenerator, encode_line_number,
 };
 use crate::line_ending::LineEnding;
 use anyhow::{Result, anyhow, bail};
 use std::io::Write;
 
 pub fn tokenize_source<W: Write>(mut writer: W, bytes: &[u8]) -> Result<()> {
-    // If we can't guess the line ending type, assume LF.
-    let line_ending = LineEnding::guess(bytes).unwrap_or(LineEnding::Lf);
-
-    for line in line_ending.lines(bytes) {
-        let line = line?;
-        tokenize_line(&mut writer, line)?;
+    for (line_number, tokens) in tokenize_lines(bytes)? {
+        let line_len = u8::try_from(tokens.len() + 4)?;
+        writer.write_all(&[0x0d])?;
+        writer.write_all(&[(line_number >> 8) as u8, (line_number & 0xff) as u8])?;
+        writer.write_all(&[line_len])?;
+        writer.write_all(&tokens)?;
     }
     writer.write_all(&END_MARKER)?;
     Ok(())
 }
 
-fn tokenize_line<W: Write>(mut writer: W, bytes: &[u8]) -> Result<()> {
-    let (line_number, bytes) = parse_line_number(bytes)?;
-    let tokens = tokenize_content(bytes)?;
-    let line_len = u8::try_from(tokens.len() + 4)?;
-    writer.write_all(&[0x0d])?;
-    writer.write_all(&[(line_number >> 8) as u8, (line_number & 0xff) as u8])?;
-    writer.write_all(&[line_len])?;
-    writer.write_all(&tokens)?;
-    Ok(())
+// Line numbers and tokenized content of each line of source, which may be
+// too long to store
+pub fn tokenize_lines(bytes: &[u8]) -> Result<Vec<(u16, Vec<u8>)>> {
+    // If we can't guess the line ending type, assume LF.
+    let line_ending = LineEnding::guess(bytes).unwrap_or(LineEnding::Lf);
+
+    let mut lines = Vec::new();
+    for line in line_ending.lines(bytes) {
+        let (line_number, bytes) = parse_line_number(line?)?;
+        lines.push((line_number, tokenize_content(bytes)?));
+    }
+    Ok(lines)
 }
 
 fn parse_line_number(bytes: &[u8]) -> Result<(u16, &[u8])> {
@@ -68,6 +72,14 @@ fn process_byte(generator: &mut TokenGenerator<'_>, byte: u8) -> Result<(), anyh
     use crate::bbc_basic::TokenGeneratorState::{Comment, LineNumber, Other};
 
     let ch = byte as char;
+
+    // Line numbers continue through a list such as ON X GOTO 10,20,30 and
+    // anything but digits, spaces and commas ends it so that THEN and ELSE
+    // can be followed by statements
+    if matches!(generator.state(), LineNumber) && !matches!(ch, '0'..='9' | ' ' | ',') {
+        generator.set_state(Other);
+    }
+
     match (generator.state(), ch) {
         (Other, '"') => {
             generator.push_next_assert();
@@ -78,6 +90,7 @@ fn process_byte(generator: &mut TokenGenerator<'_>, byte: u8) -> Result<(), anyh
                     break;
                 }
             }
+            generator.set_statement_start(false);
         }
         (Comment | LineNumber, '0'..='9') => {
             let line_number = read_line_number(generator)?;
@@ -86,9 +99,7 @@ fn process_byte(generator: &mut TokenGenerator<'_>, byte: u8) -> Result<(), anyh
             generator.push(byte0);
             generator.push(byte1);
             generator.push(byte2);
-            if matches!(generator.state(), LineNumber) {
-                generator.set_state(Other);
-            }
+            generator.set_statement_start(false);
         }
         (Other, '0'..='9') => {
             generator.push_next_assert();
@@ -99,6 +110,7 @@ fn process_byte(generator: &mut TokenGenerator<'_>, byte: u8) -> Result<(), anyh
                 }
                 generator.push_next_assert();
             }
+            generator.set_statement_start(false);
         }
         (Other, 'A'..='Z' | 'a'..='z') => {
             struct TokenRun {
@@ -146,18 +158,7 @@ fn process_byte(generator: &mut TokenGenerator<'_>, byte: u8) -> Result<(), anyh
             }
 
             for run in runs {
-                match generator.state() {
-                    Comment => {}
-                    LineNumber => generator.set_state(Other),
-                    Other => {
-                        if run.token == REM_TOKEN {
-                            generator.set_state(Comment);
-                        } else if LINE_NUMBER_TOKENS.contains(&run.token) {
-                            generator.set_state(LineNumber);
-                        }
-                    }
-                }
-                generator.push(run.token);
+                push_token(generator, run.token);
             }
 
             let remainder = &s[start..];
@@ -165,13 +166,49 @@ fn process_byte(generator: &mut TokenGenerator<'_>, byte: u8) -> Result<(), anyh
                 for c in remainder.chars() {
                     generator.push(c as u8);
                 }
+                generator.set_statement_start(false);
             }
         }
-        _ => generator.push_next_assert(),
+        _ => {
+            if ch != ' ' && ch != ',' {
+                generator.set_statement_start(ch == ':');
+            }
+            generator.push_next_assert();
+        }
     }
     Ok(())
 }
 
+fn push_token(generator: &mut TokenGenerator<'_>, token: u8) {
+    use crate::bbc_basic::TokenGeneratorState::{Comment, LineNumber, Other};
+
+    match generator.state() {
+        Comment => {}
+        LineNumber => generator.set_state(Other),
+        Other => {
+            if token == REM_TOKEN {
+                generator.set_state(Comment);
+            } else if LINE_NUMBER_TOKENS.contains(&token) {
+                generator.set_state(LineNumber);
+            }
+        }
+    }
+
+    // Pseudo-variables at the start of a statement are being assigned to and
+    // take the statement form of the token
+    if PSEUDO_VARIABLE_TOKENS.contains(&token) && generator.statement_start() {
+        generator.push(token + PSEUDO_VARIABLE_STATEMENT_OFFSET);
+    } else {
+        generator.push(token);
+    }
+
+    if STATEMENT_START_TOKENS.contains(&token) {
+        generator.set_statement_start(true);
+    } else if MID_STATEMENT_TOKENS.contains(&token) || PSEUDO_VARIABLE_TOKENS.contains(&token) {
+        generator.set_statement_start(false);
+    }
+}
+
 fn read_line_number(generator: &mut TokenGenerator<'_>) -> Result<u16> {
     let mut line_number = u16::from(generator.next_assert() - b'0');
     while let Some(byte) = generator.peek() {
@@ -190,8 +227,16 @@ fn read_line_number(generator: &mut TokenGenerator<'_>) -> Result<u16> {
     Ok(line_number)
 }
 
+// Pseudo-variables are found as their function tokens
 fn find_token(word: &str) -> Option<u8> {
-    KEYWORDS_BY_NAME.get(word).copied()
+    KEYWORDS_BY_NAME.get(word).map(|&token| {
+        let function_token = token.wrapping_sub(PSEUDO_VARIABLE_STATEMENT_OFFSET);
+        if PSEUDO_VARIABLE_TOKENS.contains(&function_token) {
+            function_token
+        } else {
+            token
+        }
+    })
 }
 
 #[cfg(test)]
@@ -315,11 +360,56 @@ mod tests {
         "   50 CHAIN \"B.ELITE\"\n\r"
     );
 
+    // PTR, PAGE, TIME, LOMEM and HIMEM take the statement form of the token
+    // only at the start of a statement, including after THEN and ELSE.
+    // PROG5 and PROG6 are assembled by hand from the BASIC II token table
+    // and its rule that a statement starts after ":", THEN, ELSE and ON
+    // ERROR, not saved from a machine
+    const PROG5: [u8; 98] = [
+        0x0d, 0x00, 0x0a, 0x0b, 0xd1, 0x3d, 0x30, 0x3a, 0xf1, 0x20, 0x91, 0x0d, 0x00, 0x14, 0x1e,
+        0xe7, 0x20, 0x90, 0x3e, 0x26, 0x45, 0x30, 0x30, 0x20, 0x8c, 0x20, 0xd0, 0x3d, 0x26, 0x31,
+        0x39, 0x30, 0x30, 0x20, 0x8b, 0x20, 0xd3, 0x3d, 0x93, 0x2d, 0x31, 0x0d, 0x00, 0x1e, 0x0f,
+        0xcf, 0x23, 0x46, 0x25, 0x3d, 0x8f, 0x23, 0x46, 0x25, 0x2b, 0x31, 0x0d, 0x00, 0x28, 0x13,
+        0xf5, 0x20, 0xd1, 0x3d, 0x91, 0x2b, 0x31, 0x3a, 0xfd, 0x20, 0x91, 0x3e, 0x31, 0x30, 0x30,
+        0x0d, 0x00, 0x32, 0x0c, 0xd2, 0x3d, 0x92, 0x3a, 0x20, 0x58, 0x3d, 0x93, 0x0d, 0x00, 0x3c,
+        0x09, 0xf1, 0x20, 0x90, 0x2c, 0x91, 0x0d, 0xff,
+    ];
+
+    const PROG5_STR: &str = concat!(
+        "   10TIME=0:PRINT TIME\n\r",
+        "   20IF PAGE>&E00 THEN PAGE=&1900 ELSE HIMEM=HIMEM-1\n\r",
+        "   30PTR#F%=PTR#F%+1\n\r",
+        "   40REPEAT TIME=TIME+1:UNTIL TIME>100\n\r",
+        "   50LOMEM=LOMEM: X=HIMEM\n\r",
+        "   60PRINT PAGE,TIME\n\r"
+    );
+
+    const PROG6: [u8; 91] = [
+        0x0d, 0x00, 0x0a, 0x0f, 0xee, 0x20, 0x85, 0x20, 0xd3, 0x3d, 0x90, 0x3a, 0xf1, 0x20, 0x91,
+        0x0d, 0x00, 0x14, 0x10, 0xee, 0x20, 0x85, 0x20, 0xf1, 0x20, 0x8f, 0x23, 0x46, 0x25, 0x3a,
+        0xe0, 0x0d, 0x00, 0x1e, 0x13, 0xe7, 0x20, 0x91, 0x3e, 0x90, 0x20, 0xf1, 0x20, 0x92, 0x20,
+        0x8b, 0x20, 0xd1, 0x3d, 0x92, 0x0d, 0x00, 0x28, 0x0c, 0xfd, 0x20, 0x8f, 0x23, 0x46, 0x25,
+        0x3d, 0x93, 0x0d, 0x00, 0x32, 0x0b, 0x41, 0x3d, 0x91, 0x3a, 0xd0, 0x3d, 0x41, 0x0d, 0x00,
+        0x3c, 0x10, 0xe7, 0x20, 0x41, 0x20, 0x8c, 0x20, 0xcf, 0x23, 0x46, 0x25, 0x3d, 0x30, 0x0d,
+        0xff,
+    ];
+
+    const PROG6_STR: &str = concat!(
+        "   10ON ERROR HIMEM=PAGE:PRINT TIME\n\r",
+        "   20ON ERROR PRINT PTR#F%:END\n\r",
+        "   30IF TIME>PAGE PRINT LOMEM ELSE TIME=LOMEM\n\r",
+        "   40UNTIL PTR#F%=HIMEM\n\r",
+        "   50A=TIME:PAGE=A\n\r",
+        "   60IF A THEN PTR#F%=0\n\r"
+    );
+
     #[rstest]
     #[case(PROG1_STR, &PROG1)]
     #[case(PROG2_STR, &PROG2)]
     #[case(PROG3_STR, &PROG3)]
     #[case(PROG4_STR, &PROG4)]
+    #[case(PROG5_STR, &PROG5)]
+    #[case(PROG6_STR, &PROG6)]
     fn detokenize(#[case] expected_source: &str, #[case] input_token_bytes: &[u8]) -> Result<()> {
         let expected_source_bytes = get_source_bytes(expected_source);
         let mut source_bytes = Vec::new();
@@ -334,6 +424,8 @@ mod tests {
     #[case(&PROG2, PROG2_STR)]
     #[case(&PROG3, PROG3_STR)]
     #[case(&PROG4, PROG4_STR)]
+    #[case(&PROG5, PROG5_STR)]
+    #[case(&PROG6, PROG6_STR)]
     fn tokenize(#[case] expected_token_bytes: &[u8], #[case] input_source: &str) -> Result<()> {
         let input_bytes = get_source_bytes(input_source);
         let mut token_bytes = Vec::new();
@@ -359,6 +451,26 @@ mod tests {
         Ok(())
     }
 
+    #[rstest]
+    #[case(
+        &[0xee, 0x20, 0x41, 0x25, 0x20, 0xe5, 0x20, 0x8d, 0x44, 0x46, 0x40, 0x2c, 0x8d, 0x44, 0x50, 0x40],
+        "ON A% GOTO 70,80"
+    )]
+    #[case(
+        &[0xee, 0x20, 0x41, 0x25, 0x20, 0xe4, 0x20, 0x8d, 0x44, 0x46, 0x40, 0x2c, 0x20, 0x8d, 0x44, 0x50, 0x40, 0x3a, 0xf1, 0x20, 0x39, 0x30],
+        "ON A% GOSUB 70, 80:PRINT 90"
+    )]
+    #[case(&[0xe5, 0x20, 0x8d, 0x44, 0x46, 0x40, 0x2b, 0x31], "GOTO 70+1")]
+    fn tokenize_line_number_lists(
+        #[case] expected_token_bytes: &[u8],
+        #[case] input_source: &str,
+    ) -> Result<()> {
+        let input_bytes = get_source_bytes(input_source);
+        let token_bytes = tokenize_content(&input_bytes)?;
+        assert_eq!(expected_token_bytes, token_bytes);
+        Ok(())
+    }
+
     fn get_source_bytes(source: &str) -> Vec<u8> {
         // Cast characters individually to u8 in order to throw away
         // the 0xc2 Unicode control character
@@ -1,4 +1,4 @@
-use crate::bbc_basic::{END_MARKER, TOKEN_MASK};
+use crate::bbc_basic::{END_MARKER, TOKEN_MASK, detokenize_source, tokenize_lines};
 use crate::line_ending::CR;
 use anyhow::{Result, bail};
 use std::fs::{File, metadata};
@@ -9,6 +9,59 @@ pub const fn is_token(value: u8) -> bool {
     (value & TOKEN_MASK) != 0
 }
 
+// Same test as is_bbc_basic_file for content already in memory
+pub fn is_bbc_basic(bytes: &[u8]) -> bool {
+    bytes.len() >= 3 && bytes[0] == CR && bytes[bytes.len() - 2..] == END_MARKER
+}
+
+// Printable listing of content in memory, if it is BBC BASIC that
+// detokenizes cleanly
+pub fn detokenize_to_string(bytes: &[u8]) -> Option<String> {
+    if !is_bbc_basic(bytes) {
+        return None;
+    }
+    let mut output = Vec::new();
+    detokenize_source(&mut output, bytes, false).ok()?;
+    Some(String::from_utf8_lossy(&output).into_owned())
+}
+
+// Line numbers and contents of the lines of a tokenized program
+pub fn split_lines(bytes: &[u8]) -> Result<Vec<(u16, &[u8])>> {
+    let mut lines = Vec::new();
+    let mut index = 0;
+    loop {
+        match bytes.get(index..index + 2) {
+            Some(b) if b == END_MARKER => return Ok(lines),
+            Some(&[CR, _]) => {}
+            _ => bail!("syntax error: file is not valid tokenized BBC BASIC"),
+        }
+        let Some(&[hi, lo, line_len]) = bytes.get(index + 1..index + 4) else {
+            bail!("end of file")
+        };
+        let end = index + usize::from(line_len);
+        if line_len < 4 || end > bytes.len() {
+            bail!(
+                "invalid length of line {line_number}",
+                line_number = u16::from_be_bytes([hi, lo])
+            )
+        }
+        lines.push((u16::from_be_bytes([hi, lo]), &bytes[index + 4..end]));
+        index = end;
+    }
+}
+
+// Lines of a program that is either tokenized or still source text
+pub fn program_lines(bytes: &[u8]) -> Result<Vec<(u16, Vec<u8>)>> {
+    if is_bbc_basic(bytes) {
+        Ok(split_lines(bytes)?
+            .into_iter()
+            .map(|(line_number, content)| (line_number, content.to_vec()))
+            .collect())
+    } else {
+        tokenize_lines(bytes)
+    }
+}
+
 pub fn is_bbc_basic_file(content_path: &Path) -> Result<bool> {
     let m = metadata(content_path)?;
     if m.len() < 3 {
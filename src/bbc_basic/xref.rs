@@ -0,0 +1,481 @@
+use crate::bbc_basic::{
+    DATA_TOKEN, DEF_TOKEN, DIM_TOKEN, ELSE_TOKEN, FN_TOKEN, FOR_TOKEN, INPUT_TOKEN,
+    KEYWORDS_BY_TOKEN, LET_TOKEN, LINE_NUMBER_TOKEN, LOCAL_TOKEN, PROC_TOKEN, READ_TOKEN,
+    REM_TOKEN, REPEAT_TOKEN, THEN_TOKEN, TO_TOKEN, decode_line_number,
+};
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum SymbolKind {
+    #[serde(rename = "integer")]
+    Integer,
+
+    #[serde(rename = "real")]
+    Real,
+
+    #[serde(rename = "string")]
+    String,
+
+    #[serde(rename = "integer-array")]
+    IntegerArray,
+
+    #[serde(rename = "real-array")]
+    RealArray,
+
+    #[serde(rename = "string-array")]
+    StringArray,
+
+    #[serde(rename = "procedure")]
+    Procedure,
+
+    #[serde(rename = "function")]
+    Function,
+
+    #[serde(rename = "line")]
+    Line,
+}
+
+impl Display for SymbolKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Integer => "integer",
+                Self::Real => "real",
+                Self::String => "string",
+                Self::IntegerArray => "integer array",
+                Self::RealArray => "real array",
+                Self::StringArray => "string array",
+                Self::Procedure => "procedure",
+                Self::Function => "function",
+                Self::Line => "line",
+            }
+        )
+    }
+}
+
+// Variable, array, procedure, function or line number target with the lines
+// it is defined, assigned and used on
+#[derive(Debug, Serialize)]
+pub struct Symbol {
+    #[serde(rename = "kind")]
+    pub kind: SymbolKind,
+
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "defined")]
+    pub defined: Vec<u16>,
+
+    #[serde(rename = "assigned")]
+    pub assigned: Vec<u16>,
+
+    #[serde(rename = "used")]
+    pub used: Vec<u16>,
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{name} ({kind})", name = self.name, kind = self.kind)?;
+        let mut separator = ":";
+        for (label, line_numbers) in [
+            ("defined", &self.defined),
+            ("assigned", &self.assigned),
+            ("used", &self.used),
+        ] {
+            if !line_numbers.is_empty() {
+                write!(
+                    f,
+                    "{separator} {label} {line_numbers}",
+                    line_numbers = line_numbers
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+                separator = ";";
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Role {
+    Define,
+    Assign,
+    Use,
+}
+
+// What names in the rest of the statement are
+#[derive(Clone, Copy, PartialEq)]
+enum Statement {
+    Normal,
+    For,
+    Dim,
+    Input,
+    Local,
+    Parameters,
+}
+
+// Piece of a line that matters for cross-referencing: strings, numbers,
+// spaces, REM, DATA, * commands and assembler are dropped
+enum Item {
+    Byte(u8),
+    LineNumber(u16),
+    Call(SymbolKind, String),
+    Variable(SymbolKind, String),
+}
+
+impl Item {
+    const fn is_byte(&self, value: u8) -> bool {
+        matches!(self, Self::Byte(b) if *b == value)
+    }
+}
+
+// List every variable, array, procedure, function and line number target in
+// the lines of a tokenized program, ordered by kind and then name
+pub fn xref_program(lines: &[(u16, &[u8])]) -> Result<Vec<Symbol>> {
+    let mut xref = Xref {
+        line_numbers: lines.iter().map(|(line_number, _)| *line_number).collect(),
+        symbols: BTreeMap::new(),
+    };
+    let mut in_assembler = false;
+    for (line_number, content) in lines {
+        let items = parse_items(content, &mut in_assembler)
+            .map_err(|e| anyhow!("{e} at line {line_number}"))?;
+        xref.xref_line(*line_number, &items);
+    }
+    Ok(xref.symbols.into_values().collect())
+}
+
+struct Xref {
+    line_numbers: HashSet<u16>,
+    // Keyed by line number target as well as name so that lines sort by
+    // number
+    symbols: BTreeMap<(SymbolKind, u16, String), Symbol>,
+}
+
+impl Xref {
+    fn add(&mut self, kind: SymbolKind, name: &str, target: u16, role: Role, line_number: u16) {
+        let symbol = self
+            .symbols
+            .entry((kind, target, String::from(name)))
+            .or_insert_with(|| Symbol {
+                kind,
+                name: String::from(name),
+                defined: Vec::new(),
+                assigned: Vec::new(),
+                used: Vec::new(),
+            });
+        let line_numbers = match role {
+            Role::Define => &mut symbol.defined,
+            Role::Assign => &mut symbol.assigned,
+            Role::Use => &mut symbol.used,
+        };
+        if line_numbers.last() != Some(&line_number) {
+            line_numbers.push(line_number);
+        }
+    }
+
+    fn xref_line(&mut self, line_number: u16, items: &[Item]) {
+        let mut statement = Statement::Normal;
+        let mut statement_start = true;
+        let mut depth = 0usize;
+        for (i, item) in items.iter().enumerate() {
+            let previous = i.checked_sub(1).map(|i| &items[i]);
+            match item {
+                Item::Byte(b':') => {
+                    statement = Statement::Normal;
+                    statement_start = true;
+                    depth = 0;
+                    continue;
+                }
+                Item::Byte(THEN_TOKEN | ELSE_TOKEN | LET_TOKEN | REPEAT_TOKEN) => {
+                    statement = Statement::Normal;
+                    statement_start = true;
+                    continue;
+                }
+                Item::Byte(FOR_TOKEN) => statement = Statement::For,
+                Item::Byte(DIM_TOKEN) => statement = Statement::Dim,
+                Item::Byte(INPUT_TOKEN | READ_TOKEN) => statement = Statement::Input,
+                Item::Byte(LOCAL_TOKEN) => statement = Statement::Local,
+                Item::Byte(b')') => {
+                    depth = depth.saturating_sub(1);
+                    if statement == Statement::Parameters && depth == 0 {
+                        statement = Statement::Normal;
+                    }
+                }
+                Item::Byte(b) => {
+                    if *b == b'('
+                        || KEYWORDS_BY_TOKEN
+                            .get(b)
+                            .is_some_and(|name| name.ends_with('('))
+                    {
+                        depth += 1;
+                    }
+                }
+                Item::LineNumber(target) => {
+                    let name = target.to_string();
+                    if self.line_numbers.contains(target) {
+                        self.add(SymbolKind::Line, &name, *target, Role::Define, *target);
+                    }
+                    self.add(SymbolKind::Line, &name, *target, Role::Use, line_number);
+                }
+                Item::Call(kind, name) => {
+                    let role = if previous.is_some_and(|item| item.is_byte(DEF_TOKEN)) {
+                        statement = Statement::Parameters;
+                        Role::Define
+                    } else {
+                        Role::Use
+                    };
+                    self.add(*kind, name, 0, role, line_number);
+                }
+                Item::Variable(kind, name) => {
+                    let role = match statement {
+                        Statement::Local | Statement::Parameters => Role::Define,
+                        Statement::For => {
+                            statement = Statement::Normal;
+                            Role::Assign
+                        }
+                        Statement::Dim if depth == 0 => match kind {
+                            SymbolKind::IntegerArray
+                            | SymbolKind::RealArray
+                            | SymbolKind::StringArray => Role::Define,
+                            _ => Role::Assign,
+                        },
+                        Statement::Input
+                            if depth == 0 && !previous.is_some_and(|item| item.is_byte(b'#')) =>
+                        {
+                            Role::Assign
+                        }
+                        _ if statement_start && is_assignment(&items[i + 1..]) => Role::Assign,
+                        _ => Role::Use,
+                    };
+                    self.add(*kind, name, 0, role, line_number);
+                }
+            }
+            statement_start = false;
+        }
+    }
+}
+
+fn parse_items(content: &[u8], in_assembler: &mut bool) -> Result<Vec<Item>> {
+    let mut items = Vec::new();
+    let mut statement_start = true;
+    let mut index = 0;
+    while index < content.len() {
+        let b = content[index];
+        if *in_assembler {
+            *in_assembler = b != b']';
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        index += 1;
+        match b {
+            b'"' => {
+                index = content[index..]
+                    .iter()
+                    .position(|&b| b == b'"')
+                    .map_or(content.len(), |i| index + i + 1);
+            }
+            b'[' => *in_assembler = true,
+            b' ' => continue,
+            b'*' if statement_start => break,
+            REM_TOKEN | DATA_TOKEN => break,
+            LINE_NUMBER_TOKEN => {
+                let Some(&[b0, b1, b2]) = content.get(index..index + 3) else {
+                    bail!("truncated line number")
+                };
+                items.push(Item::LineNumber(decode_line_number(b0, b1, b2)));
+                index += 3;
+            }
+            PROC_TOKEN | FN_TOKEN => {
+                index = name_end(content, index);
+                let name = String::from_utf8_lossy(&content[start + 1..index]);
+                items.push(if b == PROC_TOKEN {
+                    Item::Call(SymbolKind::Procedure, format!("PROC{name}"))
+                } else {
+                    Item::Call(SymbolKind::Function, format!("FN{name}"))
+                });
+            }
+            b'A'..=b'Z' | b'a'..=b'z' | b'_' | b'`' => {
+                index = name_end(content, index);
+                // TO followed by P reads as TOP
+                let top = b == b'P' && items.last().is_some_and(|item| item.is_byte(TO_TOKEN));
+                if !top {
+                    let (kind, name, end) = variable(content, start, index);
+                    items.push(Item::Variable(kind, name));
+                    index = end;
+                }
+            }
+            b'@' if content.get(index) == Some(&b'%') => {
+                items.push(Item::Variable(SymbolKind::Integer, String::from("@%")));
+                index += 1;
+            }
+            b'0'..=b'9' | b'.' => index = number_end(content, start),
+            b'&' => {
+                while content.get(index).is_some_and(u8::is_ascii_hexdigit) {
+                    index += 1;
+                }
+            }
+            _ => items.push(Item::Byte(b)),
+        }
+        // A star command may follow THEN or ELSE as well as start a statement
+        statement_start = matches!(b, b':' | THEN_TOKEN | ELSE_TOKEN);
+    }
+    Ok(items)
+}
+
+fn name_end(content: &[u8], mut index: usize) -> usize {
+    while content
+        .get(index)
+        .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_' || *b == b'`')
+    {
+        index += 1;
+    }
+    index
+}
+
+// Kind and name of the variable or array whose name runs from start to end
+// along with the index following its type suffix
+fn variable(content: &[u8], start: usize, end: usize) -> (SymbolKind, String, usize) {
+    let mut name = String::from_utf8_lossy(&content[start..end]).into_owned();
+    let mut after = end;
+    let suffix = content.get(end).copied();
+    if matches!(suffix, Some(b'%' | b'$')) {
+        name.push(char::from(content[end]));
+        after += 1;
+    }
+    let is_array = content.get(after) == Some(&b'(');
+    if is_array {
+        name.push_str("()");
+    }
+    let kind = match (suffix, is_array) {
+        (Some(b'%'), false) => SymbolKind::Integer,
+        (Some(b'%'), true) => SymbolKind::IntegerArray,
+        (Some(b'$'), false) => SymbolKind::String,
+        (Some(b'$'), true) => SymbolKind::StringArray,
+        (_, false) => SymbolKind::Real,
+        (_, true) => SymbolKind::RealArray,
+    };
+    (kind, name, after)
+}
+
+// Numbers, including exponents such as 1E-3
+fn number_end(content: &[u8], mut index: usize) -> usize {
+    while content
+        .get(index)
+        .is_some_and(|b| b.is_ascii_digit() || *b == b'.')
+    {
+        index += 1;
+    }
+    if content.get(index) == Some(&b'E') {
+        let sign = usize::from(matches!(content.get(index + 1), Some(b'+' | b'-')));
+        if content
+            .get(index + 1 + sign)
+            .is_some_and(u8::is_ascii_digit)
+        {
+            index += 1 + sign;
+            while content.get(index).is_some_and(u8::is_ascii_digit) {
+                index += 1;
+            }
+        }
+    }
+    index
+}
+
+// Whether the items following a name at the start of a statement are any
+// array subscript followed by =, += or -=
+fn is_assignment(items: &[Item]) -> bool {
+    let mut index = 0;
+    if items.first().is_some_and(|item| item.is_byte(b'(')) {
+        let mut depth = 0usize;
+        while let Some(item) = items.get(index) {
+            index += 1;
+            if item.is_byte(b'(') {
+                depth += 1;
+            } else if item.is_byte(b')') {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+    }
+    matches!(
+        items.get(index..),
+        Some([Item::Byte(b'='), ..] | [Item::Byte(b'+' | b'-'), Item::Byte(b'='), ..])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bbc_basic::{tokenize_lines, xref_program};
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        vec![
+            "I% (integer): assigned 10; used 20",
+            "N (real): assigned 5; used 10",
+            "A$() (string array): defined 5; assigned 20; used 30",
+        ],
+        "5 DIM A$(10):N=3\n10 FOR I%=1 TO N\n20 A$(I%)=\"X\":NEXT\n30 PRINT A$(1)\n"
+    )]
+    #[case(
+        vec![
+            "X (real): defined 40; used 40",
+            "Y (real): defined 50; used 50",
+            "PROCdraw (procedure): defined 40; used 10",
+            "FNsq (function): defined 50; used 40",
+            "20 (line): defined 20; used 10",
+            "99 (line): used 20",
+        ],
+        "10 PROCdraw(2):GOTO 20\n20 IF TRUE THEN 99\n40 DEF PROCdraw(X):PRINT FNsq(X):ENDPROC\n50 DEF FNsq(Y)=Y*Y\n"
+    )]
+    #[case(
+        vec![
+            "A% (integer): assigned 10; used 20",
+            "B (real): assigned 10",
+            "F (real): used 10",
+            "Z (real): assigned 30",
+            "C$ (string): assigned 10",
+        ],
+        "10 READ A%,B:INPUT#F,C$:REM X=1\n20 IF A%=&FF PRINT \"Y=2\"\n30 Z=1E3\n"
+    )]
+    #[case(
+        vec!["X (real): used 10, 20"],
+        "10 IF X THEN *SAVE DATA 1900 +100\n20 IF X THEN PRINT ELSE *LOAD FILE 3000\n"
+    )]
+    #[case(
+        vec![
+            "A% (integer): used 10",
+            "70 (line): defined 70; used 10",
+            "80 (line): defined 80; used 10",
+        ],
+        "10 ON A% GOTO 70,80\n70 END\n80 END\n"
+    )]
+    fn xref(#[case] expected: Vec<&str>, #[case] source: &str) -> Result<()> {
+        let lines = tokenize_lines(source.as_bytes())?;
+        let lines = lines
+            .iter()
+            .map(|(line_number, content)| (*line_number, content.as_slice()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expected,
+            xref_program(&lines)?
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
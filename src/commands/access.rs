@@ -0,0 +1,19 @@
+use crate::dfs::{DfsPath, Side};
+use crate::image_format::ImageFormat;
+use crate::ops::{edit_image_file, find_file};
+use anyhow::Result;
+use std::path::Path;
+
+pub fn run_access(
+    image_path: &Path,
+    name: &DfsPath,
+    locked: bool,
+    side: Side,
+    format: Option<ImageFormat>,
+) -> Result<()> {
+    edit_image_file(image_path, format, side, |catalogue, _, _| {
+        let index = find_file(catalogue, name)?;
+        catalogue.entries[index].descriptor.locked = locked;
+        Ok(())
+    })
+}
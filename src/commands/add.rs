@@ -0,0 +1,102 @@
+use crate::constants::INF_EXT;
+use crate::dfs::{
+    Address, CatalogueEntry, DfsPath, FileDescriptor, Length, Side, get_file_sector_count,
+};
+use crate::image_format::ImageFormat;
+use crate::metadata::read_inf_file;
+use crate::ops::edit_image_file;
+use crate::path_util::add_extension;
+use anyhow::{Result, anyhow, bail};
+use std::ffi::OsStr;
+use std::fs::read;
+use std::path::Path;
+
+pub struct AddOpts {
+    pub name: Option<DfsPath>,
+    pub load_address: Option<Address>,
+    pub execution_address: Option<Address>,
+    pub locked: bool,
+    pub side: Side,
+    pub format: Option<ImageFormat>,
+    pub overwrite: bool,
+}
+
+pub fn run_add(image_path: &Path, path: &Path, opts: &AddOpts) -> Result<()> {
+    let mut descriptor = make_descriptor(path)?;
+    if let Some(name) = &opts.name {
+        descriptor.directory = name.directory;
+        descriptor.file_name = name.file_name.clone();
+    }
+    if let Some(load_address) = opts.load_address {
+        descriptor.load_address = load_address;
+    }
+    if let Some(execution_address) = opts.execution_address {
+        descriptor.execution_address = execution_address;
+    }
+    if opts.locked {
+        descriptor.locked = true;
+    }
+
+    let bytes = read(path)?;
+    let length = Length::try_from(u32::try_from(bytes.len())?)?;
+    let sector_count = get_file_sector_count(length)?;
+
+    edit_image_file(
+        image_path,
+        opts.format,
+        opts.side,
+        |catalogue, _, writer| {
+            let name = format!(
+                "{directory}.{file_name}",
+                directory = descriptor.directory,
+                file_name = descriptor.file_name
+            );
+
+            if let Some(index) = catalogue.find_entry(&descriptor) {
+                if !opts.overwrite {
+                    bail!("file {name} already exists: pass --overwrite to replace it")
+                }
+                if catalogue.entries[index].descriptor.locked {
+                    bail!("file {name} is locked")
+                }
+                catalogue.entries.remove(index);
+            }
+
+            if catalogue.entries.len() >= usize::from(catalogue.flavour.max_files()) {
+                bail!("catalogue full")
+            }
+
+            let Some(start_sector) = catalogue.find_free_space(sector_count)? else {
+                bail!("disc full")
+            };
+
+            writer.write_bytes(opts.side, start_sector, &bytes)?;
+            catalogue
+                .entries
+                .push(CatalogueEntry::new(descriptor, length, start_sector));
+            Ok(())
+        },
+    )
+}
+
+// Take metadata from the file's .inf file if present, otherwise infer the
+// DFS file name from the host file name
+fn make_descriptor(path: &Path) -> Result<FileDescriptor> {
+    let inf_path = add_extension(path, INF_EXT)?;
+    if inf_path.is_file() {
+        return read_inf_file(&inf_path);
+    }
+
+    let file_name = path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .ok_or_else(|| anyhow!("could not get file name from {path}", path = path.display()))?;
+    let dfs_path = file_name.parse::<DfsPath>()?;
+    Ok(FileDescriptor::new(
+        dfs_path.file_name,
+        dfs_path.directory,
+        false,
+        Address::ZERO,
+        Address::ZERO,
+    ))
+}
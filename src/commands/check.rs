@@ -0,0 +1,95 @@
+use crate::dfs::{Issue, SECTOR_BYTES, Side, check_side};
+use crate::dsd_reader::DsdReader;
+use crate::image_format::ImageFormat;
+use crate::image_reader::ImageReader;
+use crate::ssd_reader::SsdReader;
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::fs::File;
+use std::path::Path;
+
+#[derive(Serialize)]
+struct Report {
+    #[serde(rename = "path")]
+    path: String,
+
+    #[serde(rename = "ok")]
+    ok: bool,
+
+    #[serde(rename = "sides")]
+    sides: Vec<SideReport>,
+}
+
+#[derive(Serialize)]
+struct SideReport {
+    #[serde(rename = "side")]
+    side: u8,
+
+    #[serde(rename = "issues")]
+    issues: Vec<Issue>,
+}
+
+pub fn run_check(path: &Path, format: Option<ImageFormat>, json: bool) -> Result<()> {
+    let mut f = File::open(path)?;
+    let format = ImageFormat::detect(&mut f, path, format)?;
+    let sides = match format {
+        ImageFormat::Dsd => check_image(&mut DsdReader::new(
+            f,
+            SECTOR_BYTES,
+            format.sectors_per_track(),
+        )?)?,
+        ImageFormat::Ssd => check_image(&mut SsdReader::new(f, SECTOR_BYTES)?)?,
+        ImageFormat::Dds | ImageFormat::Ddd => bail!("checking DDOS disc images is not supported"),
+        ImageFormat::Adfs => bail!("checking ADFS disc images is not supported"),
+    };
+
+    let issue_count = sides.iter().map(|s| s.issues.len()).sum::<usize>();
+    let report = Report {
+        path: path.display().to_string(),
+        ok: issue_count == 0,
+        sides,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        show_report(&report);
+    }
+
+    if issue_count > 0 {
+        bail!(
+            "found {issue_count} problem(s) in {path}",
+            path = path.display()
+        )
+    }
+
+    Ok(())
+}
+
+fn check_image<R: ImageReader>(reader: &mut R) -> Result<Vec<SideReport>> {
+    (0..reader.sides())
+        .map(|side| {
+            Ok(SideReport {
+                side,
+                issues: check_side(reader, Side::try_from(side)?)?,
+            })
+        })
+        .collect()
+}
+
+fn show_report(report: &Report) {
+    for side in &report.sides {
+        if side.issues.is_empty() {
+            println!("Side {side}: OK", side = side.side);
+        } else {
+            println!(
+                "Side {side}: {count} problem(s)",
+                side = side.side,
+                count = side.issues.len()
+            );
+            for issue in &side.issues {
+                println!("  {issue}");
+            }
+        }
+    }
+}
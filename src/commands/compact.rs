@@ -0,0 +1,219 @@
+use crate::dfs::{Catalogue, SectorSize, Side, get_file_sector_count};
+use crate::image_format::ImageFormat;
+use crate::ops::edit_image_file;
+use anyhow::{Result, bail};
+use std::path::Path;
+
+// Move file data down towards the start of the disc so that all free space
+// forms a single run at the end of the disc, as *COMPACT does
+pub fn run_compact(image_path: &Path, side: Side, format: Option<ImageFormat>) -> Result<()> {
+    let (before, after) = compact_image_file(image_path, side, format)?;
+    println!(
+        "Largest free space was {before} sectors, now {after} sectors (gained {gained} sectors)",
+        gained = after - before
+    );
+    Ok(())
+}
+
+// Returns the size of the largest free space before and after compaction
+fn compact_image_file(
+    image_path: &Path,
+    side: Side,
+    format: Option<ImageFormat>,
+) -> Result<(u16, u16)> {
+    let mut free_sectors = (0, 0);
+    edit_image_file(image_path, format, side, |catalogue, reader, writer| {
+        let before = largest_free_extent(catalogue)?;
+
+        let mut indices = (0..catalogue.entries.len()).collect::<Vec<_>>();
+        indices.sort_by_key(|&i| u16::from(catalogue.entries[i].start_sector));
+
+        // Check for overlaps before moving anything so that the image is
+        // left alone if there are any
+        let mut used_end = u16::from(catalogue.flavour.start_sector());
+        for &i in &indices {
+            let entry = &catalogue.entries[i];
+            let sector_count = u16::from(get_file_sector_count(entry.length)?);
+            let start_sector = u16::from(entry.start_sector);
+            if sector_count == 0 {
+                continue;
+            }
+            if start_sector < used_end {
+                bail!(
+                    "file {directory}.{file_name} overlaps another file or the catalogue: run check for details",
+                    directory = entry.descriptor.directory,
+                    file_name = entry.descriptor.file_name
+                )
+            }
+            used_end = start_sector + sector_count;
+        }
+
+        let mut next = catalogue.flavour.start_sector();
+        for i in indices {
+            let entry = &mut catalogue.entries[i];
+            let sector_count = get_file_sector_count(entry.length)?;
+
+            // Zero-length files occupy no sectors but keep their place
+            if sector_count == SectorSize::ZERO {
+                entry.start_sector = next;
+                continue;
+            }
+
+            if entry.start_sector != next {
+                let mut bytes = vec![0; usize::try_from(u32::from(entry.length))?];
+                reader.read_bytes(side, entry.start_sector, &mut bytes)?;
+                writer.write_bytes(side, next, &bytes)?;
+                entry.start_sector = next;
+            }
+
+            next += sector_count;
+        }
+
+        free_sectors = (before, largest_free_extent(catalogue)?);
+        Ok(())
+    })?;
+    Ok(free_sectors)
+}
+
+fn largest_free_extent(catalogue: &Catalogue) -> Result<u16> {
+    Ok(catalogue
+        .free_extents()?
+        .iter()
+        .map(|extent| u16::from(extent.sector_count))
+        .max()
+        .unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compact_image_file;
+    use crate::dfs::{
+        Address, BootOption, Catalogue, CatalogueEntry, CatalogueFlavour, CycleNumber,
+        FileDescriptor, FileOffset, SECTOR_BYTES, Side, check_catalogue,
+    };
+    use anyhow::Result;
+    use rstest::rstest;
+    use std::fs::{read, write};
+    use tempfile::tempdir;
+
+    const DISC_SIZE: u16 = 400;
+
+    // Image holding files of the given lengths and start sectors, in which
+    // every byte of file n holds n + 1
+    fn make_image(flavour: CatalogueFlavour, files: &[(u32, u16)]) -> Result<Vec<u8>> {
+        let sector_bytes = usize::from(SECTOR_BYTES);
+        let mut bytes = vec![0; usize::from(DISC_SIZE) * sector_bytes];
+        let mut entries = Vec::with_capacity(files.len());
+        for (i, &(length, start_sector)) in files.iter().enumerate() {
+            let offset = usize::from(start_sector) * sector_bytes;
+            bytes[offset..offset + usize::try_from(length)?].fill(u8::try_from(i + 1)?);
+            entries.push(CatalogueEntry::new(
+                FileDescriptor::new(
+                    format!("F{i}").parse()?,
+                    '$'.try_into()?,
+                    false,
+                    Address::try_from(0x1900)?,
+                    Address::try_from(0x8023)?,
+                ),
+                length.try_into()?,
+                start_sector.try_into()?,
+            ));
+        }
+
+        let mut catalogue = Catalogue::new(
+            "DISC".parse()?,
+            CycleNumber::default(),
+            FileOffset::new(0)?,
+            BootOption::None,
+            DISC_SIZE.try_into()?,
+            flavour,
+            entries,
+        );
+        catalogue.sort_entries();
+        catalogue.update_file_offset()?;
+        catalogue.write_to_catalogue(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    // Length, start sector and content of each file in order of name
+    fn read_files(bytes: &[u8]) -> Result<Vec<(u32, u16, Vec<u8>)>> {
+        let mut catalogue = Catalogue::from_catalogue_bytes(bytes)?;
+        catalogue.entries.sort_by_key(|entry| {
+            entry.descriptor.file_name.as_str()[1..]
+                .parse::<usize>()
+                .unwrap()
+        });
+        Ok(catalogue
+            .entries
+            .iter()
+            .map(|entry| {
+                let length = u32::from(entry.length);
+                let offset = usize::from(u16::from(entry.start_sector)) * usize::from(SECTOR_BYTES);
+                let content = bytes[offset..offset + usize::try_from(length).unwrap()].to_vec();
+                (length, u16::from(entry.start_sector), content)
+            })
+            .collect())
+    }
+
+    #[rstest]
+    // Gaps between files and before the first file are closed up and a
+    // zero-length file moves along with the file after it
+    #[case(
+        CatalogueFlavour::Acorn,
+        vec![(0x280, 10), (0, 30), (0x100, 30), (0x1ff, 50)],
+        vec![2, 5, 5, 6],
+        (348, 392),
+    )]
+    // Watford DFS files start after its second catalogue
+    #[case(
+        CatalogueFlavour::Watford62,
+        (0..40u16).map(|i| (0x180, 10 + i * 5)).collect(),
+        (0..40u16).map(|i| 4 + i * 2).collect(),
+        (193, 316),
+    )]
+    fn compact(
+        #[case] flavour: CatalogueFlavour,
+        #[case] files: Vec<(u32, u16)>,
+        #[case] expected_start_sectors: Vec<u16>,
+        #[case] expected_free_sectors: (u16, u16),
+    ) -> Result<()> {
+        let bytes = make_image(flavour, &files)?;
+        let dir = tempdir()?;
+        let path = dir.path().join("disc.ssd");
+        write(&path, &bytes)?;
+
+        let free_sectors = compact_image_file(&path, Side::Side0, None)?;
+        assert_eq!(expected_free_sectors, free_sectors);
+
+        let compacted = read(&path)?;
+        let before = read_files(&bytes)?;
+        let after = read_files(&compacted)?;
+        assert_eq!(
+            expected_start_sectors,
+            after.iter().map(|f| f.1).collect::<Vec<_>>()
+        );
+        for (b, a) in before.iter().zip(&after) {
+            assert_eq!(b.0, a.0);
+            assert_eq!(b.2, a.2);
+        }
+
+        assert!(check_catalogue(&compacted, compacted.len() as u64).is_empty());
+        Ok(())
+    }
+
+    #[rstest]
+    // Two files
+    #[case(CatalogueFlavour::Acorn, &[(0x100, 20), (0x300, 10), (0x100, 11)])]
+    // File and Watford DFS second catalogue
+    #[case(CatalogueFlavour::Watford62, &[(0x100, 20), (0x100, 3)])]
+    fn overlap(#[case] flavour: CatalogueFlavour, #[case] files: &[(u32, u16)]) -> Result<()> {
+        let bytes = make_image(flavour, files)?;
+        let dir = tempdir()?;
+        let path = dir.path().join("disc.ssd");
+        write(&path, &bytes)?;
+
+        assert!(compact_image_file(&path, Side::Side0, None).is_err());
+        assert_eq!(bytes, read(&path)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use crate::bbc_basic::crunch_program;
+use crate::util::open_for_write;
+use anyhow::Result;
+use std::fs::{read, write};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub fn run_crunch(
+    path: &Path,
+    output_path: Option<&PathBuf>,
+    overwrite: bool,
+    shorten_names: bool,
+) -> Result<()> {
+    let bytes = read(path)?;
+    let crunched = crunch_program(&bytes, shorten_names)?;
+    match output_path {
+        Some(output_path) => open_for_write(output_path, overwrite)?.write_all(&crunched)?,
+        None => write(path, &crunched)?,
+    }
+    println!(
+        "{before} bytes -> {after} bytes: saved {saved} bytes",
+        before = bytes.len(),
+        after = crunched.len(),
+        saved = bytes.len() - crunched.len()
+    );
+    Ok(())
+}
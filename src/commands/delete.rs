@@ -0,0 +1,18 @@
+use crate::dfs::{DfsPath, Side};
+use crate::image_format::ImageFormat;
+use crate::ops::{edit_image_file, find_unlocked_file};
+use anyhow::Result;
+use std::path::Path;
+
+pub fn run_delete(
+    image_path: &Path,
+    name: &DfsPath,
+    side: Side,
+    format: Option<ImageFormat>,
+) -> Result<()> {
+    edit_image_file(image_path, format, side, |catalogue, _, _| {
+        let index = find_unlocked_file(catalogue, name)?;
+        catalogue.entries.remove(index);
+        Ok(())
+    })
+}
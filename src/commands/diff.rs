@@ -0,0 +1,316 @@
+use crate::bbc_basic::detokenize_to_string;
+use crate::commands::read_manifest;
+use crate::ddos_reader::DdosReader;
+use crate::dfs::{BootOption, Catalogue, FileDescriptor, FileSpec, SECTOR_BYTES, Side};
+use crate::dsd_reader::DsdReader;
+use crate::image_format::ImageFormat;
+use crate::image_reader::ImageReader;
+use crate::ops::read_contents;
+use crate::ssd_reader::SsdReader;
+use crate::util::crc32;
+use anyhow::{Result, bail};
+use std::ffi::OsStr;
+use std::fs::{File, read_dir};
+use std::path::{Path, PathBuf};
+
+// One side of a disc image or DDOS volume, or the files listed in a manifest
+struct DiscContents {
+    title: String,
+    boot_option: BootOption,
+    files: Vec<(FileDescriptor, Vec<u8>)>,
+}
+
+pub fn run_diff(path_a: &Path, path_b: &Path, format: Option<ImageFormat>) -> Result<()> {
+    let a = load(path_a, format)?;
+    let b = load(path_b, format)?;
+    let name_a = path_a.display().to_string();
+    let name_b = path_b.display().to_string();
+
+    let mut lines = Vec::new();
+    for i in 0..a.len().max(b.len()) {
+        let side_lines = match (a.get(i), b.get(i)) {
+            (Some(a), Some(b)) => diff_contents(a, b, &name_a, &name_b),
+            (Some(_), None) => vec![format!("only in {name_a}")],
+            _ => vec![format!("only in {name_b}")],
+        };
+        if !side_lines.is_empty() {
+            lines.push(format!("Side {i}:"));
+            lines.extend(side_lines.into_iter().map(|line| format!("  {line}")));
+        }
+    }
+
+    for line in &lines {
+        println!("{line}");
+    }
+
+    if !lines.is_empty() {
+        bail!("{name_a} and {name_b} differ")
+    }
+
+    Ok(())
+}
+
+fn diff_contents(a: &DiscContents, b: &DiscContents, name_a: &str, name_b: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    if a.title != b.title {
+        lines.push(format!(
+            "title: \"{title_a}\" -> \"{title_b}\"",
+            title_a = a.title,
+            title_b = b.title
+        ));
+    }
+    if a.boot_option != b.boot_option {
+        lines.push(format!(
+            "boot option: {boot_a} -> {boot_b}",
+            boot_a = a.boot_option,
+            boot_b = b.boot_option
+        ));
+    }
+
+    for (d, _) in &a.files {
+        if !b.files.iter().any(|(other, _)| d.matches(other)) {
+            lines.push(format!("only in {name_a}: {name}", name = full_name(d)));
+        }
+    }
+    for (d, _) in &b.files {
+        if !a.files.iter().any(|(other, _)| d.matches(other)) {
+            lines.push(format!("only in {name_b}: {name}", name = full_name(d)));
+        }
+    }
+
+    for (d_a, bytes_a) in &a.files {
+        let Some((d_b, bytes_b)) = b.files.iter().find(|(other, _)| d_a.matches(other)) else {
+            continue;
+        };
+        lines.extend(
+            diff_file(d_a, bytes_a, d_b, bytes_b)
+                .into_iter()
+                .map(|line| format!("{name}: {line}", name = full_name(d_a))),
+        );
+    }
+
+    lines
+}
+
+fn diff_file(
+    d_a: &FileDescriptor,
+    bytes_a: &[u8],
+    d_b: &FileDescriptor,
+    bytes_b: &[u8],
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    if d_a.load_address != d_b.load_address {
+        lines.push(format!(
+            "load address &{a:06X} -> &{b:06X}",
+            a = d_a.load_address,
+            b = d_b.load_address
+        ));
+    }
+    if d_a.execution_address != d_b.execution_address {
+        lines.push(format!(
+            "execution address &{a:06X} -> &{b:06X}",
+            a = d_a.execution_address,
+            b = d_b.execution_address
+        ));
+    }
+    if d_a.locked != d_b.locked {
+        lines.push(String::from(if d_a.locked {
+            "locked -> unlocked"
+        } else {
+            "unlocked -> locked"
+        }));
+    }
+
+    let (crc_a, crc_b) = (crc32(bytes_a), crc32(bytes_b));
+    if crc_a != crc_b || bytes_a.len() != bytes_b.len() {
+        lines.push(format!(
+            "contents differ: {len_a} bytes, CRC-32 &{crc_a:08X} -> {len_b} bytes, CRC-32 &{crc_b:08X}",
+            len_a = bytes_a.len(),
+            len_b = bytes_b.len()
+        ));
+        if let (Some(listing_a), Some(listing_b)) =
+            (detokenize_to_string(bytes_a), detokenize_to_string(bytes_b))
+        {
+            let lines_a = listing_a.lines().collect::<Vec<_>>();
+            let lines_b = listing_b.lines().collect::<Vec<_>>();
+            for (marker, line) in diff_lines(&lines_a, &lines_b) {
+                lines.push(format!("{marker} {line}"));
+            }
+        }
+    }
+
+    lines
+}
+
+fn full_name(d: &FileDescriptor) -> String {
+    format!(
+        "{directory}.{file_name}",
+        directory = d.directory,
+        file_name = d.file_name
+    )
+}
+
+// Lines removed from a and added in b, from a longest common subsequence
+fn diff_lines<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(char, &'a str)> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            result.push(('-', a[i]));
+            i += 1;
+        } else {
+            result.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    result
+}
+
+// A path is a disc image, a manifest or a directory containing manifests
+// written by extract, either directly or in one subdirectory per side
+fn load(path: &Path, format: Option<ImageFormat>) -> Result<Vec<DiscContents>> {
+    if path.is_dir() {
+        let mut manifest_paths = find_manifests(path)?;
+        if manifest_paths.is_empty() {
+            for entry in read_dir(path)? {
+                let p = entry?.path();
+                if p.is_dir() {
+                    manifest_paths.extend(find_manifests(&p)?);
+                }
+            }
+            manifest_paths.sort();
+        }
+        if manifest_paths.is_empty() {
+            bail!("no manifests found in {path}", path = path.display())
+        }
+        manifest_paths.iter().map(|p| load_manifest(p)).collect()
+    } else if is_manifest_path(path) {
+        Ok(vec![load_manifest(path)?])
+    } else {
+        load_image(path, format)
+    }
+}
+
+fn is_manifest_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+fn find_manifests(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in read_dir(dir)? {
+        let p = entry?.path();
+        if p.is_file() && is_manifest_path(&p) {
+            paths.push(p);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn load_manifest(path: &Path) -> Result<DiscContents> {
+    let (manifest_dir, manifest) = read_manifest(path, None)?;
+    let inf_files = manifest.inf_files.iter().collect::<Vec<_>>();
+    let files = manifest.files.iter().collect::<Vec<_>>();
+    let mut files = read_contents(manifest_dir, &inf_files, &files)?;
+    files.sort_by(|a, b| FileSpec::compare(&a.0, &b.0));
+    Ok(DiscContents {
+        title: manifest
+            .disc_title
+            .map(|title| title.to_string())
+            .unwrap_or_default(),
+        boot_option: manifest.boot_option,
+        files,
+    })
+}
+
+fn load_image(path: &Path, format: Option<ImageFormat>) -> Result<Vec<DiscContents>> {
+    let mut f = File::open(path)?;
+    let format = ImageFormat::detect(&mut f, path, format)?;
+    match format {
+        ImageFormat::Ssd => load_sides(&mut SsdReader::new(f, SECTOR_BYTES)?),
+        ImageFormat::Dsd => load_sides(&mut DsdReader::new(
+            f,
+            SECTOR_BYTES,
+            format.sectors_per_track(),
+        )?),
+        ImageFormat::Dds | ImageFormat::Ddd => {
+            let mut reader = DdosReader::new(f, SECTOR_BYTES, format.sides())?;
+            Catalogue::from_ddos_reader(&mut reader, false)?
+                .into_iter()
+                .map(|catalogue| {
+                    let Some(volume) = catalogue.volume else {
+                        bail!("catalogue has no volume")
+                    };
+                    load_side(catalogue, Side::Side0, &mut reader.volume_reader(volume))
+                })
+                .collect()
+        }
+        ImageFormat::Adfs => bail!("comparing ADFS disc images is not supported"),
+    }
+}
+
+fn load_sides<R: ImageReader>(reader: &mut R) -> Result<Vec<DiscContents>> {
+    Catalogue::from_image_reader(reader, false)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, catalogue)| load_side(catalogue, Side::try_from(i)?, reader))
+        .collect()
+}
+
+fn load_side<R: ImageReader>(
+    catalogue: Catalogue,
+    side: Side,
+    reader: &mut R,
+) -> Result<DiscContents> {
+    let mut files = catalogue
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let mut bytes = vec![0; usize::try_from(u32::from(entry.length))?];
+            reader.read_bytes(side, entry.start_sector, &mut bytes)?;
+            Ok((entry.descriptor, bytes))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    files.sort_by(|a, b| FileSpec::compare(&a.0, &b.0));
+    Ok(DiscContents {
+        title: catalogue.disc_title.to_string(),
+        boot_option: catalogue.boot_option,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_lines;
+
+    #[test]
+    fn lines() {
+        assert_eq!(
+            vec![
+                ('-', "20 PRINT \"A\""),
+                ('+', "20 PRINT \"B\""),
+                ('+', "40 END")
+            ],
+            diff_lines(
+                &["10 CLS", "20 PRINT \"A\"", "30 GOTO 20"],
+                &["10 CLS", "20 PRINT \"B\"", "30 GOTO 20", "40 END"]
+            )
+        );
+    }
+}
@@ -1,18 +1,32 @@
+use crate::adfs::AdfsImage;
 use crate::bbc_basic::{detokenize_source, is_bbc_basic_file};
-use crate::constants::{INF_EXT, LOSSLESS_BBC_BASIC_EXT, LOSSY_BBC_BASIC_EXT, MANIFEST_VERSION};
-use crate::dfs::{Catalogue, CatalogueEntry, FileSpec, SECTOR_BYTES, Side};
+use crate::constants::{
+    INF_EXT, LOSSLESS_BBC_BASIC_EXT, LOSSY_BBC_BASIC_EXT, MANIFEST_VERSION, SLACK_EXT,
+};
+use crate::ddos_reader::DdosReader;
+use crate::dfs::{
+    BootOption, Catalogue, CatalogueEntry, CatalogueFlavour, CycleNumber, DiscSize, FileDescriptor,
+    FileSpec, Length, SECTOR_BYTES, SectorSize, Side,
+};
 use crate::dsd_reader::DsdReader;
+use crate::image_format::ImageFormat;
 use crate::image_reader::ImageReader;
-use crate::metadata::{FileType, KnownFileType, Manifest, make_inf_file};
-use crate::path_util::add_extension;
+use crate::metadata::{
+    File as ManifestFile, FileType, KnownFileType, Layout, LayoutEntry, Manifest, Slack,
+    make_adfs_inf_file, make_inf_file,
+};
+use crate::ops::layout_side;
+use crate::path_util::{add_extension, strip_extension};
 use crate::ssd_reader::SsdReader;
+use crate::uef::{TapeFile, UEF_EXT, read_uef_file};
 use crate::util::open_for_write;
 use anyhow::{Result, anyhow, bail};
+use path_absolutize::Absolutize;
 use pathdiff::diff_paths;
 use std::ffi::OsStr;
 use std::fmt::Write as _;
 use std::fs::{File, create_dir_all};
-use std::io::{ErrorKind, Read, Write, copy};
+use std::io::{ErrorKind, Read, Seek, Write, copy};
 use std::path::{Path, PathBuf};
 use tempfile::tempfile;
 use zip::ZipArchive;
@@ -23,19 +37,27 @@ pub struct ExtractOpts {
     pub detokenize: bool,
     pub lossless: bool,
     pub inf: bool,
+    pub preserve: bool,
+    pub format: Option<ImageFormat>,
 }
 
 pub fn run_extract(path: &Path, output_dir: &Path, opts: &ExtractOpts) -> Result<()> {
-    if path.extension().and_then(OsStr::to_str) == Some("zip") {
+    let ext = path.extension().and_then(OsStr::to_str);
+    if ext.is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
         extract_from_zip(path, output_dir, opts)?;
+    } else if ext.is_some_and(|ext| ext.eq_ignore_ascii_case(UEF_EXT)) {
+        extract_from_uef(path, output_dir, opts)?;
     } else {
         extract_from_image(path, output_dir, opts)?;
     }
     Ok(())
 }
 
-// Zip file must contain exactly one .ssd or dsd file. All other files
-// will be ignored.
+// A zip file containing a single disc image is extracted as if it were the
+// image itself. When it contains several, each is extracted to a directory
+// named after its path within the archive. Files with generic disc image
+// extensions such as .img are only considered if their content looks like a
+// disc image. All other files will be ignored.
 fn extract_from_zip(path: &Path, output_dir: &Path, opts: &ExtractOpts) -> Result<()> {
     let mut zip_f = match File::open(path) {
         Ok(f) => f,
@@ -48,44 +70,40 @@ fn extract_from_zip(path: &Path, output_dir: &Path, opts: &ExtractOpts) -> Resul
     let mut archive = ZipArchive::new(&mut zip_f)?;
     let mut image_files = Vec::new();
     for i in 0..archive.len() {
-        let file = archive.by_index(i)?;
+        let mut file = archive.by_index(i)?;
         if file.is_file()
             && let Some(p) = file.enclosed_name()
+            && ImageFormat::has_image_extension(&p)
         {
-            let ext = p.extension().and_then(OsStr::to_str);
-            if ext == Some("dsd") || ext == Some("ssd") {
-                image_files.push((i, p));
+            let mut f = tempfile()?;
+            copy(&mut file, &mut f)?;
+            if ImageFormat::from_path(&p).is_some()
+                || ImageFormat::probe_only(&mut f)?.is_some()
+                || opts.format.is_some()
+            {
+                image_files.push((p, f));
             }
         }
     }
 
-    let image_file_info = match image_files.len() {
-        0 => bail!(
+    if image_files.len() == 1 {
+        let (image_path, f) = image_files.remove(0);
+        return extract_detected(path, &image_path, f, output_dir, opts);
+    }
+
+    if image_files.is_empty() {
+        bail!(
             "no disc images found in archive {path}",
             path = path.display()
-        ),
-        1 => image_files.first().unwrap(),
-        _ => bail!(
-            "more than one disc image was found in archive {path}",
-            path = path.display()
-        ),
-    };
-
-    let mut archive_file = archive.by_index(image_file_info.0)?;
-    let mut f = tempfile()?;
-    copy(&mut archive_file, &mut f)?;
+        )
+    }
 
-    match image_file_info.1.extension().and_then(OsStr::to_str) {
-        Some("dsd") => {
-            let reader = DsdReader::new(f, SECTOR_BYTES)?;
-            extract_all(path, output_dir, opts, reader)
-        }
-        Some("ssd") => {
-            let reader = SsdReader::new(f, SECTOR_BYTES)?;
-            extract_all(path, output_dir, opts, reader)
-        }
-        _ => bail!("unsupported file type {path}", path = path.display()),
+    image_files.sort_by(|a, b| a.0.cmp(&b.0));
+    for (image_path, f) in image_files {
+        let image_output_dir = output_dir.join(strip_extension(&image_path)?);
+        extract_detected(&image_path, &image_path, f, &image_output_dir, opts)?;
     }
+    Ok(())
 }
 
 fn extract_from_image(path: &Path, output_dir: &Path, opts: &ExtractOpts) -> Result<()> {
@@ -97,33 +115,48 @@ fn extract_from_image(path: &Path, output_dir: &Path, opts: &ExtractOpts) -> Res
         Err(e) => bail!(e),
     };
 
-    match path.extension().and_then(OsStr::to_str) {
-        Some("dsd") => {
-            let reader = DsdReader::new(f, SECTOR_BYTES)?;
+    extract_detected(path, path, f, output_dir, opts)
+}
+
+fn extract_detected(
+    path: &Path,
+    image_path: &Path,
+    mut f: File,
+    output_dir: &Path,
+    opts: &ExtractOpts,
+) -> Result<()> {
+    let format = ImageFormat::detect(&mut f, image_path, opts.format)?;
+    match format {
+        ImageFormat::Dsd => {
+            let reader = DsdReader::new(f, SECTOR_BYTES, format.sectors_per_track())?;
             extract_all(path, output_dir, opts, reader)
         }
-        Some("ssd") => {
+        ImageFormat::Ssd => {
             let reader = SsdReader::new(f, SECTOR_BYTES)?;
             extract_all(path, output_dir, opts, reader)
         }
-        _ => bail!("unsupported file type {path}", path = path.display()),
+        ImageFormat::Dds | ImageFormat::Ddd => {
+            let reader = DdosReader::new(f, SECTOR_BYTES, format.sides())?;
+            extract_volumes(path, output_dir, opts, reader)
+        }
+        ImageFormat::Adfs => extract_adfs(output_dir, opts, AdfsImage::new(f)?),
     }
 }
 
-fn extract_all<R: ImageReader>(
+pub fn extract_all<R: ImageReader>(
     path: &Path,
     output_dir: &Path,
     opts: &ExtractOpts,
     mut reader: R,
 ) -> Result<()> {
-    let catalogues = Catalogue::from_image_reader(&mut reader)?;
+    let catalogues = Catalogue::from_image_reader(&mut reader, false)?;
     let double_sided = catalogues.len() > 1;
     for (i, catalogue) in catalogues.into_iter().enumerate() {
         let side = Side::try_from(i)?;
 
         let (output_dir, manifest_path) = if double_sided {
             let output_dir = output_dir.join(format!("side{i}"));
-            let manifest_path = make_manifest_path(path, &output_dir, Some(side))?;
+            let manifest_path = make_manifest_path(path, &output_dir, Some(&format!("side{i}")))?;
             (output_dir, manifest_path)
         } else {
             let manifest_path = make_manifest_path(path, output_dir, None)?;
@@ -146,6 +179,41 @@ fn extract_all<R: ImageReader>(
     Ok(())
 }
 
+// Each DDOS volume is extracted to its own directory named after the volume
+fn extract_volumes<R: Read + Seek>(
+    path: &Path,
+    output_dir: &Path,
+    opts: &ExtractOpts,
+    mut reader: DdosReader<R>,
+) -> Result<()> {
+    if opts.preserve {
+        bail!("--preserve is not supported for DDOS disc images")
+    }
+
+    let catalogues = Catalogue::from_ddos_reader(&mut reader, false)?;
+    for catalogue in catalogues {
+        let Some(volume) = catalogue.volume else {
+            bail!("catalogue has no volume")
+        };
+
+        let output_dir = output_dir.join(volume.to_string());
+        let manifest_path = make_manifest_path(path, &output_dir, Some(&volume.to_string()))?;
+        if !output_dir.exists() {
+            create_dir_all(&output_dir)?;
+        }
+
+        extract_single_side(
+            Side::Side0,
+            catalogue,
+            &manifest_path,
+            &output_dir,
+            opts,
+            &mut reader.volume_reader(volume),
+        )?;
+    }
+    Ok(())
+}
+
 fn extract_single_side<R: ImageReader>(
     side: Side,
     catalogue: Catalogue,
@@ -154,6 +222,16 @@ fn extract_single_side<R: ImageReader>(
     opts: &ExtractOpts,
     reader: &mut R,
 ) -> Result<()> {
+    let layout_entries = catalogue
+        .entries
+        .iter()
+        .map(|entry| LayoutEntry {
+            file_name: entry.descriptor.file_name.clone(),
+            directory: entry.descriptor.directory,
+            start_sector: entry.start_sector,
+        })
+        .collect::<Vec<_>>();
+
     let mut entries = catalogue.entries;
     entries.sort_by(|a, b| FileSpec::compare(&a.descriptor, &b.descriptor));
 
@@ -165,52 +243,196 @@ fn extract_single_side<R: ImageReader>(
         })
         .collect::<Result<Vec<_>>>()?;
 
-    let (inf_files, files) = if opts.inf {
+    let (inf_files, files) = describe_files(
+        output_dir,
+        opts,
+        extracted_files
+            .into_iter()
+            .map(|(entry, (content_path, file_type))| {
+                (&entry.descriptor, entry.length, content_path, file_type)
+            })
+            .collect(),
+    )?;
+
+    let layout = if opts.preserve {
+        let slack_path = add_extension(&strip_extension(manifest_path)?, SLACK_EXT)?;
+        let rel_slack_path = diff_paths(&slack_path, output_dir)
+            .ok_or_else(|| anyhow!("could not determine relative path"))?;
+        Some(Layout {
+            image_length: reader.stream_len(),
+            slack_path: rel_slack_path,
+            entries: layout_entries,
+        })
+    } else {
+        None
+    };
+
+    let manifest = Manifest {
+        version: Some(MANIFEST_VERSION),
+        disc_title: Some(catalogue.disc_title),
+        disc_size: catalogue.disc_size,
+        boot_option: catalogue.boot_option,
+        cycle_number: catalogue.cycle_number,
+        catalogue_flavour: catalogue.flavour,
+        inf_files,
+        files,
+        layout,
+    };
+
+    if let Some(layout) = &manifest.layout {
+        let slack = make_slack(side, output_dir, &manifest, reader)?;
+        let mut slack_file = open_for_write(
+            &layout.slack_path.absolutize_from(output_dir)?,
+            opts.overwrite,
+        )?;
+        slack_file.write_all(&slack.to_bytes()?)?;
+    }
+
+    let manifest_file = open_for_write(manifest_path, opts.overwrite)?;
+    serde_json::to_writer_pretty(manifest_file, &manifest)?;
+
+    Ok(())
+}
+
+// Extracted files are described either by .inf files or by entries in the
+// manifest
+fn describe_files(
+    output_dir: &Path,
+    opts: &ExtractOpts,
+    extracted_files: Vec<(&FileDescriptor, Length, PathBuf, FileType)>,
+) -> Result<(Vec<PathBuf>, Vec<ManifestFile>)> {
+    if opts.inf {
         let inf_files = extracted_files
             .into_iter()
-            .map(|(entry, (content_path, _))| {
+            .map(|(d, length, content_path, _)| {
                 let inf_path = add_extension(&content_path, INF_EXT)?;
-                make_inf_file(&inf_path, entry, opts.overwrite)?;
+                make_inf_file(&inf_path, d, length, opts.overwrite)?;
                 let rel_inf_path = diff_paths(inf_path, output_dir)
                     .ok_or_else(|| anyhow!("could not determine relative path"))?;
                 Ok(rel_inf_path)
             })
             .collect::<Result<Vec<_>>>()?;
-        (inf_files, Vec::new())
+        Ok((inf_files, Vec::new()))
     } else {
         let files = extracted_files
             .into_iter()
-            .map(|(entry, (_, file_type))| entry.descriptor.to_manifest_file(file_type))
+            .map(|(d, _, _, file_type)| d.to_manifest_file(file_type))
             .collect();
-        (Vec::new(), files)
-    };
+        Ok((Vec::new(), files))
+    }
+}
 
-    let manifest_file = open_for_write(manifest_path, opts.overwrite)?;
-    serde_json::to_writer_pretty(
-        manifest_file,
-        &Manifest {
-            version: Some(MANIFEST_VERSION),
-            disc_title: Some(catalogue.disc_title),
-            disc_size: catalogue.disc_size,
-            boot_option: catalogue.boot_option,
-            cycle_number: catalogue.cycle_number,
-            inf_files,
-            files,
-        },
-    )?;
+// Record the bytes of the original side that make cannot regenerate from the
+// extracted files and manifest: the content of sectors and parts of sectors
+// not used by any file and catalogue bytes such as padding and stale entries
+fn make_slack<R: ImageReader>(
+    side: Side,
+    output_dir: &Path,
+    manifest: &Manifest,
+    reader: &mut R,
+) -> Result<Slack> {
+    let mut original = vec![0; usize::try_from(reader.side_len(side))?];
+    reader.read_bytes(side, SectorSize::ZERO, &mut original)?;
+    let (regenerated, file_extents) = layout_side(output_dir, manifest)?;
+    Ok(Slack::diff(
+        &original,
+        &regenerated,
+        manifest.catalogue_flavour.catalogue_len(),
+        &file_extents,
+    ))
+}
+
+// Directories on the disc become directories on the host. ADFS names do not
+// fit in a manifest so every file gets an .inf file instead.
+fn extract_adfs<R: Read + Seek>(
+    output_dir: &Path,
+    opts: &ExtractOpts,
+    mut image: AdfsImage<R>,
+) -> Result<()> {
+    if opts.preserve {
+        bail!("--preserve is not supported for ADFS disc images")
+    }
+
+    create_dir_all(output_dir)?;
+    for file in image.walk()? {
+        // RISC OS maps . on the host to / in ADFS names
+        let host_path = file
+            .parents
+            .iter()
+            .chain([&file.entry.name])
+            .fold(output_dir.to_path_buf(), |p, name| {
+                p.join(name.replace('/', "."))
+            });
+
+        if file.entry.is_directory {
+            create_dir_all(&host_path)?;
+            continue;
+        }
+
+        let bytes = image.read_file(&file.entry)?;
+        write_content(&host_path, &bytes, opts)?;
+        make_adfs_inf_file(&add_extension(&host_path, INF_EXT)?, &file, opts.overwrite)?;
+    }
+    Ok(())
+}
+
+// Files are listed in tape order with default disc metadata so that make can
+// turn the manifest into either a disc image or a tape
+fn extract_from_uef(path: &Path, output_dir: &Path, opts: &ExtractOpts) -> Result<()> {
+    if opts.preserve {
+        bail!("--preserve is not supported for UEF files")
+    }
+
+    if !path.is_file() {
+        bail!("file {path} not found", path = path.display())
+    }
 
+    let tape_files = read_uef_file(path)?;
+    let descriptors = tape_files
+        .iter()
+        .map(TapeFile::to_file_descriptor)
+        .collect::<Vec<_>>();
+
+    create_dir_all(output_dir)?;
+    let extracted_files = tape_files
+        .iter()
+        .zip(&descriptors)
+        .map(|(file, d)| {
+            let content_path = output_dir.join(d.content_path());
+            let file_type = write_content(&content_path, &file.content, opts)?;
+            let length = Length::try_from(u32::try_from(file.content.len())?)?;
+            Ok((d, length, content_path, file_type))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let (inf_files, files) = describe_files(output_dir, opts, extracted_files)?;
+    let manifest = Manifest {
+        version: Some(MANIFEST_VERSION),
+        disc_title: None,
+        disc_size: DiscSize::default(),
+        boot_option: BootOption::default(),
+        cycle_number: CycleNumber::default(),
+        catalogue_flavour: CatalogueFlavour::default(),
+        inf_files,
+        files,
+        layout: None,
+    };
+
+    let manifest_file =
+        open_for_write(&make_manifest_path(path, output_dir, None)?, opts.overwrite)?;
+    serde_json::to_writer_pretty(manifest_file, &manifest)?;
     Ok(())
 }
 
-fn make_manifest_path(path: &Path, output_dir: &Path, side: Option<Side>) -> Result<PathBuf> {
+fn make_manifest_path(path: &Path, output_dir: &Path, suffix: Option<&str>) -> Result<PathBuf> {
     let mut file_name = String::new();
     file_name.push_str(
         path.file_stem()
             .and_then(OsStr::to_str)
             .ok_or_else(|| anyhow!("could not get file name from {path}", path = path.display()))?,
     );
-    match side {
-        Some(side) => write!(file_name, "-side{side}.json", side = u8::from(side))?,
+    match suffix {
+        Some(suffix) => write!(file_name, "-{suffix}.json")?,
         None => file_name.push_str(".json"),
     }
     Ok(output_dir.join(file_name))
@@ -229,23 +451,26 @@ fn extract_file<R: ImageReader>(
     reader.read_bytes(side, entry.start_sector, &mut bytes)?;
 
     let content_path = output_dir.join(d.content_path());
-    let mut content_file = open_for_write(&content_path, opts.overwrite)?;
-    content_file.write_all(&bytes)?;
+    let file_type = write_content(&content_path, &bytes, opts)?;
+    Ok((content_path, file_type))
+}
+
+fn write_content(content_path: &Path, bytes: &[u8], opts: &ExtractOpts) -> Result<FileType> {
+    let mut content_file = open_for_write(content_path, opts.overwrite)?;
+    content_file.write_all(bytes)?;
 
-    let is_bbc_basic = is_bbc_basic_file(&content_path)?;
+    let is_bbc_basic = is_bbc_basic_file(content_path)?;
     if opts.detokenize && is_bbc_basic {
         // Attempt to detokenize the file just in case it contains BASIC
         // Don't fail if it can't be detokenized
-        _ = detokenize_file(&content_path, opts.overwrite, opts.lossless);
+        _ = detokenize_file(content_path, opts.overwrite, opts.lossless);
     }
 
-    let file_type = FileType::Known(if is_bbc_basic {
+    Ok(FileType::Known(if is_bbc_basic {
         KnownFileType::BbcBasic
     } else {
         KnownFileType::Other
-    });
-
-    Ok((content_path, file_type))
+    }))
 }
 
 fn detokenize_file(input_path: &Path, overwrite: bool, lossless: bool) -> Result<()> {
@@ -264,3 +489,204 @@ fn detokenize_file(input_path: &Path, overwrite: bool, lossless: bool) -> Result
     input_file.read_to_end(&mut bytes)?;
     detokenize_source(output_file, &bytes, lossless)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{ExtractOpts, run_extract, run_make};
+    use crate::dfs::{
+        Address, BootOption, Catalogue, CatalogueEntry, CatalogueFlavour, CycleNumber, FileCount,
+        FileDescriptor, SECTOR_BYTES,
+    };
+    use anyhow::Result;
+    use rstest::rstest;
+    use std::fs::{File, read, write};
+    use std::io::{Read, Write};
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+    use zip::{ZipArchive, ZipWriter};
+
+    // Truncated image full of data not referenced by any file with $.ZED at
+    // sector 10 followed by unused sectors up to $.ALPHA at sector 20
+    fn make_preserve_image() -> Result<Vec<u8>> {
+        let sector_bytes = usize::from(SECTOR_BYTES);
+        let mut bytes = (0..45 * sector_bytes + 100)
+            .map(|i| u8::try_from((i * 7 + 3) % 251).unwrap())
+            .collect::<Vec<_>>();
+
+        let make_entry = |name: &str, directory: char, length: u32, start_sector: u16| {
+            Ok::<_, anyhow::Error>(CatalogueEntry::new(
+                FileDescriptor::new(
+                    name.parse()?,
+                    directory.try_into()?,
+                    directory == 'B',
+                    Address::try_from(0x1900)?,
+                    Address::try_from(0x8023)?,
+                ),
+                length.try_into()?,
+                start_sector.try_into()?,
+            ))
+        };
+
+        // Catalogue in descending order of start sector but not alphabetical
+        let entries = vec![
+            make_entry("ALPHA", '$', 0x100, 20)?,
+            make_entry("ZED", '$', 0x12c, 10)?,
+            make_entry("PROG", 'B', 0x0a, 2)?,
+        ];
+        let file_count: FileCount = 3u8.try_into()?;
+        let catalogue = Catalogue::new(
+            "GAME".parse()?,
+            CycleNumber::new(7)?,
+            file_count.into(),
+            BootOption::None,
+            400u16.try_into()?,
+            CatalogueFlavour::Acorn,
+            entries,
+        );
+        bytes[0..2 * sector_bytes].fill(0);
+        catalogue.write_to_catalogue(&mut bytes)?;
+
+        // Space-padded title, NUL-padded file name and a stale entry
+        bytes[4..8].fill(b' ');
+        bytes[sector_bytes..sector_bytes + 4].fill(b' ');
+        bytes[0x10 + 3..0x10 + 7].fill(0);
+        bytes[0x20..0x28].copy_from_slice(b"OLDFILE$");
+
+        Ok(bytes)
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn preserve_round_trip(#[case] inf: bool) -> Result<()> {
+        let bytes = make_preserve_image()?;
+        let dir = tempdir()?;
+        let image_path = dir.path().join("game.ssd");
+        write(&image_path, &bytes)?;
+
+        let output_dir = dir.path().join("game");
+        run_extract(
+            &image_path,
+            &output_dir,
+            &ExtractOpts {
+                overwrite: false,
+                detokenize: false,
+                lossless: false,
+                inf,
+                preserve: true,
+                format: None,
+            },
+        )?;
+
+        let rebuilt_path = dir.path().join("rebuilt.ssd");
+        run_make(
+            &output_dir.join("game.json"),
+            None,
+            &rebuilt_path,
+            false,
+            None,
+        )?;
+
+        assert_eq!(bytes, read(&rebuilt_path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn preserve_edited_file() -> Result<()> {
+        let sector_bytes = usize::from(SECTOR_BYTES);
+        let bytes = make_preserve_image()?;
+
+        let dir = tempdir()?;
+        let image_path = dir.path().join("game.ssd");
+        write(&image_path, &bytes)?;
+
+        let output_dir = dir.path().join("game");
+        run_extract(
+            &image_path,
+            &output_dir,
+            &ExtractOpts {
+                overwrite: false,
+                detokenize: false,
+                lossless: false,
+                inf: false,
+                preserve: true,
+                format: None,
+            },
+        )?;
+
+        // Grow $.ZED into the unused sectors that follow it
+        let content = vec![0xaa; 3 * sector_bytes];
+        write(output_dir.join("ZED"), &content)?;
+
+        let rebuilt_path = dir.path().join("rebuilt.ssd");
+        run_make(
+            &output_dir.join("game.json"),
+            None,
+            &rebuilt_path,
+            false,
+            None,
+        )?;
+
+        let rebuilt = read(&rebuilt_path)?;
+        assert_eq!(bytes.len(), rebuilt.len());
+        assert_eq!(
+            content,
+            rebuilt[10 * sector_bytes..13 * sector_bytes].to_vec()
+        );
+        assert_eq!([0x00, 0x03], rebuilt[0x114..0x116]);
+
+        // Everything else is as it was, including unused sectors and
+        // catalogue padding
+        assert_eq!(bytes[..0x114], rebuilt[..0x114]);
+        assert_eq!(
+            bytes[0x116..10 * sector_bytes],
+            rebuilt[0x116..10 * sector_bytes]
+        );
+        assert_eq!(bytes[13 * sector_bytes..], rebuilt[13 * sector_bytes..]);
+        Ok(())
+    }
+
+    #[test]
+    fn zip_round_trip() -> Result<()> {
+        let dir = tempdir()?;
+        write(dir.path().join("HELLO"), b"hello")?;
+        write(
+            dir.path().join("game.json"),
+            r#"{"discTitle": "GAME", "files": [{"fileName": "HELLO", "directory": "$", "locked": false, "loadAddress": "&1900", "executionAddress": "&1900", "contentPath": "HELLO", "type": "other"}]}"#,
+        )?;
+
+        let zip_path = dir.path().join("game.zip");
+        run_make(&dir.path().join("game.json"), None, &zip_path, false, None)?;
+        let mut archive = ZipArchive::new(File::open(&zip_path)?)?;
+        let mut image_bytes = Vec::new();
+        archive.by_name("game.ssd")?.read_to_end(&mut image_bytes)?;
+
+        let multi_path = dir.path().join("multi.zip");
+        let mut writer = ZipWriter::new(File::create(&multi_path)?);
+        for name in ["a.ssd", "discs/b.ssd", "readme.txt"] {
+            writer.start_file(name, SimpleFileOptions::default())?;
+            writer.write_all(&image_bytes)?;
+        }
+        writer.finish()?;
+
+        let output_dir = dir.path().join("multi");
+        run_extract(
+            &multi_path,
+            &output_dir,
+            &ExtractOpts {
+                overwrite: false,
+                detokenize: false,
+                lossless: false,
+                inf: false,
+                preserve: false,
+                format: None,
+            },
+        )?;
+
+        for p in ["a/a.json", "a/HELLO", "discs/b/b.json", "discs/b/HELLO"] {
+            assert!(output_dir.join(p).is_file(), "{p} not extracted");
+        }
+        assert!(!output_dir.join("readme").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use crate::bbc_basic::{lint_program, program_lines};
+use anyhow::{Result, bail};
+use std::fs::read;
+use std::path::Path;
+
+pub fn run_lint(path: &Path) -> Result<()> {
+    let lines = program_lines(&read(path)?)?;
+    let lines = lines
+        .iter()
+        .map(|(line_number, content)| (*line_number, content.as_slice()))
+        .collect::<Vec<_>>();
+    let issues = lint_program(&lines);
+
+    for issue in &issues {
+        println!("{issue}");
+    }
+
+    if !issues.is_empty() {
+        bail!(
+            "found {issue_count} problem(s) in {path}",
+            issue_count = issues.len(),
+            path = path.display()
+        )
+    }
+
+    Ok(())
+}
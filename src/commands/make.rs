@@ -1,20 +1,86 @@
 use crate::constants::MANIFEST_VERSION;
+use crate::dfs::BootOption;
 use crate::metadata::Manifest;
-use crate::ops::new_image_file;
+use crate::ops::{create_image_file, new_dsd_image_file, new_image_file, new_uef_file};
+use crate::uef::UEF_EXT;
 use anyhow::{Result, anyhow, bail};
+use std::ffi::OsStr;
 use std::fs::File;
+use std::io::copy;
 use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
 
 pub fn run_make(
     path: &Path,
     side_1_path: Option<&PathBuf>,
     output_path: &Path,
     overwrite: bool,
+    boot_option: Option<BootOption>,
 ) -> Result<()> {
-    if side_1_path.is_some() {
-        todo!(".dsd not implemented yet")
+    let ext = output_path.extension().and_then(OsStr::to_str);
+    if ext.is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
+        return make_zip(path, side_1_path, output_path, overwrite, boot_option);
     }
 
+    let (manifest_dir, manifest) = read_manifest(path, boot_option)?;
+    if ext.is_some_and(|ext| ext.eq_ignore_ascii_case(UEF_EXT)) {
+        if side_1_path.is_some() {
+            bail!("a tape has only one side: pass a single manifest")
+        }
+        return new_uef_file(output_path, overwrite, manifest_dir, &manifest);
+    }
+
+    match side_1_path {
+        Some(side_1_path) => {
+            let (side_1_manifest_dir, side_1_manifest) = read_manifest(side_1_path, boot_option)?;
+            new_dsd_image_file(
+                output_path,
+                overwrite,
+                (manifest_dir, &manifest),
+                (side_1_manifest_dir, &side_1_manifest),
+            )
+        }
+        None => new_image_file(output_path, overwrite, manifest_dir, &manifest),
+    }
+}
+
+// The image is built in a temporary directory and stored in the archive under
+// the archive's name, e.g. game.zip contains game.ssd
+fn make_zip(
+    path: &Path,
+    side_1_path: Option<&PathBuf>,
+    output_path: &Path,
+    overwrite: bool,
+    boot_option: Option<BootOption>,
+) -> Result<()> {
+    let stem = output_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .ok_or_else(|| {
+            anyhow!(
+                "could not get file name from {path}",
+                path = output_path.display()
+            )
+        })?;
+    let image_name = format!(
+        "{stem}.{ext}",
+        ext = if side_1_path.is_some() { "dsd" } else { "ssd" }
+    );
+
+    let temp_dir = tempdir()?;
+    let image_path = temp_dir.path().join(&image_name);
+    run_make(path, side_1_path, &image_path, false, boot_option)?;
+
+    let mut writer = ZipWriter::new(create_image_file(output_path, overwrite)?);
+    writer.start_file(image_name, SimpleFileOptions::default())?;
+    copy(&mut File::open(&image_path)?, &mut writer)?;
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn read_manifest(path: &Path, boot_option: Option<BootOption>) -> Result<(&Path, Manifest)> {
     let manifest_dir = path.parent().ok_or_else(|| {
         anyhow!(
             "cannot get parent directory from {path}",
@@ -23,12 +89,16 @@ pub fn run_make(
     })?;
 
     let f = File::open(path)?;
-    let manifest = serde_json::from_reader::<_, Manifest>(f)?;
+    let mut manifest = serde_json::from_reader::<_, Manifest>(f)?;
     if let Some(version) = manifest.version
         && version != MANIFEST_VERSION
     {
         bail!("unsupported manifest version {version}");
     }
 
-    new_image_file(output_path, overwrite, manifest_dir, manifest)
+    if let Some(boot_option) = boot_option {
+        manifest.boot_option = boot_option;
+    }
+
+    Ok((manifest_dir, manifest))
 }
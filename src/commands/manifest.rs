@@ -1,6 +1,6 @@
 use crate::bbc_basic::is_bbc_basic_file;
 use crate::constants::{INF_EXT, MANIFEST_VERSION};
-use crate::dfs::{Address, BootOption, CycleNumber, DfsPath, DiscSize, FileSpec};
+use crate::dfs::{Address, BootOption, CatalogueFlavour, CycleNumber, DfsPath, DiscSize, FileSpec};
 use crate::metadata::{File, FileType, KnownFileType, Manifest};
 use crate::path_util::{add_extension, has_extension, strip_extension};
 use crate::util::open_for_write;
@@ -116,8 +116,10 @@ pub fn run_manifest(dir: &Path, output_path: Option<&PathBuf>, overwrite: bool)
             disc_size: DiscSize::default(),
             boot_option: BootOption::default(),
             cycle_number: CycleNumber::default(),
+            catalogue_flavour: CatalogueFlavour::default(),
             inf_files: inf_files.into_iter().map(|x| x.1).collect(),
             files,
+            layout: None,
         },
     )?;
 
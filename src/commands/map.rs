@@ -0,0 +1,347 @@
+use crate::ddos_reader::DdosReader;
+use crate::dfs::{
+    Catalogue, CatalogueFlavour, DD_SECTORS_PER_TRACK, FileSpec, SECTOR_BYTES, SECTORS_PER_TRACK,
+    SectorSize, Side, get_file_sector_count,
+};
+use crate::dsd_reader::DsdReader;
+use crate::image_format::ImageFormat;
+use crate::image_reader::ImageReader;
+use crate::ssd_reader::SsdReader;
+use anyhow::{Result, bail};
+use std::fs::File;
+use std::path::Path;
+
+// Symbols identifying the files on a side: enough for a Watford DFS disc
+const FILE_SYMBOLS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Owner {
+    Catalogue,
+    File(usize),
+}
+
+// Owners of every sector of a side, with files numbered in FileSpec order
+struct DiscMap {
+    names: Vec<String>,
+    owners: Vec<Vec<Owner>>,
+    // Free sectors that do not look blank, e.g. left by a deleted file
+    orphaned: Vec<bool>,
+    // Files running past the end of the disc
+    overruns: Vec<usize>,
+    sectors_per_track: usize,
+}
+
+impl DiscMap {
+    fn new<F>(catalogue: &Catalogue, mut is_blank: F) -> Result<Self>
+    where
+        F: FnMut(u16) -> Result<bool>,
+    {
+        let disc_size = u16::from(catalogue.disc_size);
+        let mut owners = vec![Vec::new(); usize::from(disc_size)];
+        for owner in owners
+            .iter_mut()
+            .take(usize::from(catalogue.flavour.start_sector()))
+        {
+            owner.push(Owner::Catalogue);
+        }
+
+        let mut entries = catalogue.entries.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| FileSpec::compare(&a.descriptor, &b.descriptor));
+
+        let mut names = Vec::with_capacity(entries.len());
+        let mut overruns = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let d = &entry.descriptor;
+            names.push(format!(
+                "{directory}.{file_name}",
+                directory = d.directory,
+                file_name = d.file_name
+            ));
+
+            let start = usize::from(entry.start_sector);
+            let end = start + usize::from(get_file_sector_count(entry.length)?);
+            if end > owners.len() {
+                overruns.push(i);
+            }
+            for owner in owners.iter_mut().take(end).skip(start) {
+                owner.push(Owner::File(i));
+            }
+        }
+
+        let orphaned = owners
+            .iter()
+            .zip(0..)
+            .map(|(owner, sector)| Ok(owner.is_empty() && !is_blank(sector)?))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            names,
+            owners,
+            orphaned,
+            overruns,
+            sectors_per_track: if catalogue.flavour == CatalogueFlavour::Ddos {
+                DD_SECTORS_PER_TRACK
+            } else {
+                SECTORS_PER_TRACK
+            },
+        })
+    }
+
+    fn symbol(&self, sector: usize) -> char {
+        match self.owners[sector].as_slice() {
+            [] if self.orphaned[sector] => '?',
+            [] => '.',
+            [Owner::Catalogue] => '#',
+            [Owner::File(i)] => FILE_SYMBOLS.get(*i).map_or('*', |&b| char::from(b)),
+            _ => '!',
+        }
+    }
+
+    // Runs of consecutive sectors for which the predicate holds
+    fn runs<P>(&self, predicate: P) -> Vec<(usize, usize)>
+    where
+        P: Fn(usize) -> bool,
+    {
+        let mut runs = Vec::new();
+        let mut start = None;
+        for sector in 0..=self.owners.len() {
+            match (start, sector < self.owners.len() && predicate(sector)) {
+                (None, true) => start = Some(sector),
+                (Some(s), false) => {
+                    runs.push((s, sector - s));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        runs
+    }
+
+    fn free_extents(&self) -> Vec<(usize, usize)> {
+        self.runs(|sector| self.owners[sector].is_empty())
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        let columns = (0..self.sectors_per_track)
+            .map(|c| char::from(b"0123456789ABCDEFGHIJ"[c]))
+            .collect::<String>();
+        lines.push(format!("     {columns}"));
+        for (row, sectors) in (0..self.owners.len())
+            .collect::<Vec<_>>()
+            .chunks(self.sectors_per_track)
+            .enumerate()
+        {
+            lines.push(format!(
+                "{start:03X}  {symbols}",
+                start = row * self.sectors_per_track,
+                symbols = sectors.iter().map(|&s| self.symbol(s)).collect::<String>()
+            ));
+        }
+        lines.push(String::from(
+            "# catalogue  . free  ? free but not blank  ! more than one owner",
+        ));
+
+        lines.push(String::from("Files:"));
+        for (i, name) in self.names.iter().enumerate() {
+            let symbol = FILE_SYMBOLS.get(i).map_or('*', |&b| char::from(b));
+            let runs = self.runs(|sector| self.owners[sector].contains(&Owner::File(i)));
+            let sectors = if runs.is_empty() {
+                String::from("no sectors")
+            } else {
+                describe_runs(&runs)
+            };
+            lines.push(format!("  {symbol} {name:<9} {sectors}"));
+        }
+
+        lines.push(String::from("Free extents:"));
+        let free_extents = self.free_extents();
+        for (start, count) in &free_extents {
+            lines.push(format!("  {run}", run = format_run(*start, *count)));
+        }
+        let largest = free_extents
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or_default();
+        lines.push(format!(
+            "Largest free extent: {largest} sectors ({bytes} bytes) of {total} free sectors",
+            bytes = largest * usize::from(SECTOR_BYTES),
+            total = free_extents.iter().map(|(_, count)| count).sum::<usize>()
+        ));
+
+        let mut problems = Vec::new();
+        for (start, count) in self.runs(|sector| self.owners[sector].len() > 1) {
+            let owners = self.owners[start]
+                .iter()
+                .map(|owner| match owner {
+                    Owner::Catalogue => String::from("catalogue"),
+                    Owner::File(i) => self.names[*i].clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            problems.push(format!(
+                "  {run} used by more than one owner: {owners}",
+                run = format_run(start, count)
+            ));
+        }
+        for &i in &self.overruns {
+            problems.push(format!(
+                "  {name} runs past the end of the disc",
+                name = self.names[i]
+            ));
+        }
+        for (start, count) in self.runs(|sector| self.orphaned[sector]) {
+            problems.push(format!(
+                "  {run} free but not blank",
+                run = format_run(start, count)
+            ));
+        }
+        if !problems.is_empty() {
+            lines.push(String::from("Problems:"));
+            lines.extend(problems);
+        }
+
+        lines
+    }
+}
+
+// Files split by another file's sectors are listed run by run
+fn describe_runs(runs: &[(usize, usize)]) -> String {
+    runs.iter()
+        .map(|(start, count)| format_run(*start, *count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_run(start: usize, count: usize) -> String {
+    format!(
+        "&{start:03X}-&{end:03X} ({count} {noun})",
+        end = start + count - 1,
+        noun = if count == 1 { "sector" } else { "sectors" }
+    )
+}
+
+pub fn run_map(path: &Path, format: Option<ImageFormat>) -> Result<()> {
+    let mut f = File::open(path)?;
+    let format = ImageFormat::detect(&mut f, path, format)?;
+    match format {
+        ImageFormat::Ssd => map_image(&mut SsdReader::new(f, SECTOR_BYTES)?),
+        ImageFormat::Dsd => map_image(&mut DsdReader::new(
+            f,
+            SECTOR_BYTES,
+            format.sectors_per_track(),
+        )?),
+        ImageFormat::Dds | ImageFormat::Ddd => {
+            let mut reader = DdosReader::new(f, SECTOR_BYTES, format.sides())?;
+            for catalogue in Catalogue::from_ddos_reader(&mut reader, true)? {
+                let Some(volume) = catalogue.volume else {
+                    bail!("catalogue has no volume")
+                };
+                println!("Volume {volume}");
+                map_side(&catalogue, Side::Side0, &mut reader.volume_reader(volume))?;
+            }
+            Ok(())
+        }
+        ImageFormat::Adfs => bail!("mapping ADFS disc images is not supported"),
+    }
+}
+
+fn map_image<R: ImageReader>(reader: &mut R) -> Result<()> {
+    let catalogues = Catalogue::from_image_reader(reader, true)?;
+    let double_sided = catalogues.len() > 1;
+    for (i, catalogue) in catalogues.into_iter().enumerate() {
+        if double_sided {
+            println!("Side {i}");
+        }
+        map_side(&catalogue, Side::try_from(i)?, reader)?;
+    }
+    Ok(())
+}
+
+fn map_side<R: ImageReader>(catalogue: &Catalogue, side: Side, reader: &mut R) -> Result<()> {
+    let side_len = reader.side_len(side);
+    let mut bytes = vec![0; usize::from(SECTOR_BYTES)];
+
+    // Sectors missing from a truncated image count as blank, as do sectors
+    // filled with a single value such as the &E5 written when formatting
+    let map = DiscMap::new(catalogue, |sector| {
+        if (u64::from(sector) + 1) * u64::from(SECTOR_BYTES) > side_len {
+            return Ok(true);
+        }
+        reader.read_bytes(side, SectorSize::try_from(sector)?, &mut bytes)?;
+        Ok(bytes.iter().all(|&b| b == bytes[0]))
+    })?;
+
+    for line in map.lines() {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiscMap;
+    use crate::dfs::{
+        Address, BootOption, Catalogue, CatalogueEntry, CatalogueFlavour, CycleNumber,
+        FileDescriptor, FileOffset,
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn map() -> Result<()> {
+        let make_entry = |name: &str, length: u32, start_sector: u16| {
+            Ok::<_, anyhow::Error>(CatalogueEntry::new(
+                FileDescriptor::new(
+                    name.parse()?,
+                    '$'.try_into()?,
+                    false,
+                    Address::ZERO,
+                    Address::ZERO,
+                ),
+                length.try_into()?,
+                start_sector.try_into()?,
+            ))
+        };
+        let catalogue = Catalogue::new(
+            "".parse()?,
+            CycleNumber::default(),
+            FileOffset::new(0)?,
+            BootOption::None,
+            20u16.try_into()?,
+            CatalogueFlavour::Acorn,
+            vec![
+                make_entry("LAST", 0x300, 18)?,
+                make_entry("BIG", 0x400, 8)?,
+                make_entry("SMALL", 0x200, 10)?,
+                make_entry("FIRST", 0x201, 2)?,
+            ],
+        );
+
+        let map = DiscMap::new(&catalogue, |sector| Ok(sector != 6))?;
+        assert_eq!(
+            vec![
+                "     0123456789",
+                "000  ##BBB.?.AA",
+                "00A  !!......CC",
+                "# catalogue  . free  ? free but not blank  ! more than one owner",
+                "Files:",
+                "  A $.BIG     &008-&00B (4 sectors)",
+                "  B $.FIRST   &002-&004 (3 sectors)",
+                "  C $.LAST    &012-&013 (2 sectors)",
+                "  D $.SMALL   &00A-&00B (2 sectors)",
+                "Free extents:",
+                "  &005-&007 (3 sectors)",
+                "  &00C-&011 (6 sectors)",
+                "Largest free extent: 6 sectors (1536 bytes) of 9 free sectors",
+                "Problems:",
+                "  &00A-&00B (2 sectors) used by more than one owner: $.BIG, $.SMALL",
+                "  $.LAST runs past the end of the disc",
+                "  &006-&006 (1 sector) free but not blank",
+            ],
+            map.lines()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+use crate::commands::{ExtractOpts, extract_all};
+use crate::dfs::{Catalogue, SECTOR_BYTES};
+use crate::image_format::ImageFormat;
+use crate::mmb::{DiscStatus, MMB_FILE_BYTES, MmbImage};
+use crate::ssd_reader::SsdReader;
+use anyhow::{Result, anyhow, bail};
+use std::ffi::OsStr;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions, read};
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::Path;
+
+pub fn run_mmb_list(mmb_path: &Path) -> Result<()> {
+    let mmb = MmbImage::new(File::open(mmb_path)?)?;
+    for entry in mmb
+        .entries
+        .iter()
+        .filter(|entry| entry.status.is_formatted())
+    {
+        // Mark the drives the slot is loaded into at boot
+        let mut drives = String::new();
+        for (drive, slot) in mmb.drives.iter().enumerate() {
+            if *slot == entry.slot {
+                write!(drives, " :{drive}")?;
+            }
+        }
+        println!(
+            "{slot:>3} {title:<12} {status}{drives}",
+            slot = entry.slot,
+            title = entry.title,
+            status = entry.status
+        );
+    }
+    Ok(())
+}
+
+pub fn run_mmb_extract(
+    mmb_path: &Path,
+    slot: u16,
+    output_dir: &Path,
+    opts: &ExtractOpts,
+) -> Result<()> {
+    let mut mmb = MmbImage::new(File::open(mmb_path)?)?;
+    let bytes = mmb.read_slot(slot)?;
+
+    // Manifest is named after the MMB file and slot, e.g. BEEB-12.json
+    let stem = mmb_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .ok_or_else(|| {
+            anyhow!(
+                "could not get file name from {path}",
+                path = mmb_path.display()
+            )
+        })?;
+    let path = mmb_path.with_file_name(format!("{stem}-{slot}.ssd"));
+    extract_all(
+        &path,
+        output_dir,
+        opts,
+        SsdReader::new(Cursor::new(bytes), SECTOR_BYTES)?,
+    )
+}
+
+// Only single-sided DFS images fit in a slot, which is where make's .ssd
+// output comes in
+pub fn run_mmb_insert(
+    mmb_path: &Path,
+    image_path: &Path,
+    slot: Option<u16>,
+    overwrite: bool,
+) -> Result<()> {
+    let bytes = read(image_path)?;
+    let mut reader = Cursor::new(&bytes);
+    if ImageFormat::detect(&mut reader, image_path, None)? != ImageFormat::Ssd {
+        bail!(
+            "disc image {path} is not a single-sided DFS disc image",
+            path = image_path.display()
+        )
+    }
+    let catalogues =
+        Catalogue::from_image_reader(&mut SsdReader::new(reader, SECTOR_BYTES)?, false)?;
+    let Some(catalogue) = catalogues.first() else {
+        bail!("disc image has no catalogue")
+    };
+    let title = catalogue.disc_title.to_string();
+
+    let slot = if mmb_path.exists() {
+        insert_disc(
+            &mut open_mmb_for_write(mmb_path)?,
+            slot,
+            overwrite,
+            &title,
+            &bytes,
+        )?
+    } else {
+        // Try the insertion on an empty MMB in memory first so that a file of
+        // over 80 MB is not left behind if it fails
+        let slot = insert_disc(
+            &mut MmbImage::create(Cursor::new(Vec::new()))?,
+            slot,
+            overwrite,
+            &title,
+            &bytes,
+        )?;
+        let f = File::create_new(mmb_path)?;
+        f.set_len(MMB_FILE_BYTES)?;
+        insert_disc(
+            &mut MmbImage::create(f)?,
+            Some(slot),
+            overwrite,
+            &title,
+            &bytes,
+        )?
+    };
+    println!("{slot}");
+    Ok(())
+}
+
+pub fn run_mmb_remove(mmb_path: &Path, slot: u16) -> Result<()> {
+    remove_disc(&mut open_mmb_for_write(mmb_path)?, slot)
+}
+
+pub fn run_mmb_lock(mmb_path: &Path, slot: u16, lock: bool) -> Result<()> {
+    lock_disc(&mut open_mmb_for_write(mmb_path)?, slot, lock)
+}
+
+// Write the disc to the given slot or the first free one, returning the slot
+fn insert_disc<F: Read + Write + Seek>(
+    mmb: &mut MmbImage<F>,
+    slot: Option<u16>,
+    overwrite: bool,
+    title: &str,
+    bytes: &[u8],
+) -> Result<u16> {
+    let slot = match slot {
+        Some(slot) => slot,
+        None => mmb
+            .first_free_slot()
+            .ok_or_else(|| anyhow!("MMB file has no free slots"))?,
+    };
+
+    match mmb.entry(slot)?.status {
+        DiscStatus::Locked => bail!("disc in MMB slot {slot} is locked"),
+        DiscStatus::Unlocked if !overwrite => {
+            bail!("MMB slot {slot} is in use: pass --overwrite to replace its disc")
+        }
+        _ => {}
+    }
+
+    mmb.write_slot(slot, title, bytes)?;
+    Ok(slot)
+}
+
+fn remove_disc<F: Read + Write + Seek>(mmb: &mut MmbImage<F>, slot: u16) -> Result<()> {
+    match mmb.entry(slot)?.status {
+        DiscStatus::Locked => bail!("disc in MMB slot {slot} is locked"),
+        DiscStatus::Unformatted | DiscStatus::Invalid => {
+            bail!("MMB slot {slot} does not contain a disc")
+        }
+        DiscStatus::Unlocked => mmb.set_status(slot, DiscStatus::Unformatted),
+    }
+}
+
+fn lock_disc<F: Read + Write + Seek>(mmb: &mut MmbImage<F>, slot: u16, lock: bool) -> Result<()> {
+    if !mmb.entry(slot)?.status.is_formatted() {
+        bail!("MMB slot {slot} does not contain a disc")
+    }
+    mmb.set_status(
+        slot,
+        if lock {
+            DiscStatus::Locked
+        } else {
+            DiscStatus::Unlocked
+        },
+    )
+}
+
+fn open_mmb_for_write(mmb_path: &Path) -> Result<MmbImage<File>> {
+    MmbImage::new(OpenOptions::new().read(true).write(true).open(mmb_path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_disc, lock_disc, remove_disc, run_mmb_insert};
+    use crate::dfs::{
+        BootOption, Catalogue, CatalogueFlavour, CycleNumber, FileOffset, SECTOR_BYTES,
+    };
+    use crate::mmb::{DiscStatus, MMB_SLOT_BYTES, MmbImage};
+    use anyhow::Result;
+    use std::fs::write;
+    use std::io::{Cursor, Read, Seek};
+    use tempfile::tempdir;
+
+    fn make_mmb() -> Result<MmbImage<Cursor<Vec<u8>>>> {
+        let mut mmb = MmbImage::create(Cursor::new(Vec::new()))?;
+        insert_disc(&mut mmb, None, false, "GAMES", &[1; 10])?;
+        insert_disc(&mut mmb, None, false, "LOCKED", &[2; 10])?;
+        lock_disc(&mut mmb, 1, true)?;
+        Ok(mmb)
+    }
+
+    fn statuses<F: Read + Seek>(mmb: &MmbImage<F>) -> Vec<DiscStatus> {
+        mmb.entries[..3].iter().map(|entry| entry.status).collect()
+    }
+
+    #[test]
+    fn insert() -> Result<()> {
+        let mut mmb = make_mmb()?;
+        assert_eq!(
+            vec![
+                DiscStatus::Unlocked,
+                DiscStatus::Locked,
+                DiscStatus::Unformatted
+            ],
+            statuses(&mmb)
+        );
+        assert_eq!(2, insert_disc(&mut mmb, None, false, "NEW", &[3; 10])?);
+        assert_eq!("NEW", mmb.entry(2)?.title);
+
+        // Slot in use needs --overwrite and a locked one is refused outright
+        assert!(insert_disc(&mut mmb, Some(0), false, "NEW", &[3; 10]).is_err());
+        assert!(insert_disc(&mut mmb, Some(1), true, "NEW", &[3; 10]).is_err());
+        assert!(insert_disc(&mut mmb, Some(511), false, "NEW", &[3; 10]).is_err());
+        assert_eq!(&[2; 10], &mmb.read_slot(1)?[..10]);
+
+        assert_eq!(0, insert_disc(&mut mmb, Some(0), true, "NEW", &[3; 10])?);
+        assert_eq!(&[3; 10], &mmb.read_slot(0)?[..10]);
+        Ok(())
+    }
+
+    #[test]
+    fn remove() -> Result<()> {
+        let mut mmb = make_mmb()?;
+        assert!(remove_disc(&mut mmb, 1).is_err());
+        assert!(remove_disc(&mut mmb, 2).is_err());
+        remove_disc(&mut mmb, 0)?;
+        assert_eq!(
+            vec![
+                DiscStatus::Unformatted,
+                DiscStatus::Locked,
+                DiscStatus::Unformatted
+            ],
+            statuses(&mmb)
+        );
+        assert!(remove_disc(&mut mmb, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn lock() -> Result<()> {
+        let mut mmb = make_mmb()?;
+        lock_disc(&mut mmb, 0, true)?;
+        lock_disc(&mut mmb, 1, false)?;
+        assert!(lock_disc(&mut mmb, 2, true).is_err());
+        assert_eq!(
+            vec![
+                DiscStatus::Locked,
+                DiscStatus::Unlocked,
+                DiscStatus::Unformatted
+            ],
+            statuses(&mmb)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn insert_invalid_slot_creates_no_file() -> Result<()> {
+        let mut bytes = vec![0; usize::try_from(MMB_SLOT_BYTES)?];
+        Catalogue::new(
+            "DISC".parse()?,
+            CycleNumber::default(),
+            FileOffset::new(0)?,
+            BootOption::None,
+            800u16.try_into()?,
+            CatalogueFlavour::Acorn,
+            Vec::new(),
+        )
+        .write_to_catalogue(&mut bytes[..usize::from(SECTOR_BYTES) * 2])?;
+
+        let dir = tempdir()?;
+        let image_path = dir.path().join("disc.ssd");
+        write(&image_path, &bytes)?;
+        let mmb_path = dir.path().join("BEEB.MMB");
+
+        assert!(run_mmb_insert(&mmb_path, &image_path, Some(511), false).is_err());
+        assert!(!mmb_path.exists());
+        Ok(())
+    }
+}
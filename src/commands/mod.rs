@@ -1,15 +1,45 @@
+mod access;
+mod add;
+mod check;
+mod compact;
+mod crunch;
+mod delete;
 mod detokenize;
+mod diff;
 mod extract;
+mod lint;
 mod make;
 mod manifest;
+mod map;
+mod mmb;
 mod new;
+mod rename;
+mod renumber;
+mod set_addr;
 mod show;
+mod textconv;
 mod tokenize;
+mod xref;
 
+pub use access::*;
+pub use add::*;
+pub use check::*;
+pub use compact::*;
+pub use crunch::*;
+pub use delete::*;
 pub use detokenize::*;
+pub use diff::*;
 pub use extract::*;
+pub use lint::*;
 pub use make::*;
 pub use manifest::*;
+pub use map::*;
+pub use mmb::*;
 pub use new::*;
+pub use rename::*;
+pub use renumber::*;
+pub use set_addr::*;
 pub use show::*;
+pub use textconv::*;
 pub use tokenize::*;
+pub use xref::*;
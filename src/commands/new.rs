@@ -1,28 +1,45 @@
-use crate::dfs::{BootOption, CycleNumber, DiscSize};
+use crate::dfs::{BootOption, CatalogueFlavour, CycleNumber, DiscSize};
+use crate::image_format::ImageFormat;
 use crate::metadata::Manifest;
-use crate::ops::new_image_file;
-use anyhow::{Result, anyhow};
+use crate::ops::{new_dsd_image_file, new_image_file};
+use anyhow::{Result, anyhow, bail};
 use std::path::Path;
 
-pub fn run_new(output_path: &Path, disc_size: Option<DiscSize>, overwrite: bool) -> Result<()> {
+// Format is taken from the output file extension if not specified, creating
+// a single-sided image if the extension is not recognized
+pub fn run_new(
+    output_path: &Path,
+    disc_size: Option<DiscSize>,
+    overwrite: bool,
+    boot_option: Option<BootOption>,
+    format: Option<ImageFormat>,
+) -> Result<()> {
     let dir = output_path.parent().ok_or_else(|| {
         anyhow!(
             "could not get directory from path {path}",
             path = output_path.display()
         )
     })?;
-    new_image_file(
-        output_path,
-        overwrite,
-        dir,
-        Manifest {
-            version: None,
-            disc_title: None,
-            disc_size: disc_size.unwrap_or_default(),
-            boot_option: BootOption::None,
-            cycle_number: CycleNumber::default(),
-            inf_files: Vec::new(),
-            files: Vec::new(),
-        },
-    )
+    let manifest = Manifest {
+        version: None,
+        disc_title: None,
+        disc_size: disc_size.unwrap_or_default(),
+        boot_option: boot_option.unwrap_or_default(),
+        cycle_number: CycleNumber::default(),
+        catalogue_flavour: CatalogueFlavour::default(),
+        inf_files: Vec::new(),
+        files: Vec::new(),
+        layout: None,
+    };
+    match format
+        .or_else(|| ImageFormat::from_path(output_path))
+        .unwrap_or(ImageFormat::Ssd)
+    {
+        ImageFormat::Ssd => new_image_file(output_path, overwrite, dir, &manifest),
+        ImageFormat::Dsd => {
+            new_dsd_image_file(output_path, overwrite, (dir, &manifest), (dir, &manifest))
+        }
+        ImageFormat::Dds | ImageFormat::Ddd => bail!("creating DDOS disc images is not supported"),
+        ImageFormat::Adfs => bail!("creating ADFS disc images is not supported"),
+    }
 }
@@ -0,0 +1,27 @@
+use crate::dfs::{DfsPath, Side};
+use crate::image_format::ImageFormat;
+use crate::ops::{edit_image_file, find_unlocked_file};
+use anyhow::{Result, bail};
+use std::path::Path;
+
+pub fn run_rename(
+    image_path: &Path,
+    name: &DfsPath,
+    new_name: &DfsPath,
+    side: Side,
+    format: Option<ImageFormat>,
+) -> Result<()> {
+    edit_image_file(image_path, format, side, |catalogue, _, _| {
+        let index = find_unlocked_file(catalogue, name)?;
+        if let Some(other_index) = catalogue.find_entry(new_name)
+            && other_index != index
+        {
+            bail!("file {new_name} already exists")
+        }
+
+        let d = &mut catalogue.entries[index].descriptor;
+        d.directory = new_name.directory;
+        d.file_name = new_name.file_name.clone();
+        Ok(())
+    })
+}
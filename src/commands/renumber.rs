@@ -0,0 +1,22 @@
+use crate::bbc_basic::renumber_program;
+use crate::util::open_for_write;
+use anyhow::Result;
+use std::fs::{read, write};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub fn run_renumber(
+    path: &Path,
+    output_path: Option<&PathBuf>,
+    overwrite: bool,
+    start: u16,
+    step: u16,
+    from: u16,
+) -> Result<()> {
+    let bytes = renumber_program(&read(path)?, start, step, from)?;
+    match output_path {
+        Some(output_path) => open_for_write(output_path, overwrite)?.write_all(&bytes)?,
+        None => write(path, bytes)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use crate::dfs::{Address, DfsPath, Side};
+use crate::image_format::ImageFormat;
+use crate::ops::{edit_image_file, find_file};
+use anyhow::Result;
+use std::path::Path;
+
+pub fn run_set_addr(
+    image_path: &Path,
+    name: &DfsPath,
+    load_address: Option<Address>,
+    execution_address: Option<Address>,
+    side: Side,
+    format: Option<ImageFormat>,
+) -> Result<()> {
+    edit_image_file(image_path, format, side, |catalogue, _, _| {
+        let index = find_file(catalogue, name)?;
+        let d = &mut catalogue.entries[index].descriptor;
+        if let Some(load_address) = load_address {
+            d.load_address = load_address;
+        }
+        if let Some(execution_address) = execution_address {
+            d.execution_address = execution_address;
+        }
+        Ok(())
+    })
+}
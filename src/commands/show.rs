@@ -1,18 +1,157 @@
-use crate::dfs::{
-    Catalogue, FileSpec, SECTOR_BYTES, START_SECTOR, SectorSize, get_file_sector_count,
-};
-use anyhow::{Error, Result};
+use crate::adfs::{ADFS_SECTOR_BYTES, AdfsImage, ROOT_DIRECTORY_SECTOR};
+use crate::dfs::{Catalogue, CatalogueFlavour, FileSpec, SECTOR_BYTES};
+use crate::image_format::ImageFormat;
+use crate::metadata::write_inf_line;
+use anyhow::{Error, Result, bail};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, Write, stdout};
 use std::path::Path;
+use std::result::Result as StdResult;
+use std::str::FromStr;
 
-pub fn run_show(path: &Path) -> Result<()> {
-    let catalogues = Catalogue::from_image_file(path)?;
-    for catalogue in catalogues {
-        show_catalogue(catalogue)?;
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShowFormat {
+    Json,
+    Csv,
+    Inf,
+}
+
+impl FromStr for ShowFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "json" => Self::Json,
+            "csv" => Self::Csv,
+            "inf" => Self::Inf,
+            _ => bail!("invalid output format {s}"),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShowStyle {
+    // Acorn DFS *CAT
+    Cat,
+    // Acorn DFS *INFO *.*
+    Info,
+}
+
+impl FromStr for ShowStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "cat" => Self::Cat,
+            "info" => Self::Info,
+            _ => bail!("invalid style {s}"),
+        })
+    }
+}
+
+// Catalogue of one side or DDOS volume together with values derived from it
+#[derive(Serialize)]
+struct CatalogueReport {
+    #[serde(rename = "side")]
+    side: u8,
+
+    #[serde(rename = "freeSectors")]
+    free_sectors: usize,
+
+    #[serde(flatten)]
+    catalogue: Catalogue,
+}
+
+pub fn run_show(
+    path: &Path,
+    format: Option<ImageFormat>,
+    lenient: bool,
+    output_format: Option<ShowFormat>,
+    style: Option<ShowStyle>,
+) -> Result<()> {
+    let mut f = File::open(path)?;
+    if ImageFormat::detect(&mut f, path, format)? == ImageFormat::Adfs {
+        if output_format.is_some() || style.is_some() {
+            bail!("--output-format and --style are not supported for ADFS disc images")
+        }
+        return show_adfs_image(AdfsImage::new(f)?);
+    }
+
+    let catalogues = Catalogue::from_image_file(path, format, lenient)?;
+    let Some(output_format) = output_format else {
+        for (i, catalogue) in catalogues.into_iter().enumerate() {
+            match style {
+                Some(style) => {
+                    if i > 0 {
+                        println!();
+                    }
+                    let lines = match style {
+                        ShowStyle::Cat => cat_lines(&catalogue, &drive_label(&catalogue, i)),
+                        ShowStyle::Info => info_lines(&catalogue),
+                    };
+                    for line in lines {
+                        println!("{line}");
+                    }
+                }
+                None => show_catalogue(catalogue),
+            }
+        }
+        return Ok(());
+    };
+
+    let reports = make_reports(catalogues)?;
+    write_reports(stdout().lock(), output_format, &reports)
+}
+
+fn make_reports(catalogues: Vec<Catalogue>) -> Result<Vec<CatalogueReport>> {
+    catalogues
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut catalogue)| {
+            catalogue
+                .entries
+                .sort_by(|a, b| FileSpec::compare(&a.descriptor, &b.descriptor));
+            Ok(CatalogueReport {
+                side: match catalogue.volume {
+                    Some(volume) => u8::from(volume.side),
+                    None => u8::try_from(i)?,
+                },
+                free_sectors: free_sectors(&catalogue),
+                catalogue,
+            })
+        })
+        .collect()
+}
+
+fn write_reports<W: Write>(
+    mut output: W,
+    output_format: ShowFormat,
+    reports: &[CatalogueReport],
+) -> Result<()> {
+    match output_format {
+        ShowFormat::Json => {
+            serde_json::to_writer_pretty(&mut output, reports)?;
+            writeln!(output)?;
+        }
+        ShowFormat::Csv => write_csv(output, reports)?,
+        // Lines hold only what an .inf file records, so there is no start
+        // sector and nothing about the disc itself
+        ShowFormat::Inf => {
+            for report in reports {
+                for entry in &report.catalogue.entries {
+                    write_inf_line(&mut output, &entry.descriptor, entry.length)?;
+                }
+            }
+        }
     }
     Ok(())
 }
 
-fn show_catalogue(catalogue: Catalogue) -> Result<()> {
+fn show_catalogue(catalogue: Catalogue) {
+    if let Some(volume) = catalogue.volume {
+        println!("{label:<13}: {value}", label = "Volume", value = volume);
+    }
     println!(
         "{label:<13}: {value}",
         label = "Title",
@@ -26,31 +165,24 @@ fn show_catalogue(catalogue: Catalogue) -> Result<()> {
     println!(
         "{label:<13}: {value}",
         label = "File count",
-        value = catalogue.file_offset.number()
+        value = catalogue.entries.len()
     );
+    if catalogue.flavour == CatalogueFlavour::Watford62 {
+        println!("{label:<13}: Watford DFS (62 files)", label = "Catalogue");
+    }
     println!(
-        "{label:<13}: {value:?}",
+        "{label:<13}: {value}",
         label = "Boot option",
         value = catalogue.boot_option
     );
 
-    let total_sectors = usize::from(u16::from(catalogue.disc_size));
-
     println!(
         "{label:<13}: {value}",
         label = "Total sectors",
-        value = total_sectors
+        value = u16::from(catalogue.disc_size)
     );
 
-    let used_sectors = usize::from(
-        catalogue
-            .entries
-            .iter()
-            .try_fold(SectorSize::ZERO, |acc, entry| {
-                Ok::<SectorSize, Error>(acc + get_file_sector_count(entry.length)?)
-            })?,
-    );
-    let free_sectors = total_sectors - used_sectors - usize::from(START_SECTOR);
+    let free_sectors = free_sectors(&catalogue);
     let free_bytes = free_sectors * usize::from(SECTOR_BYTES);
 
     println!(
@@ -80,5 +212,407 @@ fn show_catalogue(catalogue: Catalogue) -> Result<()> {
         );
     }
 
+    if !catalogue.issues.is_empty() {
+        println!("Issues:");
+        for issue in &catalogue.issues {
+            println!("  {issue}");
+        }
+    }
+}
+
+// Side 1 of a double-sided disc is drive 2
+fn drive_label(catalogue: &Catalogue, index: usize) -> String {
+    match catalogue.volume {
+        Some(volume) => volume.to_string(),
+        None => (index * 2).to_string(),
+    }
+}
+
+// Files in the current directory, $, come first without a directory prefix,
+// followed by a blank line and files in other directories, two to a line
+fn cat_lines(catalogue: &Catalogue, drive: &str) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "{title} ({cycle_number:02})",
+            title = catalogue.disc_title,
+            cycle_number = u8::from(catalogue.cycle_number)
+        ),
+        format!(
+            "{drive:<20}Option {boot_option}",
+            drive = format!("Drive {drive}"),
+            boot_option = catalogue.boot_option
+        ),
+        format!(
+            "{directory:<20}Lib. :{drive}.$",
+            directory = format!("Dir. :{drive}.$")
+        ),
+        String::new(),
+    ];
+
+    let mut entries = catalogue.entries.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| FileSpec::compare(&a.descriptor, &b.descriptor));
+    let (current, others): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|entry| entry.descriptor.directory.is_root());
+
+    for (i, group) in [current, others].iter().enumerate() {
+        if i > 0 && !group.is_empty() && lines.len() > 4 {
+            lines.push(String::new());
+        }
+        for pair in group.chunks(2) {
+            let items = pair
+                .iter()
+                .map(|entry| {
+                    let d = &entry.descriptor;
+                    let prefix = if d.directory.is_root() {
+                        String::from("  ")
+                    } else {
+                        format!("{directory}.", directory = d.directory)
+                    };
+                    format!(
+                        "  {prefix}{file_name:<7}{locked}",
+                        file_name = d.file_name.to_string(),
+                        locked = if d.locked { "  L" } else { "" }
+                    )
+                })
+                .collect::<Vec<_>>();
+            let line = match items.as_slice() {
+                [left, right] => format!("{left:<20}{right}"),
+                _ => items.concat(),
+            };
+            lines.push(line.trim_end().to_string());
+        }
+    }
+
+    lines
+}
+
+// DFS shows addresses in the I/O processor with FF in the high byte
+fn info_lines(catalogue: &Catalogue) -> Vec<String> {
+    let mut entries = catalogue.entries.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| FileSpec::compare(&a.descriptor, &b.descriptor));
+    entries
+        .into_iter()
+        .map(|entry| {
+            let d = &entry.descriptor;
+            format!(
+                "{directory}.{file_name:<7} {locked} {load_address:06X} {execution_address:06X} {length:06X} {start_sector:03X}",
+                directory = d.directory,
+                file_name = d.file_name.to_string(),
+                locked = if d.locked { 'L' } else { ' ' },
+                load_address = d.load_address.to_cfs_address() & 0x00ff_ffff,
+                execution_address = d.execution_address.to_cfs_address() & 0x00ff_ffff,
+                length = entry.length,
+                start_sector = entry.start_sector
+            )
+        })
+        .collect()
+}
+
+// One row per file with the same values as the JSON output, repeating the
+// values for the side on each row and writing a row with no file for a side
+// with no files
+fn write_csv<W: Write>(mut output: W, reports: &[CatalogueReport]) -> Result<()> {
+    writeln!(
+        output,
+        "side,volume,discTitle,cycleNumber,bootOption,discSize,freeSectors,directory,fileName,loadAddress,executionAddress,length,startSector,locked"
+    )?;
+    for report in reports {
+        let catalogue = &report.catalogue;
+        let boot_option = serde_json::to_value(catalogue.boot_option)?;
+        let side = format!(
+            "{side},{volume},{disc_title},{cycle_number},{boot_option},{disc_size},{free_sectors}",
+            side = report.side,
+            volume = catalogue
+                .volume
+                .map(|volume| volume.to_string())
+                .unwrap_or_default(),
+            disc_title = csv_field(&catalogue.disc_title.to_string()),
+            cycle_number = catalogue.cycle_number,
+            boot_option = boot_option.as_str().unwrap_or_default(),
+            disc_size = u16::from(catalogue.disc_size),
+            free_sectors = report.free_sectors
+        );
+        if catalogue.entries.is_empty() {
+            writeln!(output, "{side},,,,,,,")?;
+        }
+        for entry in &catalogue.entries {
+            let d = &entry.descriptor;
+            writeln!(
+                output,
+                "{side},{directory},{file_name},&{load_address:06X},&{execution_address:06X},&{length:06X},{start_sector},{locked}",
+                directory = csv_field(&d.directory.to_string()),
+                file_name = csv_field(d.file_name.as_str()),
+                load_address = d.load_address,
+                execution_address = d.execution_address,
+                length = entry.length,
+                start_sector = entry.start_sector.to_u16(),
+                locked = d.locked
+            )?;
+        }
+    }
     Ok(())
 }
+
+// Quote fields containing separators as described in RFC 4180
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"']) {
+        format!("\"{s}\"", s = s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// Computed without SectorSize since damaged catalogues may claim more
+// sectors than the disc holds
+fn free_sectors(catalogue: &Catalogue) -> usize {
+    let used_sectors = catalogue
+        .entries
+        .iter()
+        .map(|entry| {
+            usize::try_from(u32::from(entry.length).div_ceil(u32::from(SECTOR_BYTES)))
+                .unwrap_or(usize::MAX)
+        })
+        .fold(0, usize::saturating_add);
+    usize::from(u16::from(catalogue.disc_size))
+        .saturating_sub(used_sectors)
+        .saturating_sub(usize::from(catalogue.flavour.start_sector()))
+}
+
+fn show_adfs_image<R: Read + Seek>(mut image: AdfsImage<R>) -> Result<()> {
+    let root = image.read_directory(ROOT_DIRECTORY_SECTOR)?;
+    let files = image.walk()?;
+    let map = &image.map;
+
+    println!(
+        "{label:<13}: {value}",
+        label = "Title",
+        value = map.disc_name
+    );
+    println!(
+        "{label:<13}: {value}",
+        label = "Root title",
+        value = root.title
+    );
+    println!(
+        "{label:<13}: {value}",
+        label = "Boot option",
+        value = map.boot_option
+    );
+    println!(
+        "{label:<13}: {value}",
+        label = "Total sectors",
+        value = map.total_sectors
+    );
+
+    let free_sectors = map.free_sectors();
+    println!(
+        "{label:<13}: {value} ({free_bytes} bytes)",
+        label = "Free sectors",
+        value = free_sectors,
+        free_bytes = u64::from(free_sectors) * ADFS_SECTOR_BYTES as u64
+    );
+
+    println!("Files:");
+
+    let width = files
+        .iter()
+        .map(|file| file.adfs_path().len())
+        .max()
+        .unwrap_or_default();
+    for file in &files {
+        let entry = &file.entry;
+        if entry.is_directory {
+            println!(
+                "  {path:<width$} {blank:26} {start_sector:06X} {attributes}",
+                path = file.adfs_path(),
+                blank = "",
+                start_sector = entry.start_sector,
+                attributes = entry.attributes()
+            );
+        } else {
+            println!(
+                "  {path:<width$} {load_address:08X} {execution_address:08X} {length:08X} {start_sector:06X} {attributes}",
+                path = file.adfs_path(),
+                load_address = entry.load_address,
+                execution_address = entry.execution_address,
+                length = entry.length,
+                start_sector = entry.start_sector,
+                attributes = entry.attributes()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ShowFormat, cat_lines, info_lines, make_reports, write_reports};
+    use crate::dfs::{
+        Address, BootOption, Catalogue, CatalogueEntry, CatalogueFlavour, CycleNumber, DdosVolume,
+        FileDescriptor, FileOffset, Side,
+    };
+    use anyhow::Result;
+    use rstest::rstest;
+
+    fn make_catalogue() -> Result<Catalogue> {
+        let make_entry = |name: &str, directory: char, locked: bool, start_sector: u16| {
+            Ok::<_, anyhow::Error>(CatalogueEntry::new(
+                FileDescriptor::new(
+                    name.parse()?,
+                    directory.try_into()?,
+                    locked,
+                    Address::try_from(0x31900)?,
+                    Address::try_from(0x8023)?,
+                ),
+                0x2bc.try_into()?,
+                start_sector.try_into()?,
+            ))
+        };
+        Ok(Catalogue::new(
+            "GAMES".parse()?,
+            CycleNumber::new(7)?,
+            FileOffset::new(0)?,
+            BootOption::Exec,
+            800u16.try_into()?,
+            CatalogueFlavour::Acorn,
+            vec![
+                make_entry("ZED", '$', false, 20)?,
+                make_entry("HELLO", 'B', false, 14)?,
+                make_entry("ELITE", '$', true, 8)?,
+                make_entry("!BOOT", '$', false, 2)?,
+            ],
+        ))
+    }
+
+    #[test]
+    fn cat() -> Result<()> {
+        assert_eq!(
+            vec![
+                "GAMES (07)",
+                "Drive 0             Option 3 (EXEC)",
+                "Dir. :0.$           Lib. :0.$",
+                "",
+                "    !BOOT               ELITE    L",
+                "    ZED",
+                "",
+                "  B.HELLO",
+            ],
+            cat_lines(&make_catalogue()?, "0")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn info() -> Result<()> {
+        assert_eq!(
+            vec![
+                "$.!BOOT     FF1900 008023 0002BC 002",
+                "$.ELITE   L FF1900 008023 0002BC 008",
+                "$.ZED       FF1900 008023 0002BC 014",
+                "B.HELLO     FF1900 008023 0002BC 00E",
+            ],
+            info_lines(&make_catalogue()?)
+        );
+        Ok(())
+    }
+
+    fn make_ddos_catalogue() -> Result<Catalogue> {
+        let mut catalogue = make_catalogue()?;
+        catalogue.flavour = CatalogueFlavour::Ddos;
+        catalogue.volume = Some(DdosVolume {
+            side: Side::Side1,
+            letter: 'B',
+            start_track: 41,
+        });
+        Ok(catalogue)
+    }
+
+    fn output(catalogue: Catalogue, output_format: ShowFormat) -> Result<String> {
+        let mut output = Vec::new();
+        write_reports(&mut output, output_format, &make_reports(vec![catalogue])?)?;
+        Ok(String::from_utf8(output)?)
+    }
+
+    #[rstest]
+    #[case(0, 786, None, make_catalogue())]
+    #[case(1, 788, Some("2B"), make_ddos_catalogue())]
+    fn json(
+        #[case] side: u8,
+        #[case] free_sectors: usize,
+        #[case] volume: Option<&str>,
+        #[case] catalogue: Result<Catalogue>,
+    ) -> Result<()> {
+        let value =
+            serde_json::from_str::<serde_json::Value>(&output(catalogue?, ShowFormat::Json)?)?;
+        let report = &value[0];
+        assert_eq!(side, report["side"]);
+        assert_eq!(free_sectors, report["freeSectors"]);
+        assert_eq!(volume, report["volume"].as_str());
+        assert_eq!("GAMES", report["discTitle"]);
+
+        let entries = report["entries"].as_array().unwrap();
+        assert_eq!(4, entries.len());
+        assert_eq!("ELITE", entries[1]["fileName"]);
+        assert_eq!("&031900", entries[1]["loadAddress"]);
+        assert_eq!("&0002BC", entries[1]["length"]);
+        assert_eq!(8, entries[1]["startSector"]);
+        assert_eq!(true, entries[1]["locked"]);
+        assert_eq!(false, entries[0]["locked"]);
+        Ok(())
+    }
+
+    const CSV_HEADER: &str = "side,volume,discTitle,cycleNumber,bootOption,discSize,freeSectors,directory,fileName,loadAddress,executionAddress,length,startSector,locked";
+
+    #[rstest]
+    #[case("0,,GAMES,7,exec,800,786", make_catalogue())]
+    #[case("1,2B,GAMES,7,exec,800,788", make_ddos_catalogue())]
+    fn csv(#[case] prefix: &str, #[case] catalogue: Result<Catalogue>) -> Result<()> {
+        let expected = [
+            CSV_HEADER.to_string(),
+            format!("{prefix},$,!BOOT,&031900,&008023,&0002BC,2,false"),
+            format!("{prefix},$,ELITE,&031900,&008023,&0002BC,8,true"),
+            format!("{prefix},$,ZED,&031900,&008023,&0002BC,20,false"),
+            format!("{prefix},B,HELLO,&031900,&008023,&0002BC,14,false"),
+        ];
+        assert_eq!(
+            expected.to_vec(),
+            output(catalogue?, ShowFormat::Csv)?
+                .lines()
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn csv_no_files() -> Result<()> {
+        let mut catalogue = make_catalogue()?;
+        catalogue.entries.clear();
+        assert_eq!(
+            vec![CSV_HEADER, "0,,GAMES,7,exec,800,798,,,,,,,"],
+            output(catalogue, ShowFormat::Csv)?
+                .lines()
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(make_catalogue())]
+    #[case(make_ddos_catalogue())]
+    fn inf(#[case] catalogue: Result<Catalogue>) -> Result<()> {
+        assert_eq!(
+            vec![
+                "$.!BOOT 031900 008023 0002BC 00 0000",
+                "$.ELITE 031900 008023 0002BC 08 0000",
+                "$.ZED 031900 008023 0002BC 00 0000",
+                "B.HELLO 031900 008023 0002BC 00 0000",
+            ],
+            output(catalogue?, ShowFormat::Inf)?
+                .lines()
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
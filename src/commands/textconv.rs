@@ -0,0 +1,236 @@
+use crate::bbc_basic::detokenize_to_string;
+use crate::ddos_reader::DdosReader;
+use crate::dfs::{Catalogue, FileSpec, SECTOR_BYTES, Side};
+use crate::dsd_reader::DsdReader;
+use crate::image_format::ImageFormat;
+use crate::image_reader::ImageReader;
+use crate::ssd_reader::SsdReader;
+use crate::util::{crc32, is_ascii_printable};
+use anyhow::{Result, bail};
+use std::fs::File;
+use std::path::Path;
+
+const HEX_DUMP_WIDTH: usize = 16;
+
+pub fn run_textconv(path: &Path, format: Option<ImageFormat>) -> Result<()> {
+    let mut f = File::open(path)?;
+    let format = ImageFormat::detect(&mut f, path, format)?;
+    let lines = match format {
+        ImageFormat::Ssd => textconv_image(&mut SsdReader::new(f, SECTOR_BYTES)?)?,
+        ImageFormat::Dsd => textconv_image(&mut DsdReader::new(
+            f,
+            SECTOR_BYTES,
+            format.sectors_per_track(),
+        )?)?,
+        ImageFormat::Dds | ImageFormat::Ddd => {
+            let mut reader = DdosReader::new(f, SECTOR_BYTES, format.sides())?;
+            let mut lines = Vec::new();
+            for catalogue in Catalogue::from_ddos_reader(&mut reader, true)? {
+                let Some(volume) = catalogue.volume else {
+                    bail!("catalogue has no volume")
+                };
+                lines.push(format!("Volume {volume}"));
+                lines.extend(textconv_side(
+                    catalogue,
+                    Side::Side0,
+                    &mut reader.volume_reader(volume),
+                )?);
+            }
+            lines
+        }
+        ImageFormat::Adfs => bail!("converting ADFS disc images to text is not supported"),
+    };
+
+    for line in lines {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn textconv_image<R: ImageReader>(reader: &mut R) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for (i, catalogue) in Catalogue::from_image_reader(reader, true)?
+        .into_iter()
+        .enumerate()
+    {
+        lines.push(format!("Side {i}"));
+        lines.extend(textconv_side(catalogue, Side::try_from(i)?, reader)?);
+    }
+    Ok(lines)
+}
+
+fn textconv_side<R: ImageReader>(
+    mut catalogue: Catalogue,
+    side: Side,
+    reader: &mut R,
+) -> Result<Vec<String>> {
+    catalogue
+        .entries
+        .sort_by(|a, b| FileSpec::compare(&a.descriptor, &b.descriptor));
+
+    // Entries may run past the end of a truncated image, in which case the
+    // content is left out rather than failing the whole conversion
+    let side_len = reader.side_len(side);
+    let contents = catalogue
+        .entries
+        .iter()
+        .map(|entry| {
+            let offset = u64::from(entry.start_sector) * u64::from(SECTOR_BYTES);
+            if offset + u64::from(u32::from(entry.length)) > side_len {
+                return Ok(None);
+            }
+            let mut bytes = vec![0; usize::try_from(u32::from(entry.length))?];
+            reader.read_bytes(side, entry.start_sector, &mut bytes)?;
+            Ok(Some(bytes))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(side_lines(&catalogue, &contents))
+}
+
+// Catalogue followed by each file, with entries already in FileSpec order
+fn side_lines(catalogue: &Catalogue, contents: &[Option<Vec<u8>>]) -> Vec<String> {
+    let mut lines = vec![
+        format!("Title        : \"{title}\"", title = catalogue.disc_title),
+        format!("Cycle number : {}", catalogue.cycle_number),
+        format!("Boot option  : {}", catalogue.boot_option),
+        format!("Disc size    : {}", u16::from(catalogue.disc_size)),
+        String::from("Files:"),
+    ];
+
+    for (entry, content) in catalogue.entries.iter().zip(contents) {
+        let d = &entry.descriptor;
+        let digest = content.as_ref().map_or_else(
+            || String::from("--------"),
+            |bytes| format!("{:08X}", crc32(bytes)),
+        );
+        lines.push(format!(
+            "  {directory}.{file_name:<7} {load_address:06X} {execution_address:06X} {length:06X} {start_sector:03X} {digest}{locked}",
+            directory = d.directory,
+            file_name = d.file_name.to_string(),
+            load_address = d.load_address,
+            execution_address = d.execution_address,
+            length = entry.length,
+            start_sector = entry.start_sector,
+            locked = if d.locked { " L" } else { "" }
+        ));
+    }
+
+    for issue in &catalogue.issues {
+        lines.push(format!("Issue: {issue}"));
+    }
+
+    for (entry, content) in catalogue.entries.iter().zip(contents) {
+        let d = &entry.descriptor;
+        lines.push(String::new());
+        lines.push(format!(
+            "{directory}.{file_name}",
+            directory = d.directory,
+            file_name = d.file_name
+        ));
+        match content {
+            Some(bytes) => match detokenize_to_string(bytes) {
+                Some(listing) => lines.extend(listing.lines().map(String::from)),
+                None => lines.extend(hex_dump(bytes)),
+            },
+            None => lines.push(String::from("(runs past end of image)")),
+        }
+    }
+
+    lines
+}
+
+fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(HEX_DUMP_WIDTH)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|b| format!("{b:02X}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let text = chunk
+                .iter()
+                .map(|&b| {
+                    if is_ascii_printable(b) {
+                        char::from(b)
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!(
+                "{offset:06X}  {hex:<width$}  {text}",
+                offset = i * HEX_DUMP_WIDTH,
+                width = HEX_DUMP_WIDTH * 3 - 1
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::side_lines;
+    use crate::bbc_basic::tokenize_source;
+    use crate::dfs::{
+        Address, BootOption, Catalogue, CatalogueEntry, CatalogueFlavour, CycleNumber,
+        FileDescriptor, FileOffset,
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn textconv() -> Result<()> {
+        let make_entry = |name: &str, locked: bool, length: u32, start_sector: u16| {
+            Ok::<_, anyhow::Error>(CatalogueEntry::new(
+                FileDescriptor::new(
+                    name.parse()?,
+                    '$'.try_into()?,
+                    locked,
+                    Address::try_from(0x1900u32)?,
+                    Address::try_from(0x8023u32)?,
+                ),
+                length.try_into()?,
+                start_sector.try_into()?,
+            ))
+        };
+
+        let mut program = Vec::new();
+        tokenize_source(&mut program, b"10 PRINT \"HELLO\"\n")?;
+        let data = b"ABCDEFGHIJKLMNOPQ\r".to_vec();
+
+        let catalogue = Catalogue::new(
+            "GAME".parse()?,
+            CycleNumber::default(),
+            FileOffset::new(0)?,
+            BootOption::Run,
+            800u16.try_into()?,
+            CatalogueFlavour::Acorn,
+            vec![
+                make_entry("DATA", false, u32::try_from(data.len())?, 3)?,
+                make_entry("PROG", true, u32::try_from(program.len())?, 2)?,
+            ],
+        );
+
+        assert_eq!(
+            vec![
+                "Title        : \"GAME\"",
+                "Cycle number : 0",
+                "Boot option  : 2 (RUN)",
+                "Disc size    : 800",
+                "Files:",
+                "  $.DATA    001900 008023 000012 003 F41D3C7A",
+                "  $.PROG    001900 008023 000010 002 17C6A27A L",
+                "",
+                "$.DATA",
+                "000000  41 42 43 44 45 46 47 48 49 4A 4B 4C 4D 4E 4F 50  ABCDEFGHIJKLMNOP",
+                "000010  51 0D                                            Q.",
+                "",
+                "$.PROG",
+                "   10 PRINT \"HELLO\"",
+            ],
+            side_lines(&catalogue, &[Some(data), Some(program)])
+        );
+        Ok(())
+    }
+}
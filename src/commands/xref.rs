@@ -0,0 +1,36 @@
+use crate::bbc_basic::{Symbol, program_lines, xref_program};
+use anyhow::Result;
+use serde::Serialize;
+use std::fs::read;
+use std::path::Path;
+
+#[derive(Serialize)]
+struct Report {
+    #[serde(rename = "path")]
+    path: String,
+
+    #[serde(rename = "symbols")]
+    symbols: Vec<Symbol>,
+}
+
+pub fn run_xref(path: &Path, json: bool) -> Result<()> {
+    let lines = program_lines(&read(path)?)?;
+    let lines = lines
+        .iter()
+        .map(|(line_number, content)| (*line_number, content.as_slice()))
+        .collect::<Vec<_>>();
+    let report = Report {
+        path: path.display().to_string(),
+        symbols: xref_program(&lines)?,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for symbol in &report.symbols {
+            println!("{symbol}");
+        }
+    }
+
+    Ok(())
+}
@@ -5,3 +5,5 @@ pub const LOSSLESS_BBC_BASIC_EXT: &str = "bbc";
 pub const LOSSY_BBC_BASIC_EXT: &str = "bbc.txt";
 
 pub const INF_EXT: &str = "inf";
+
+pub const SLACK_EXT: &str = "slack";
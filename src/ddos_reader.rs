@@ -0,0 +1,197 @@
+use crate::dfs::{
+    DD_SECTORS_PER_TRACK, DDOS_VOLUME_TABLE_SECTOR, DdosVolume, SectorBytes, SectorSize, Side,
+};
+use crate::image_reader::ImageReader;
+use anyhow::{Result, bail};
+use std::io::{Read, Seek, SeekFrom};
+
+// Reads Opus DDOS double-density images: .dds files hold a single side and
+// .ddd files interleave the tracks of both sides in the same way as .dsd
+// files
+pub struct DdosReader<R: Read + Seek> {
+    stream_len: u64,
+    sides: u8,
+    sector_bytes: SectorBytes,
+    reader: R,
+}
+
+impl<R: Read + Seek> DdosReader<R> {
+    pub fn new(mut reader: R, sector_bytes: SectorBytes, sides: u8) -> Result<Self> {
+        let stream_len = reader.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            stream_len,
+            sides,
+            sector_bytes,
+            reader,
+        })
+    }
+
+    pub fn volumes(&mut self) -> Result<Vec<DdosVolume>> {
+        let mut volumes = Vec::new();
+        for i in 0..self.sides {
+            let side = Side::try_from(i)?;
+            let mut bytes = vec![0; usize::from(self.sector_bytes)];
+            self.read_sectors(side, DDOS_VOLUME_TABLE_SECTOR, &mut bytes)?;
+            volumes.extend(DdosVolume::from_volume_table(&bytes, side)?);
+        }
+        Ok(volumes)
+    }
+
+    pub fn read_catalogue_bytes(&mut self, volume: DdosVolume) -> Result<Vec<u8>> {
+        let mut bytes = vec![0; usize::from(self.sector_bytes) * 2];
+        self.read_sectors(volume.side, volume.catalogue_sector(), &mut bytes)?;
+        Ok(bytes)
+    }
+
+    // View of a single volume with sectors numbered as in its catalogue
+    pub const fn volume_reader(&mut self, volume: DdosVolume) -> VolumeReader<'_, R> {
+        VolumeReader {
+            ddos_reader: self,
+            volume,
+        }
+    }
+
+    fn side_len(&self, side: Side) -> u64 {
+        let track_bytes = u64::from(self.sector_bytes) * DD_SECTORS_PER_TRACK as u64;
+        let mut len = 0;
+        let mut track = 0;
+        loop {
+            let offset = track_bytes * (track * u64::from(self.sides) + u64::from(u8::from(side)));
+            if offset >= self.stream_len {
+                break;
+            }
+            len += track_bytes.min(self.stream_len - offset);
+            track += 1;
+        }
+        len
+    }
+
+    fn read_sectors(&mut self, side: Side, start_sector: usize, buffer: &mut [u8]) -> Result<()> {
+        let sector_bytes = usize::from(self.sector_bytes);
+        for (i, chunk) in buffer.chunks_mut(sector_bytes).enumerate() {
+            let sector = start_sector + i;
+            let (track, r) = (sector / DD_SECTORS_PER_TRACK, sector % DD_SECTORS_PER_TRACK);
+            let track_offset = sector_bytes
+                * DD_SECTORS_PER_TRACK
+                * (track * usize::from(self.sides) + usize::from(side));
+            let sector_offset = track_offset + r * sector_bytes;
+
+            self.reader
+                .seek(SeekFrom::Start(u64::try_from(sector_offset)?))?;
+
+            self.reader.read_exact(chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct VolumeReader<'a, R: Read + Seek> {
+    ddos_reader: &'a mut DdosReader<R>,
+    volume: DdosVolume,
+}
+
+impl<R: Read + Seek> ImageReader for VolumeReader<'_, R> {
+    fn sides(&self) -> u8 {
+        1
+    }
+
+    fn stream_len(&self) -> u64 {
+        self.ddos_reader.stream_len
+    }
+
+    fn side_len(&self, side: Side) -> u64 {
+        if u8::from(side) > 0 {
+            0
+        } else {
+            let start_byte =
+                self.volume.start_sector() as u64 * u64::from(self.ddos_reader.sector_bytes);
+            self.ddos_reader
+                .side_len(self.volume.side)
+                .saturating_sub(start_byte)
+        }
+    }
+
+    fn sector_bytes(&self) -> SectorBytes {
+        self.ddos_reader.sector_bytes
+    }
+
+    fn read_bytes(
+        &mut self,
+        side: Side,
+        start_sector: SectorSize,
+        buffer: &mut [u8],
+    ) -> Result<()> {
+        if u8::from(side) > 0 {
+            bail!("DDOS volume reader supports one side only")
+        }
+
+        self.ddos_reader.read_sectors(
+            self.volume.side,
+            self.volume.start_sector() + usize::from(u16::from(start_sector)),
+            buffer,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ddos_reader::DdosReader;
+    use crate::dfs::{DD_SECTORS_PER_TRACK, SECTOR_BYTES, SectorSize, Side};
+    use crate::image_reader::ImageReader;
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    // Tag every sector of an interleaved image with its side, track and
+    // sector number
+    fn make_image(sides: usize, track_count: usize) -> Vec<u8> {
+        let sector_bytes = usize::from(SECTOR_BYTES);
+        let mut bytes = Vec::new();
+        for track in 0..track_count {
+            for side in 0..sides {
+                for sector in 0..DD_SECTORS_PER_TRACK {
+                    let mut data = vec![0u8; sector_bytes];
+                    data[0] = u8::try_from(side).unwrap();
+                    data[1] = u8::try_from(track).unwrap();
+                    data[2] = u8::try_from(sector).unwrap();
+                    if track == 0 && sector == 16 {
+                        data[1..3].copy_from_slice(
+                            &(u16::try_from(track_count).unwrap() * 18).to_be_bytes(),
+                        );
+                        data[3] = 18;
+                        data[4] = u8::try_from(track_count).unwrap();
+                        data[8] = 1;
+                        data[10] = 3;
+                    }
+                    bytes.extend(data);
+                }
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn volumes() -> Result<()> {
+        let mut reader = DdosReader::new(Cursor::new(make_image(2, 5)), SECTOR_BYTES, 2)?;
+        let volumes = reader.volumes()?;
+        assert_eq!(
+            vec!["0A", "0B", "2A", "2B"],
+            volumes.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+
+        let catalogue_bytes = reader.read_catalogue_bytes(volumes[3])?;
+        assert_eq!([1, 0, 2], catalogue_bytes[0..3]);
+
+        let mut volume_reader = reader.volume_reader(volumes[3]);
+        assert_eq!(
+            2 * DD_SECTORS_PER_TRACK as u64 * u64::from(SECTOR_BYTES),
+            volume_reader.side_len(Side::Side0)
+        );
+
+        let mut buffer = vec![0; usize::from(SECTOR_BYTES) * 2];
+        volume_reader.read_bytes(Side::Side0, SectorSize::try_from(17u16)?, &mut buffer)?;
+        assert_eq!([1, 3, 17], buffer[0..3]);
+        assert_eq!([1, 4, 0], buffer[256..259]);
+        Ok(())
+    }
+}
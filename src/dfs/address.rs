@@ -4,4 +4,14 @@ u18!(Address);
 
 impl Address {
     pub const ZERO: Self = Self(0);
+
+    // Tape files use 32-bit addresses; DFS sets bits 16 and 17 for addresses
+    // &FFFFxxxx in the I/O processor
+    pub const fn to_cfs_address(self) -> u32 {
+        if self.0 & 0x30000 == 0x30000 {
+            0xffff_0000 | (self.0 & 0xffff)
+        } else {
+            self.0
+        }
+    }
 }
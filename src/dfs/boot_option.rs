@@ -1,8 +1,11 @@
-use crate::dfs::{CatalogueBytes, SECTOR_BYTES};
-use anyhow::{Result, bail};
+use crate::dfs::{CatalogueBytes, CatalogueError, SECTOR_BYTES};
+use anyhow::{Error, bail};
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::result::Result as StdResult;
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub enum BootOption {
     #[default]
     #[serde(rename = "none")]
@@ -16,19 +19,90 @@ pub enum BootOption {
 }
 
 impl BootOption {
-    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> Result<Self> {
+    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> StdResult<Self, CatalogueError> {
         let temp = bytes[usize::from(SECTOR_BYTES) + 6];
-        assert_eq!(0, temp & 0b1100_1100);
-        Ok(match (temp & 0b0011_0000) >> 4 {
+        if temp & 0b1100_1100 != 0 {
+            return Err(CatalogueError::ReservedBits { value: temp });
+        }
+        Ok(Self::from_catalogue_bytes_lossy(bytes))
+    }
+
+    // Ignores reserved bits
+    pub fn from_catalogue_bytes_lossy(bytes: &CatalogueBytes) -> Self {
+        Self::from_option_bits((bytes[usize::from(SECTOR_BYTES) + 6] & 0b0011_0000) >> 4)
+    }
+
+    // Uses the bottom two bits of an *OPT4 value
+    pub const fn from_option_bits(value: u8) -> Self {
+        match value & 0b0000_0011 {
             0 => Self::None,
             1 => Self::Load,
             2 => Self::Run,
-            3 => Self::Exec,
-            _ => bail!("invalid boot option {temp}"),
-        })
+            _ => Self::Exec,
+        }
     }
 
     pub fn write_to_catalogue(self, bytes: &mut [u8]) {
-        bytes[usize::from(SECTOR_BYTES) + 6] |= (self as u8) << 4;
+        let offset = usize::from(SECTOR_BYTES) + 6;
+        bytes[offset] = (bytes[offset] & !0b0011_0000) | ((self as u8) << 4);
+    }
+}
+
+impl Display for BootOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let s = match self {
+            Self::None => "off",
+            Self::Load => "LOAD",
+            Self::Run => "RUN",
+            Self::Exec => "EXEC",
+        };
+        write!(f, "{value} ({s})", value = *self as u8)
+    }
+}
+
+// Accepts *OPT4 number or name of option
+impl FromStr for BootOption {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "0" | "none" | "off" => Self::None,
+            "1" | "load" => Self::Load,
+            "2" | "run" => Self::Run,
+            "3" | "exec" => Self::Exec,
+            _ => bail!("invalid boot option {s}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dfs::BootOption;
+    use anyhow::Result;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("0 (off)", BootOption::None)]
+    #[case("1 (LOAD)", BootOption::Load)]
+    #[case("2 (RUN)", BootOption::Run)]
+    #[case("3 (EXEC)", BootOption::Exec)]
+    fn display(#[case] expected: &str, #[case] input: BootOption) {
+        assert_eq!(expected, input.to_string());
+    }
+
+    #[rstest]
+    #[case(BootOption::None, "0")]
+    #[case(BootOption::None, "off")]
+    #[case(BootOption::Load, "load")]
+    #[case(BootOption::Run, "2")]
+    #[case(BootOption::Exec, "EXEC")]
+    fn from_str(#[case] expected: BootOption, #[case] input: &str) -> Result<()> {
+        assert_eq!(expected, input.parse()?);
+        Ok(())
+    }
+
+    #[test]
+    fn from_str_invalid() {
+        assert!("4".parse::<BootOption>().is_err());
     }
 }
@@ -1,83 +1,226 @@
+use crate::ddos_reader::DdosReader;
 use crate::dfs::{
-    BootOption, CatalogueBytes, CatalogueEntry, CycleNumber, DiscSize, DiscTitle, FileOffset,
-    SECTOR_BYTES, SectorSize, Side,
+    BootOption, CatalogueBytes, CatalogueEntry, CatalogueError, CatalogueFlavour, CycleNumber,
+    DFS_TOTAL_FILES, DdosVolume, DiscSize, DiscTitle, Extent, FileCount, FileOffset, FileSpec,
+    SECTOR_BYTES, SectorSize, Side, WATFORD_SIGNATURE, get_file_sector_count,
 };
 use crate::dsd_reader::DsdReader;
+use crate::image_format::ImageFormat;
 use crate::image_reader::ImageReader;
 use crate::ssd_reader::SsdReader;
 use anyhow::{Result, bail};
-use std::ffi::OsStr;
+use serde::Serialize;
+use std::cmp::Reverse;
 use std::fs::File;
+use std::io::{Read, Seek};
 use std::path::Path;
+use std::result::Result as StdResult;
 
 // https://beebwiki.mdfs.net/Acorn_DFS_disc_format
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Catalogue {
+    #[serde(rename = "discTitle")]
     pub disc_title: DiscTitle,
+
+    #[serde(rename = "cycleNumber")]
     pub cycle_number: CycleNumber,
+
+    #[serde(skip)]
     pub file_offset: FileOffset,
+
+    #[serde(rename = "bootOption")]
     pub boot_option: BootOption,
+
+    #[serde(rename = "discSize")]
     pub disc_size: DiscSize,
+
+    #[serde(rename = "catalogueFlavour")]
+    pub flavour: CatalogueFlavour,
+
+    #[serde(rename = "entries")]
     pub entries: Vec<CatalogueEntry>,
+
+    // DDOS volume this catalogue belongs to
+    #[serde(rename = "volume", skip_serializing_if = "Option::is_none")]
+    pub volume: Option<DdosVolume>,
+
+    // Problems found while parsing in lenient mode
+    #[serde(rename = "issues", skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<CatalogueError>,
 }
 
 impl Catalogue {
-    pub fn from_image_file(path: &Path) -> Result<Vec<Catalogue>> {
-        let f = File::open(path)?;
-        match path.extension().and_then(OsStr::to_str) {
-            Some("dsd") => {
-                let mut reader = DsdReader::new(f, SECTOR_BYTES)?;
-                Self::from_image_reader(&mut reader)
+    pub fn from_image_file(
+        path: &Path,
+        format: Option<ImageFormat>,
+        lenient: bool,
+    ) -> Result<Vec<Catalogue>> {
+        let mut f = File::open(path)?;
+        let format = ImageFormat::detect(&mut f, path, format)?;
+        match format {
+            ImageFormat::Dsd => {
+                let mut reader = DsdReader::new(f, SECTOR_BYTES, format.sectors_per_track())?;
+                Self::from_image_reader(&mut reader, lenient)
             }
-            Some("ssd") => {
+            ImageFormat::Ssd => {
                 let mut reader = SsdReader::new(f, SECTOR_BYTES)?;
-                Self::from_image_reader(&mut reader)
+                Self::from_image_reader(&mut reader, lenient)
+            }
+            ImageFormat::Dds | ImageFormat::Ddd => {
+                let mut reader = DdosReader::new(f, SECTOR_BYTES, format.sides())?;
+                Self::from_ddos_reader(&mut reader, lenient)
             }
-            _ => bail!("unsupported file type {path}", path = path.display()),
+            ImageFormat::Adfs => bail!(
+                "{path} is an ADFS disc image and has no DFS catalogue",
+                path = path.display()
+            ),
         }
     }
 
-    pub fn from_image_reader<R: ImageReader>(reader: &mut R) -> Result<Vec<Catalogue>> {
-        let sector_bytes = usize::from(reader.sector_bytes());
-        let mut bytes = vec![0; sector_bytes * 2];
+    // One catalogue for each volume of a DDOS disc
+    pub fn from_ddos_reader<R: Read + Seek>(
+        reader: &mut DdosReader<R>,
+        lenient: bool,
+    ) -> Result<Vec<Catalogue>> {
+        reader
+            .volumes()?
+            .into_iter()
+            .map(|volume| {
+                let bytes = reader.read_catalogue_bytes(volume)?;
+                let mut catalogue = if lenient {
+                    Self::from_catalogue_bytes_lenient(&bytes)?
+                } else {
+                    if !Self::is_valid_catalogue(&bytes) {
+                        bail!("volume {volume} does not contain a valid catalogue")
+                    }
+                    Self::from_catalogue_bytes(&bytes)?
+                };
+                catalogue.flavour = CatalogueFlavour::Ddos;
+                catalogue.volume = Some(volume);
+                Ok(catalogue)
+            })
+            .collect()
+    }
 
+    pub fn from_image_reader<R: ImageReader>(
+        reader: &mut R,
+        lenient: bool,
+    ) -> Result<Vec<Catalogue>> {
         (0..reader.sides())
             .map(|i| {
-                reader.read_bytes(Side::try_from(i)?, SectorSize::ZERO, &mut bytes)?;
-
-                if !Self::is_valid_catalogue(&bytes) {
-                    bail!("input file does not contain a valid disc image")
-                }
-
-                Self::from_catalogue_bytes(&bytes)
+                let side = Side::try_from(i)?;
+                Ok(if lenient {
+                    let bytes = Self::read_raw_catalogue_bytes(reader, side)?;
+                    Self::from_catalogue_bytes_lenient(&bytes)?
+                } else {
+                    let bytes = Self::read_catalogue_bytes(reader, side)?;
+                    Self::from_catalogue_bytes(&bytes)?
+                })
             })
             .collect::<Result<Vec<_>>>()
     }
 
+    pub fn read_catalogue_bytes<R: ImageReader>(reader: &mut R, side: Side) -> Result<Vec<u8>> {
+        let bytes = Self::read_raw_catalogue_bytes(reader, side)?;
+        if !Self::is_valid_catalogue(&bytes) {
+            bail!("input file does not contain a valid disc image")
+        }
+
+        Ok(bytes)
+    }
+
     #[allow(clippy::similar_names)]
-    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> Result<Self> {
+    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> StdResult<Self, CatalogueError> {
+        Self::check_len(bytes)?;
         let disc_title = DiscTitle::from_catalogue_bytes(bytes)?;
         let cycle_number = CycleNumber::from_catalogue_bytes(bytes)?;
         let file_offset = FileOffset::from_catalogue_bytes(bytes)?;
         let boot_option = BootOption::from_catalogue_bytes(bytes)?;
         let disc_size = DiscSize::from_catalogue_bytes(bytes)?;
-        let entries = CatalogueEntry::from_catalogue_bytes(bytes, file_offset.number())?;
+        let flavour = CatalogueFlavour::from_catalogue_bytes(bytes);
+        let mut entries = CatalogueEntry::from_catalogue_bytes(bytes, file_offset.number())?;
+        if flavour == CatalogueFlavour::Watford62 {
+            let bytes = Self::second_catalogue(bytes);
+            let file_offset = FileOffset::from_catalogue_bytes(bytes)?;
+            entries.extend(CatalogueEntry::from_catalogue_bytes(
+                bytes,
+                file_offset.number(),
+            )?);
+        }
         Ok(Self::new(
             disc_title,
             cycle_number,
             file_offset,
             boot_option,
             disc_size,
+            flavour,
             entries,
         ))
     }
 
+    // Best-effort parse of a damaged or copy-protected catalogue: invalid
+    // fields are replaced with plausible values and recorded in issues
+    #[allow(clippy::similar_names)]
+    pub fn from_catalogue_bytes_lenient(bytes: &CatalogueBytes) -> StdResult<Self, CatalogueError> {
+        Self::check_len(bytes)?;
+        let mut issues = Vec::new();
+        let disc_title =
+            CatalogueError::recover(DiscTitle::from_catalogue_bytes(bytes), &mut issues, || {
+                DiscTitle::from_catalogue_bytes_lossy(bytes)
+            });
+        let cycle_number = CatalogueError::recover(
+            CycleNumber::from_catalogue_bytes(bytes),
+            &mut issues,
+            CycleNumber::default,
+        );
+        let file_offset =
+            CatalogueError::recover(FileOffset::from_catalogue_bytes(bytes), &mut issues, || {
+                FileOffset::from_catalogue_bytes_lossy(bytes)
+            });
+        let boot_option =
+            CatalogueError::recover(BootOption::from_catalogue_bytes(bytes), &mut issues, || {
+                BootOption::from_catalogue_bytes_lossy(bytes)
+            });
+        let disc_size =
+            CatalogueError::recover(DiscSize::from_catalogue_bytes(bytes), &mut issues, || {
+                DiscSize::from_catalogue_bytes_lossy(bytes)
+            });
+        let flavour = CatalogueFlavour::from_catalogue_bytes(bytes);
+        let mut entries =
+            CatalogueEntry::from_catalogue_bytes_lenient(bytes, file_offset.number(), &mut issues);
+        if flavour == CatalogueFlavour::Watford62 {
+            let bytes = Self::second_catalogue(bytes);
+            let file_offset = CatalogueError::recover(
+                FileOffset::from_catalogue_bytes(bytes),
+                &mut issues,
+                || FileOffset::from_catalogue_bytes_lossy(bytes),
+            );
+            entries.extend(CatalogueEntry::from_catalogue_bytes_lenient(
+                bytes,
+                file_offset.number(),
+                &mut issues,
+            ));
+        }
+        let mut catalogue = Self::new(
+            disc_title,
+            cycle_number,
+            file_offset,
+            boot_option,
+            disc_size,
+            flavour,
+            entries,
+        );
+        catalogue.issues = issues;
+        Ok(catalogue)
+    }
+
     pub const fn new(
         disc_title: DiscTitle,
         cycle_number: CycleNumber,
         file_offset: FileOffset,
         boot_option: BootOption,
         disc_size: DiscSize,
+        flavour: CatalogueFlavour,
         entries: Vec<CatalogueEntry>,
     ) -> Self {
         Self {
@@ -86,22 +229,157 @@ impl Catalogue {
             file_offset,
             boot_option,
             disc_size,
+            flavour,
             entries,
+            volume: None,
+            issues: Vec::new(),
         }
     }
 
+    // The first 31 entries go in the main catalogue and any others in the
+    // Watford DFS second catalogue
     pub fn write_to_catalogue(&self, bytes: &mut [u8]) -> Result<()> {
+        if self.entries.len() > usize::from(self.flavour.max_files()) {
+            bail!("catalogue full")
+        }
+        if bytes.len() < self.flavour.catalogue_len() {
+            bail!("disc too small to hold catalogue")
+        }
+
+        let (entries, second_entries) = self
+            .entries
+            .split_at(self.entries.len().min(usize::from(DFS_TOTAL_FILES)));
+
         self.disc_title.write_to_catalogue(bytes);
         self.cycle_number.write_to_catalogue(bytes)?;
         self.file_offset.write_to_catalogue(bytes);
         self.boot_option.write_to_catalogue(bytes);
         self.disc_size.write_to_catalogue(bytes);
-        CatalogueEntry::write_to_catalogue(bytes, &self.entries)?;
+        CatalogueEntry::write_to_catalogue(bytes, entries)?;
+
+        if self.flavour == CatalogueFlavour::Watford62 {
+            let sector_bytes = usize::from(SECTOR_BYTES);
+            let (bytes, second_bytes) = bytes.split_at_mut(sector_bytes * 2);
+            second_bytes[0..8].fill(WATFORD_SIGNATURE);
+            second_bytes[sector_bytes..sector_bytes + 4].fill(0);
+            self.cycle_number.write_to_catalogue(second_bytes)?;
+            let file_count: FileCount = u8::try_from(second_entries.len())?.try_into()?;
+            FileOffset::from(file_count).write_to_catalogue(second_bytes);
+            second_bytes[sector_bytes + 6..sector_bytes + 8]
+                .copy_from_slice(&bytes[sector_bytes + 6..sector_bytes + 8]);
+            CatalogueEntry::write_to_catalogue(second_bytes, second_entries)?;
+        }
+
+        Ok(())
+    }
+
+    // Recalculate the main catalogue's file offset after entries are added
+    // or removed
+    pub fn update_file_offset(&mut self) -> Result<()> {
+        if self.entries.len() > usize::from(self.flavour.max_files()) {
+            bail!("catalogue full")
+        }
+
+        let file_count: FileCount =
+            u8::try_from(self.entries.len().min(usize::from(DFS_TOTAL_FILES)))?.try_into()?;
+        self.file_offset = file_count.into();
+        Ok(())
+    }
+
+    pub fn find_entry<T: FileSpec>(&self, spec: &T) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.descriptor.matches(spec))
+    }
+
+    // Runs of sectors between the first sector available for file data and
+    // the end of the disc not occupied by any file, in ascending order
+    pub fn free_extents(&self) -> Result<Vec<Extent>> {
+        let mut used = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let start = u16::from(entry.start_sector);
+                Ok((
+                    start,
+                    start + u16::from(get_file_sector_count(entry.length)?),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        used.sort_unstable();
+
+        let mut ranges = Vec::new();
+        let mut next = u16::from(self.flavour.start_sector());
+        for (start, end) in used {
+            if start > next {
+                ranges.push((next, start - next));
+            }
+            next = next.max(end);
+        }
+
+        let disc_size = u16::from(self.disc_size);
+        if disc_size > next {
+            ranges.push((next, disc_size - next));
+        }
+
+        ranges
+            .into_iter()
+            .map(|(start, count)| Ok(Extent::new(start.try_into()?, count.try_into()?)))
+            .collect()
+    }
+
+    // Find the first gap large enough to hold the given number of sectors
+    pub fn find_free_space(&self, sector_count: SectorSize) -> Result<Option<SectorSize>> {
+        let free_extents = self.free_extents()?;
+
+        // A zero-length file takes no space so fits even on a full disc, in
+        // which case it goes at the end of the disc
+        if sector_count == SectorSize::ZERO {
+            return Ok(Some(match free_extents.first() {
+                Some(extent) => extent.start_sector,
+                None => u16::from(self.disc_size).try_into()?,
+            }));
+        }
+
+        Ok(free_extents
+            .into_iter()
+            .find(|extent| u16::from(extent.sector_count) >= u16::from(sector_count))
+            .map(|extent| extent.start_sector))
+    }
+
+    // DFS keeps catalogue entries in descending order of start sector
+    pub fn sort_entries(&mut self) {
+        self.entries
+            .sort_by_key(|entry| Reverse(u16::from(entry.start_sector)));
+    }
+
+    // Includes the Watford DFS second catalogue if present
+    fn read_raw_catalogue_bytes<R: ImageReader>(reader: &mut R, side: Side) -> Result<Vec<u8>> {
+        let sector_bytes = usize::from(reader.sector_bytes());
+        let watford_len = CatalogueFlavour::Watford62.catalogue_len();
+        let mut bytes = if reader.side_len(side) >= watford_len as u64 {
+            vec![0; watford_len]
+        } else {
+            vec![0; sector_bytes * 2]
+        };
+        reader.read_bytes(side, SectorSize::ZERO, &mut bytes)?;
+        bytes.truncate(CatalogueFlavour::from_catalogue_bytes(&bytes).catalogue_len());
+        Ok(bytes)
+    }
+
+    fn second_catalogue(bytes: &CatalogueBytes) -> &CatalogueBytes {
+        &bytes[usize::from(SECTOR_BYTES) * 2..]
+    }
+
+    fn check_len(bytes: &CatalogueBytes) -> StdResult<(), CatalogueError> {
+        if bytes.len() < usize::from(SECTOR_BYTES) * 2 {
+            return Err(CatalogueError::Truncated { len: bytes.len() });
+        }
         Ok(())
     }
 
     // https://www.geraldholdsworth.co.uk/documents/DiscImage.pdf
-    fn is_valid_catalogue(bytes: &CatalogueBytes) -> bool {
+    pub fn is_valid_catalogue(bytes: &CatalogueBytes) -> bool {
         if !bytes[0x0000..0x0009]
             .iter()
             .all(|&b| (b & 0x80) == 0 && b > 31 || b == 0)
@@ -126,3 +404,156 @@ impl Catalogue {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::dfs::{
+        Address, BootOption, Catalogue, CatalogueEntry, CatalogueError, CatalogueFlavour,
+        CycleNumber, FileDescriptor, FileOffset, SECTOR_BYTES,
+    };
+    use anyhow::Result;
+    use rstest::rstest;
+
+    const SECTOR_BYTES_USIZE: usize = SECTOR_BYTES as usize;
+
+    fn make_catalogue() -> Vec<u8> {
+        let mut bytes = vec![0u8; 2 * SECTOR_BYTES_USIZE];
+        bytes[0..4].copy_from_slice(b"DISC");
+        bytes[8..15].copy_from_slice(b"FILE   ");
+        bytes[15] = b'$';
+        bytes[SECTOR_BYTES_USIZE + 5] = 0x08;
+        bytes[SECTOR_BYTES_USIZE + 6] = 0x33;
+        bytes[SECTOR_BYTES_USIZE + 7] = 0x20;
+        bytes[SECTOR_BYTES_USIZE + 8 + 4] = 0x10;
+        bytes[SECTOR_BYTES_USIZE + 8 + 7] = 0x02;
+        bytes
+    }
+
+    #[rstest]
+    #[case(CatalogueError::ReservedBits { value: 0x73 }, &[(SECTOR_BYTES_USIZE + 6, 0x73)])]
+    #[case(CatalogueError::InvalidDiscSize { value: 1 }, &[(SECTOR_BYTES_USIZE + 6, 0x30), (SECTOR_BYTES_USIZE + 7, 0x01)])]
+    #[case(CatalogueError::InvalidCycleNumber { value: 0x0a }, &[(SECTOR_BYTES_USIZE + 4, 0x0a)])]
+    #[case(CatalogueError::InvalidFileOffset { value: 0x09 }, &[(SECTOR_BYTES_USIZE + 5, 0x09)])]
+    #[case(CatalogueError::InvalidTitle { bytes: b"D\x01SC\0\0\0\0\0\0\0\0".to_vec() }, &[(1, 0x01)])]
+    #[case(CatalogueError::InvalidFileName { index: 0, bytes: b"F\xe9LE".to_vec() }, &[(9, 0xe9)])]
+    #[case(CatalogueError::InvalidDirectory { index: 0, value: b'.' }, &[(15, b'.')])]
+    fn strict_errors(#[case] expected: CatalogueError, #[case] patches: &[(usize, u8)]) {
+        let mut bytes = make_catalogue();
+        for &(offset, value) in patches {
+            bytes[offset] = value;
+        }
+        assert_eq!(
+            Some(expected.clone()),
+            Catalogue::from_catalogue_bytes(&bytes).err()
+        );
+
+        let catalogue = Catalogue::from_catalogue_bytes_lenient(&bytes).unwrap();
+        assert_eq!(vec![expected], catalogue.issues);
+    }
+
+    #[test]
+    fn lenient_substitutes_values() {
+        let mut bytes = make_catalogue();
+        bytes[9] = 0xe9;
+        bytes[15] = 0x80 | b'*';
+        bytes[SECTOR_BYTES_USIZE + 6] = 0xf3;
+        let catalogue = Catalogue::from_catalogue_bytes_lenient(&bytes).unwrap();
+        assert_eq!(3, catalogue.issues.len());
+        assert!(matches!(catalogue.boot_option, BootOption::Exec));
+        assert_eq!(0x320, u16::from(catalogue.disc_size));
+        let descriptor = &catalogue.entries[0].descriptor;
+        assert_eq!("F?LE", descriptor.file_name.as_str());
+        assert_eq!('?', descriptor.directory.to_char());
+        assert!(descriptor.locked);
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = [0u8; 100];
+        assert_eq!(
+            Some(CatalogueError::Truncated { len: 100 }),
+            Catalogue::from_catalogue_bytes(&bytes).err()
+        );
+        assert_eq!(
+            Some(CatalogueError::Truncated { len: 100 }),
+            Catalogue::from_catalogue_bytes_lenient(&bytes).err()
+        );
+    }
+
+    #[test]
+    fn arbitrary_bytes_do_not_panic() {
+        let mut state = 0x1234_5678u32;
+        for _ in 0..1000 {
+            let bytes = (0..2 * SECTOR_BYTES_USIZE)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state.to_le_bytes()[0]
+                })
+                .collect::<Vec<_>>();
+            _ = Catalogue::from_catalogue_bytes(&bytes);
+            let catalogue = Catalogue::from_catalogue_bytes_lenient(&bytes).unwrap();
+            assert!(catalogue.entries.len() <= 31);
+        }
+    }
+
+    #[test]
+    fn watford_round_trip() -> Result<()> {
+        let entries = (0..40u16)
+            .map(|i| {
+                Ok(CatalogueEntry::new(
+                    FileDescriptor::new(
+                        format!("F{i}").parse()?,
+                        '$'.try_into()?,
+                        false,
+                        Address::try_from(0x1900)?,
+                        Address::try_from(0x1900)?,
+                    ),
+                    0x100u32.try_into()?,
+                    (0x40 - i).try_into()?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut catalogue = Catalogue::new(
+            "WATFORD".parse()?,
+            CycleNumber::new(3)?,
+            FileOffset::new(0)?,
+            BootOption::Exec,
+            800u16.try_into()?,
+            CatalogueFlavour::Watford62,
+            entries,
+        );
+        catalogue.update_file_offset()?;
+
+        let mut bytes = vec![0u8; 4 * SECTOR_BYTES_USIZE];
+        catalogue.write_to_catalogue(&mut bytes)?;
+        assert_eq!(0xf8, bytes[SECTOR_BYTES_USIZE + 5]);
+        assert_eq!(
+            [0xaa; 8],
+            bytes[2 * SECTOR_BYTES_USIZE..2 * SECTOR_BYTES_USIZE + 8]
+        );
+        assert_eq!(0x48, bytes[3 * SECTOR_BYTES_USIZE + 5]);
+
+        let read = Catalogue::from_catalogue_bytes(&bytes)?;
+        assert_eq!(CatalogueFlavour::Watford62, read.flavour);
+        let names = |catalogue: &Catalogue| {
+            catalogue
+                .entries
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.descriptor.file_name.to_string(),
+                        u16::from(entry.start_sector),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&catalogue), names(&read));
+        assert_eq!(
+            CatalogueFlavour::Acorn,
+            Catalogue::from_catalogue_bytes(&bytes[..2 * SECTOR_BYTES_USIZE])?.flavour
+        );
+        Ok(())
+    }
+}
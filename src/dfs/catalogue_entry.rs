@@ -1,17 +1,56 @@
-use crate::dfs::{CatalogueBytes, FileDescriptor, Length, SECTOR_BYTES, SectorSize};
+use crate::dfs::{
+    Address, CatalogueBytes, CatalogueError, Directory, FileDescriptor, FileName, Length,
+    SECTOR_BYTES, SectorSize,
+};
 use anyhow::Result;
+use serde::Serialize;
+use std::result::Result as StdResult;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct CatalogueEntry {
+    #[serde(flatten)]
     pub descriptor: FileDescriptor,
+
+    #[serde(rename = "length")]
     pub length: Length,
+
+    #[serde(rename = "startSector")]
     pub start_sector: SectorSize,
 }
 
 impl CatalogueEntry {
-    pub fn from_catalogue_bytes(bytes: &CatalogueBytes, number: u8) -> Result<Vec<Self>> {
-        (0..number)
-            .map(|i| Self::from_catalogue_bytes_inner(bytes, i as usize))
+    pub fn from_catalogue_bytes(
+        bytes: &CatalogueBytes,
+        number: u8,
+    ) -> StdResult<Vec<Self>, CatalogueError> {
+        (0..usize::from(number))
+            .map(|index| {
+                let file_name = Self::read_file_name(bytes, index)?;
+                let directory = Self::read_directory(bytes, index)?;
+                Ok(Self::read_entry(bytes, index, file_name, directory))
+            })
+            .collect()
+    }
+
+    // Substitutes '?' for invalid characters in file names and directories,
+    // recording each problem in issues
+    pub fn from_catalogue_bytes_lenient(
+        bytes: &CatalogueBytes,
+        number: u8,
+        issues: &mut Vec<CatalogueError>,
+    ) -> Vec<Self> {
+        (0..usize::from(number))
+            .map(|index| {
+                let file_name =
+                    CatalogueError::recover(Self::read_file_name(bytes, index), issues, || {
+                        FileName::from_bytes_lossy(Self::file_name_bytes(bytes, index))
+                    });
+                let directory =
+                    CatalogueError::recover(Self::read_directory(bytes, index), issues, || {
+                        Directory::from_char_lossy(Self::directory_char(bytes, index))
+                    });
+                Self::read_entry(bytes, index, file_name, directory)
+            })
             .collect()
     }
 
@@ -30,34 +69,72 @@ impl CatalogueEntry {
         }
     }
 
-    fn from_catalogue_bytes_inner(bytes: &CatalogueBytes, index: usize) -> Result<Self> {
+    fn file_name_bytes(bytes: &CatalogueBytes, index: usize) -> &[u8] {
         let offset = (index + 1) * 8;
         let file_name_bytes = &bytes[offset..offset + 7];
-        let file_name_str = str::from_utf8(file_name_bytes)?.trim_end_matches(['\0', ' ']);
-        let file_name = file_name_str.parse()?;
-        let temp = bytes[offset + 7];
-        let locked = (temp & 0b1000_0000) != 0;
-        let d = (temp & 0b0111_1111) as char;
-        let directory = d.try_into()?;
+        let len = file_name_bytes
+            .iter()
+            .rposition(|&b| b != 0 && b != b' ')
+            .map_or(0, |i| i + 1);
+        &file_name_bytes[..len]
+    }
+
+    fn directory_char(bytes: &CatalogueBytes, index: usize) -> char {
+        char::from(bytes[(index + 1) * 8 + 7] & 0b0111_1111)
+    }
+
+    fn read_file_name(bytes: &CatalogueBytes, index: usize) -> StdResult<FileName, CatalogueError> {
+        let file_name_bytes = Self::file_name_bytes(bytes, index);
+        str::from_utf8(file_name_bytes)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| CatalogueError::InvalidFileName {
+                index,
+                bytes: file_name_bytes.to_vec(),
+            })
+    }
+
+    fn read_directory(
+        bytes: &CatalogueBytes,
+        index: usize,
+    ) -> StdResult<Directory, CatalogueError> {
+        let c = Self::directory_char(bytes, index);
+        c.try_into().map_err(|_| CatalogueError::InvalidDirectory {
+            index,
+            value: c as u8,
+        })
+    }
+
+    // Remaining fields are bit fields which cannot be invalid
+    fn read_entry(
+        bytes: &CatalogueBytes,
+        index: usize,
+        file_name: FileName,
+        directory: Directory,
+    ) -> Self {
+        let offset = (index + 1) * 8;
+        let locked = (bytes[offset + 7] & 0b1000_0000) != 0;
 
         let offset2 = usize::from(SECTOR_BYTES) + offset;
 
         let (load_address_top, execution_address_top, length_top, start_sector_top) =
             Self::extract_extra_bits(bytes[offset2 + 6]);
 
-        let load_address =
-            (u32::from(bytes[offset2]) + (u32::from(bytes[offset2 + 1]) << 8) + load_address_top)
-                .try_into()?;
-        let execution_address = (u32::from(bytes[offset2 + 2])
-            + (u32::from(bytes[offset2 + 3]) << 8)
-            + execution_address_top)
-            .try_into()?;
-        let length =
-            (u32::from(bytes[offset2 + 4]) + (u32::from(bytes[offset2 + 5]) << 8) + length_top)
-                .try_into()?;
-        let start_sector = (u16::from(bytes[offset2 + 7]) + start_sector_top).try_into()?;
-
-        Ok(Self::new(
+        let load_address = Address::from_bits_truncate(
+            u32::from(bytes[offset2]) + (u32::from(bytes[offset2 + 1]) << 8) + load_address_top,
+        );
+        let execution_address = Address::from_bits_truncate(
+            u32::from(bytes[offset2 + 2])
+                + (u32::from(bytes[offset2 + 3]) << 8)
+                + execution_address_top,
+        );
+        let length = Length::from_bits_truncate(
+            u32::from(bytes[offset2 + 4]) + (u32::from(bytes[offset2 + 5]) << 8) + length_top,
+        );
+        let start_sector =
+            SectorSize::from_bits_truncate(u16::from(bytes[offset2 + 7]) + start_sector_top);
+
+        Self::new(
             FileDescriptor::new(
                 file_name,
                 directory,
@@ -67,7 +144,7 @@ impl CatalogueEntry {
             ),
             length,
             start_sector,
-        ))
+        )
     }
 
     fn write_to_inner(&self, bytes: &mut [u8], index: usize) -> Result<()> {
@@ -98,7 +175,7 @@ impl CatalogueEntry {
         Ok(())
     }
 
-    fn extract_extra_bits(value: u8) -> (u32, u32, u32, u16) {
+    pub fn extract_extra_bits(value: u8) -> (u32, u32, u32, u16) {
         let load_address_top = u32::from((value & 0b0000_1100) >> 2) << 16;
         let execution_address_top = u32::from((value & 0b1100_0000) >> 6) << 16;
         let length_top = u32::from((value & 0b0011_0000) >> 4) << 16;
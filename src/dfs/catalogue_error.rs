@@ -0,0 +1,75 @@
+use serde::{Serialize, Serializer};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CatalogueError {
+    Truncated { len: usize },
+    InvalidTitle { bytes: Vec<u8> },
+    InvalidCycleNumber { value: u8 },
+    InvalidFileOffset { value: u8 },
+    ReservedBits { value: u8 },
+    InvalidDiscSize { value: u16 },
+    InvalidFileName { index: usize, bytes: Vec<u8> },
+    InvalidDirectory { index: usize, value: u8 },
+}
+
+impl CatalogueError {
+    // Record the error and substitute a best-effort value
+    pub fn recover<T, F>(result: Result<T, Self>, issues: &mut Vec<Self>, fallback: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        result.unwrap_or_else(|e| {
+            if !issues.contains(&e) {
+                issues.push(e);
+            }
+            fallback()
+        })
+    }
+}
+
+impl Display for CatalogueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Truncated { len } => {
+                write!(f, "catalogue truncated to {len} bytes")
+            }
+            Self::InvalidTitle { bytes } => {
+                write!(f, "invalid disc title {bytes:02X?}")
+            }
+            Self::InvalidCycleNumber { value } => {
+                write!(f, "invalid cycle number &{value:02X}")
+            }
+            Self::InvalidFileOffset { value } => {
+                write!(f, "invalid file offset &{value:02X}")
+            }
+            Self::ReservedBits { value } => {
+                write!(
+                    f,
+                    "reserved bits set in boot option/disc size byte &{value:02X}"
+                )
+            }
+            Self::InvalidDiscSize { value } => {
+                write!(f, "invalid disc size {value}")
+            }
+            Self::InvalidFileName { index, bytes } => {
+                write!(f, "invalid file name {bytes:02X?} in entry {index}")
+            }
+            Self::InvalidDirectory { index, value } => {
+                write!(f, "invalid directory &{value:02X} in entry {index}")
+            }
+        }
+    }
+}
+
+impl Error for CatalogueError {}
+
+impl Serialize for CatalogueError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
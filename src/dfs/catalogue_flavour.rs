@@ -0,0 +1,65 @@
+use crate::dfs::{
+    CatalogueBytes, DFS_TOTAL_FILES, SECTOR_BYTES, START_SECTOR, SectorSize, WATFORD_SIGNATURE,
+    WATFORD_START_SECTOR,
+};
+use serde::{Deserialize, Serialize};
+
+// Watford DFS can keep a second catalogue of up to 31 further files in
+// sectors 2 and 3, identified by eight signature bytes at the start of
+// sector 2
+// https://beebwiki.mdfs.net/Watford_DFS_disc_format
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum CatalogueFlavour {
+    #[default]
+    #[serde(rename = "acorn")]
+    Acorn,
+    #[serde(rename = "watford62")]
+    Watford62,
+    // Opus DDOS volume, whose catalogue is held outside the volume in track 0
+    #[serde(rename = "ddos")]
+    Ddos,
+}
+
+impl CatalogueFlavour {
+    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> Self {
+        let offset = usize::from(SECTOR_BYTES) * 2;
+        if bytes.len() >= Self::Watford62.catalogue_len()
+            && bytes[offset..offset + 8]
+                .iter()
+                .all(|&b| b == WATFORD_SIGNATURE)
+        {
+            Self::Watford62
+        } else {
+            Self::Acorn
+        }
+    }
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub const fn is_acorn(&self) -> bool {
+        matches!(self, Self::Acorn)
+    }
+
+    pub const fn max_files(self) -> u8 {
+        match self {
+            Self::Acorn | Self::Ddos => DFS_TOTAL_FILES,
+            Self::Watford62 => DFS_TOTAL_FILES * 2,
+        }
+    }
+
+    // First sector available for file data
+    pub const fn start_sector(self) -> SectorSize {
+        match self {
+            Self::Acorn => START_SECTOR,
+            Self::Watford62 => WATFORD_START_SECTOR,
+            Self::Ddos => SectorSize::ZERO,
+        }
+    }
+
+    pub fn catalogue_len(self) -> usize {
+        let sectors = match self {
+            Self::Acorn | Self::Ddos => 2,
+            Self::Watford62 => 4,
+        };
+        sectors * usize::from(SECTOR_BYTES)
+    }
+}
@@ -0,0 +1,443 @@
+use crate::dfs::{
+    CatalogueBytes, CatalogueEntry, CatalogueError, CatalogueFlavour, DFS_TOTAL_FILES,
+    SECTOR_BYTES, SectorSize, Side,
+};
+use crate::image_reader::ImageReader;
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum IssueKind {
+    #[serde(rename = "missing-catalogue")]
+    MissingCatalogue,
+    #[serde(rename = "invalid-title")]
+    InvalidTitle,
+    #[serde(rename = "invalid-file-count")]
+    InvalidFileCount,
+    #[serde(rename = "reserved-bits")]
+    ReservedBits,
+    #[serde(rename = "invalid-disc-size")]
+    InvalidDiscSize,
+    #[serde(rename = "illegal-character")]
+    IllegalCharacter,
+    #[serde(rename = "duplicate-name")]
+    DuplicateName,
+    #[serde(rename = "unsorted")]
+    Unsorted,
+    #[serde(rename = "overlaps-catalogue")]
+    OverlapsCatalogue,
+    #[serde(rename = "overlap")]
+    Overlap,
+    #[serde(rename = "past-end-of-disc")]
+    PastEndOfDisc,
+    #[serde(rename = "length-mismatch")]
+    LengthMismatch,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    #[serde(rename = "kind")]
+    pub kind: IssueKind,
+
+    #[serde(rename = "message")]
+    pub message: String,
+
+    #[serde(rename = "file", skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    #[serde(rename = "sector", skip_serializing_if = "Option::is_none")]
+    pub sector: Option<u16>,
+}
+
+impl Issue {
+    fn new(kind: IssueKind, message: String, file: Option<&RawEntry>, sector: Option<u16>) -> Self {
+        Self {
+            kind,
+            message,
+            file: file.map(RawEntry::display_name),
+            sector,
+        }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(file) = &self.file {
+            write!(f, "{file}: ")?;
+        }
+        if let Some(sector) = self.sector {
+            write!(f, "sector &{sector:03X}: ")?;
+        }
+        Display::fmt(&self.message, f)
+    }
+}
+
+// Catalogue entry decoded leniently along with the fields needed to report
+// problems with it
+struct RawEntry {
+    index: usize,
+    entry: CatalogueEntry,
+    legal: bool,
+}
+
+impl RawEntry {
+    fn display_name(&self) -> String {
+        format!(
+            "{directory}.{name} (entry {index})",
+            directory = self.entry.descriptor.directory,
+            name = self.entry.descriptor.file_name,
+            index = self.index
+        )
+    }
+
+    fn key(&self) -> (char, String) {
+        (
+            self.entry
+                .descriptor
+                .directory
+                .to_char()
+                .to_ascii_uppercase(),
+            self.entry
+                .descriptor
+                .file_name
+                .as_str()
+                .to_ascii_uppercase(),
+        )
+    }
+
+    fn length(&self) -> u32 {
+        u32::from(self.entry.length)
+    }
+
+    fn start_sector(&self) -> u16 {
+        u16::from(self.entry.start_sector)
+    }
+
+    fn sector_count(&self) -> u16 {
+        u16::try_from(self.length().div_ceil(u32::from(SECTOR_BYTES))).unwrap_or(u16::MAX)
+    }
+
+    fn end_sector(&self) -> u32 {
+        u32::from(self.start_sector()) + u32::from(self.sector_count())
+    }
+}
+
+pub fn check_side<R: ImageReader>(reader: &mut R, side: Side) -> Result<Vec<Issue>> {
+    let side_len = reader.side_len(side);
+    let mut bytes = vec![0; usize::from(reader.sector_bytes()) * 2];
+    if side_len < bytes.len() as u64 {
+        return Ok(vec![Issue::new(
+            IssueKind::MissingCatalogue,
+            format!("image holds only {side_len} bytes of this side"),
+            None,
+            Some(0),
+        )]);
+    }
+
+    // Include the Watford DFS second catalogue if there is room for one
+    let watford_len = CatalogueFlavour::Watford62.catalogue_len();
+    if side_len >= watford_len as u64 {
+        bytes.resize(watford_len, 0);
+    }
+
+    reader.read_bytes(side, SectorSize::ZERO, &mut bytes)?;
+    Ok(check_catalogue(&bytes, side_len))
+}
+
+// Report every problem found in the catalogue of one side of a disc, given
+// the number of bytes of that side actually present in the image
+pub fn check_catalogue(bytes: &CatalogueBytes, side_len: u64) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let sector_bytes = usize::from(SECTOR_BYTES);
+
+    let title = bytes[0..8]
+        .iter()
+        .chain(&bytes[sector_bytes..sector_bytes + 4]);
+    if let Some(b) = title.copied().find(|&b| b != 0 && !(32..=126).contains(&b)) {
+        issues.push(Issue::new(
+            IssueKind::InvalidTitle,
+            format!("disc title contains illegal character &{b:02X}"),
+            None,
+            Some(0),
+        ));
+    }
+
+    let flavour = CatalogueFlavour::from_catalogue_bytes(bytes);
+    let mut entries = read_entries(&mut issues, bytes, 0, 0);
+    if flavour == CatalogueFlavour::Watford62 {
+        entries.extend(read_entries(
+            &mut issues,
+            &bytes[sector_bytes * 2..],
+            2,
+            usize::from(DFS_TOTAL_FILES),
+        ));
+    }
+
+    let temp = bytes[sector_bytes + 6];
+    if temp & 0b1100_1100 != 0 {
+        issues.push(Issue::new(
+            IssueKind::ReservedBits,
+            format!("reserved bits set in boot option/disc size byte &{temp:02X}"),
+            None,
+            Some(1),
+        ));
+    }
+
+    let disc_size = (u16::from(temp & 0b0000_0011) << 8) + u16::from(bytes[sector_bytes + 7]);
+    if disc_size < u16::from(flavour.start_sector()) {
+        issues.push(Issue::new(
+            IssueKind::InvalidDiscSize,
+            format!("disc size {disc_size} is too small to hold catalogue"),
+            None,
+            Some(1),
+        ));
+    }
+
+    for entry in &entries {
+        check_entry(&mut issues, entry, flavour, disc_size, side_len);
+    }
+
+    for pair in entries.windows(2) {
+        if pair[0].start_sector() < pair[1].start_sector() {
+            issues.push(Issue::new(
+                IssueKind::Unsorted,
+                format!(
+                    "entry is not in descending order of start sector: next entry {next} starts at &{next_sector:03X}",
+                    next = pair[1].display_name(),
+                    next_sector = pair[1].start_sector()
+                ),
+                Some(&pair[0]),
+                Some(pair[0].start_sector()),
+            ));
+        }
+    }
+
+    for (i, a) in entries.iter().enumerate() {
+        for b in &entries[i + 1..] {
+            if a.key() == b.key() {
+                issues.push(Issue::new(
+                    IssueKind::DuplicateName,
+                    format!("duplicate of {other}", other = a.display_name()),
+                    Some(b),
+                    None,
+                ));
+            }
+
+            let start = a.start_sector().max(b.start_sector());
+            if u32::from(start) < a.end_sector().min(b.end_sector()) {
+                issues.push(Issue::new(
+                    IssueKind::Overlap,
+                    format!("overlaps {other}", other = a.display_name()),
+                    Some(b),
+                    Some(start),
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+// Validate the file count of the catalogue held in the first two sectors of
+// bytes and read its entries
+fn read_entries(
+    issues: &mut Vec<Issue>,
+    bytes: &CatalogueBytes,
+    first_sector: u16,
+    first_index: usize,
+) -> Vec<RawEntry> {
+    let sector_bytes = usize::from(SECTOR_BYTES);
+    let file_offset = bytes[sector_bytes + 5];
+    if file_offset & 0b0000_0111 != 0 {
+        issues.push(Issue::new(
+            IssueKind::InvalidFileCount,
+            format!("file offset &{file_offset:02X} is not a multiple of 8"),
+            None,
+            Some(first_sector + 1),
+        ));
+    }
+
+    let mut file_count = usize::from(file_offset >> 3);
+    if file_count > usize::from(DFS_TOTAL_FILES) {
+        issues.push(Issue::new(
+            IssueKind::InvalidFileCount,
+            format!("file count {file_count} exceeds maximum of {DFS_TOTAL_FILES}"),
+            None,
+            Some(first_sector + 1),
+        ));
+        file_count = usize::from(DFS_TOTAL_FILES);
+    }
+
+    // Illegal characters are reported by check_entry, so only note which
+    // entries had them
+    let mut errors = Vec::new();
+    let entries = CatalogueEntry::from_catalogue_bytes_lenient(
+        bytes,
+        u8::try_from(file_count).unwrap_or(DFS_TOTAL_FILES),
+        &mut errors,
+    );
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| RawEntry {
+            index: first_index + i,
+            entry,
+            legal: !errors.iter().any(|e| {
+                matches!(e, CatalogueError::InvalidFileName { index, .. }
+                    | CatalogueError::InvalidDirectory { index, .. } if *index == i)
+            }),
+        })
+        .collect()
+}
+
+fn check_entry(
+    issues: &mut Vec<Issue>,
+    entry: &RawEntry,
+    flavour: CatalogueFlavour,
+    disc_size: u16,
+    side_len: u64,
+) {
+    if !entry.legal {
+        issues.push(Issue::new(
+            IssueKind::IllegalCharacter,
+            String::from("file name or directory contains illegal characters"),
+            Some(entry),
+            None,
+        ));
+    }
+
+    if entry.sector_count() == 0 {
+        return;
+    }
+
+    if entry.start_sector() < u16::from(flavour.start_sector()) {
+        issues.push(Issue::new(
+            IssueKind::OverlapsCatalogue,
+            String::from("file overlaps catalogue"),
+            Some(entry),
+            Some(entry.start_sector()),
+        ));
+    }
+
+    if entry.end_sector() > u32::from(disc_size) {
+        issues.push(Issue::new(
+            IssueKind::PastEndOfDisc,
+            format!(
+                "file of {count} sectors runs past end of disc ({disc_size} sectors)",
+                count = entry.sector_count()
+            ),
+            Some(entry),
+            Some(entry.start_sector()),
+        ));
+    }
+
+    let end_byte =
+        u64::from(entry.start_sector()) * u64::from(SECTOR_BYTES) + u64::from(entry.length());
+    if end_byte > side_len {
+        issues.push(Issue::new(
+            IssueKind::LengthMismatch,
+            format!(
+                "file length &{length:06X} ({count} sectors) extends past end of image data at byte &{side_len:X}",
+                length = entry.length(),
+                count = entry.sector_count()
+            ),
+            Some(entry),
+            Some(entry.start_sector()),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dfs::{IssueKind, SECTOR_BYTES, check_catalogue};
+    use rstest::rstest;
+
+    const SIDE_LEN: u64 = 1024 * SECTOR_BYTES as u64;
+
+    fn make_catalogue(entries: &[(&[u8; 7], u8, u32, u16)]) -> Vec<u8> {
+        let sector_bytes = usize::from(SECTOR_BYTES);
+        let mut bytes = vec![0u8; 2 * sector_bytes];
+        bytes[0..4].copy_from_slice(b"DISC");
+        bytes[sector_bytes + 5] = u8::try_from(entries.len() * 8).unwrap();
+        bytes[sector_bytes + 6] = 0x03;
+        bytes[sector_bytes + 7] = 0x20;
+        for (i, (name, directory, length, start_sector)) in entries.iter().enumerate() {
+            let offset = (i + 1) * 8;
+            bytes[offset..offset + 7].copy_from_slice(*name);
+            bytes[offset + 7] = *directory;
+            let offset2 = sector_bytes + offset;
+            let [length_0, length_1, length_2, _] = length.to_le_bytes();
+            let [start_sector_0, start_sector_1] = start_sector.to_le_bytes();
+            bytes[offset2 + 4] = length_0;
+            bytes[offset2 + 5] = length_1;
+            bytes[offset2 + 6] = (length_2 << 4) | start_sector_1;
+            bytes[offset2 + 7] = start_sector_0;
+        }
+        bytes
+    }
+
+    fn kinds(bytes: &[u8], side_len: u64) -> Vec<IssueKind> {
+        check_catalogue(bytes, side_len)
+            .into_iter()
+            .map(|issue| issue.kind)
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let bytes = make_catalogue(&[
+            (b"B      ", b'$', 0x200, 0x10),
+            (b"A      ", b'$', 0x100, 0x02),
+        ]);
+        assert!(kinds(&bytes, SIDE_LEN).is_empty());
+    }
+
+    #[rstest]
+    #[case(IssueKind::Overlap, &[(b"B      ", b'$', 0x300, 0x04), (b"A      ", b'$', 0x300, 0x02)])]
+    #[case(IssueKind::Unsorted, &[(b"A      ", b'$', 0x100, 0x02), (b"B      ", b'$', 0x100, 0x04)])]
+    #[case(IssueKind::DuplicateName, &[(b"ABC    ", b'$', 0x100, 0x04), (b"abc    ", b'$', 0x100, 0x02)])]
+    #[case(IssueKind::OverlapsCatalogue, &[(b"A      ", b'$', 0x100, 0x01)])]
+    #[case(IssueKind::PastEndOfDisc, &[(b"A      ", b'$', 0x200, 0x31f)])]
+    #[case(IssueKind::IllegalCharacter, &[(b"A.B    ", b'$', 0x100, 0x02)])]
+    #[case(IssueKind::IllegalCharacter, &[(b"A      ", 0x01, 0x100, 0x02)])]
+    fn entry_issues(#[case] expected: IssueKind, #[case] entries: &[(&[u8; 7], u8, u32, u16)]) {
+        assert_eq!(vec![expected], kinds(&make_catalogue(entries), SIDE_LEN));
+    }
+
+    #[test]
+    fn length_mismatch() {
+        let bytes = make_catalogue(&[(b"A      ", b'$', 0x1000, 0x02)]);
+        assert_eq!(
+            vec![IssueKind::LengthMismatch],
+            kinds(&bytes, u64::from(SECTOR_BYTES) * 10)
+        );
+    }
+
+    #[test]
+    fn catalogue_header_issues() {
+        let mut bytes = make_catalogue(&[]);
+        bytes[2] = 0x07;
+        bytes[usize::from(SECTOR_BYTES) + 5] = 0x04;
+        bytes[usize::from(SECTOR_BYTES) + 6] = 0x40;
+        bytes[usize::from(SECTOR_BYTES) + 7] = 0x01;
+        assert_eq!(
+            vec![
+                IssueKind::InvalidTitle,
+                IssueKind::InvalidFileCount,
+                IssueKind::ReservedBits,
+                IssueKind::InvalidDiscSize
+            ],
+            kinds(&bytes, SIDE_LEN)
+        );
+    }
+
+    #[test]
+    fn watford() {
+        let sector_bytes = usize::from(SECTOR_BYTES);
+        let mut bytes = make_catalogue(&[(b"A      ", b'$', 0x100, 0x04)]);
+        bytes.extend(make_catalogue(&[(b"B      ", b'$', 0x100, 0x03)]));
+        bytes[2 * sector_bytes..2 * sector_bytes + 8].fill(0xaa);
+        assert_eq!(vec![IssueKind::OverlapsCatalogue], kinds(&bytes, SIDE_LEN));
+    }
+}
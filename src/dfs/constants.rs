@@ -2,10 +2,16 @@ use crate::dfs::{SectorBytes, SectorSize};
 
 pub const START_SECTOR: SectorSize = SectorSize::TWO;
 
+pub const WATFORD_START_SECTOR: SectorSize = SectorSize::FOUR;
+
+pub const WATFORD_SIGNATURE: u8 = 0xaa;
+
 pub const SIDES_PER_DISC: usize = 2;
 
 pub const SECTORS_PER_TRACK: usize = 10;
 
+pub const DD_SECTORS_PER_TRACK: usize = 18;
+
 pub const SECTOR_BYTES: SectorBytes = 256;
 
 pub const DFS_TOTAL_FILES: u8 = 31;
@@ -1,11 +1,11 @@
-use crate::dfs::{CatalogueBytes, SECTOR_BYTES};
+use crate::dfs::{CatalogueBytes, CatalogueError, SECTOR_BYTES};
 use anyhow::{Result, bail};
 use serde::de::Error as SerdeError;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::result::Result as StdResult;
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Serialize)]
 pub struct CycleNumber(u8);
 
 impl CycleNumber {
@@ -17,11 +17,19 @@ impl CycleNumber {
         Ok(Self(value))
     }
 
-    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> Result<Self> {
-        Self::new(Self::from_bcd(bytes[usize::from(SECTOR_BYTES) + 4])?)
+    // DFS increments the cycle number each time the catalogue is written
+    pub const fn next(self) -> Self {
+        Self((self.0 + 1) % 100)
     }
 
-    pub fn write_to_catalogue(&self, bytes: &mut [u8]) -> Result<()> {
+    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> StdResult<Self, CatalogueError> {
+        let value = bytes[usize::from(SECTOR_BYTES) + 4];
+        Self::from_bcd(value)
+            .and_then(Self::new)
+            .map_err(|_| CatalogueError::InvalidCycleNumber { value })
+    }
+
+    pub fn write_to_catalogue(self, bytes: &mut [u8]) -> Result<()> {
         bytes[usize::from(SECTOR_BYTES) + 4] = Self::to_bcd(self.0)?;
         Ok(())
     }
@@ -96,6 +104,15 @@ mod tests {
         Ok(())
     }
 
+    #[rstest]
+    #[case(1, 0)]
+    #[case(43, 42)]
+    #[case(0, 99)]
+    fn next(#[case] expected_result: u8, #[case] input: u8) -> Result<()> {
+        assert_eq!(expected_result, u8::from(CycleNumber::new(input)?.next()));
+        Ok(())
+    }
+
     #[test]
     fn bcd_errors() {
         assert!(CycleNumber::from_bcd(0x0a).is_err());
@@ -0,0 +1,142 @@
+use crate::dfs::{DD_SECTORS_PER_TRACK, SECTOR_BYTES, Side};
+use anyhow::{Result, bail};
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::result::Result as StdResult;
+
+// Opus DDOS divides each side of a double-density disc into up to eight
+// volumes A-H, each with its own catalogue in track 0: volume A's catalogue
+// occupies sectors 0 and 1, volume B's sectors 2 and 3 and so on. Sector 16
+// holds the volume table:
+// &00     : &20
+// &01-&02 : total number of sectors on the side (high byte first)
+// &03     : sectors per track
+// &04     : number of tracks
+// &08-&17 : start track of each volume followed by a zero byte, or zero if
+//           the volume is not present
+// File start sectors in each volume's catalogue are relative to the start of
+// the volume's first track
+pub const DDOS_VOLUME_TABLE_SECTOR: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DdosVolume {
+    pub side: Side,
+    pub letter: char,
+    pub start_track: u8,
+}
+
+impl DdosVolume {
+    pub fn from_volume_table(bytes: &[u8], side: Side) -> Result<Vec<Self>> {
+        if bytes.len() < usize::from(SECTOR_BYTES) {
+            bail!("DDOS volume table truncated")
+        }
+
+        let sectors_per_track = bytes[3];
+        if usize::from(sectors_per_track) != DD_SECTORS_PER_TRACK {
+            bail!("invalid sectors per track {sectors_per_track} in DDOS volume table")
+        }
+
+        let track_count = bytes[4];
+        let total_sectors = u16::from_be_bytes([bytes[1], bytes[2]]);
+        if track_count == 0
+            || total_sectors != u16::from(track_count) * u16::from(sectors_per_track)
+        {
+            bail!("invalid disc size {total_sectors} in DDOS volume table")
+        }
+
+        let mut volumes = Vec::new();
+        for (i, letter) in ('A'..='H').enumerate() {
+            let offset = 8 + i * 2;
+            let start_track = bytes[offset];
+            if bytes[offset + 1] != 0 || start_track >= track_count {
+                bail!("invalid entry for volume {letter} in DDOS volume table")
+            }
+            if start_track != 0 {
+                volumes.push(Self {
+                    side,
+                    letter,
+                    start_track,
+                });
+            }
+        }
+
+        if volumes.is_empty() {
+            bail!("no volumes in DDOS volume table")
+        }
+
+        Ok(volumes)
+    }
+
+    // First of the two sectors in track 0 holding this volume's catalogue
+    pub const fn catalogue_sector(self) -> usize {
+        (self.letter as usize - 'A' as usize) * 2
+    }
+
+    // Sector of the side, counting from track 0, at which the volume starts
+    pub fn start_sector(self) -> usize {
+        usize::from(self.start_track) * DD_SECTORS_PER_TRACK
+    }
+}
+
+// Drive number and volume letter as used by DDOS, e.g. 0A or 2C
+impl Display for DdosVolume {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let drive = if u8::from(self.side) == 0 { 0 } else { 2 };
+        write!(f, "{drive}{letter}", letter = self.letter)
+    }
+}
+
+impl Serialize for DdosVolume {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dfs::{DdosVolume, SECTOR_BYTES, Side};
+    use rstest::rstest;
+
+    fn make_volume_table(start_tracks: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0u8; usize::from(SECTOR_BYTES)];
+        bytes[0] = 0x20;
+        bytes[1..3].copy_from_slice(&(80u16 * 18).to_be_bytes());
+        bytes[3] = 18;
+        bytes[4] = 80;
+        for (i, &start_track) in start_tracks.iter().enumerate() {
+            bytes[8 + i * 2] = start_track;
+        }
+        bytes
+    }
+
+    #[test]
+    fn volumes() {
+        let volumes =
+            DdosVolume::from_volume_table(&make_volume_table(&[1, 0, 41]), Side::Side1).unwrap();
+        assert_eq!(
+            vec![(0, "2A".to_string(), 1), (4, "2C".to_string(), 41)],
+            volumes
+                .iter()
+                .map(|v| (v.catalogue_sector(), v.to_string(), v.start_track))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    #[case(&[(3, 10)])]
+    #[case(&[(4, 0)])]
+    #[case(&[(2, 0)])]
+    #[case(&[(8, 0)])]
+    #[case(&[(8, 80)])]
+    #[case(&[(9, 1)])]
+    fn invalid(#[case] patches: &[(usize, u8)]) {
+        let mut bytes = make_volume_table(&[1]);
+        for &(offset, value) in patches {
+            bytes[offset] = value;
+        }
+        assert!(DdosVolume::from_volume_table(&bytes, Side::Side0).is_err());
+    }
+}
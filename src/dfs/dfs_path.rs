@@ -1,7 +1,9 @@
 use crate::dfs::{Directory, FileName, FileSpec};
 use anyhow::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::str::FromStr;
 
+#[derive(Clone, Debug)]
 pub struct DfsPath {
     pub directory: Directory,
     pub file_name: FileName,
@@ -12,19 +14,10 @@ impl FromStr for DfsPath {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (directory, s) = match s.split_once('.') {
-            Some((prefix, suffix)) => {
-                if prefix.len() == 1 {
-                    let c = prefix
-                        .chars()
-                        .collect::<Vec<_>>()
-                        .first()
-                        .unwrap()
-                        .to_owned();
-                    (c.try_into().unwrap_or(Directory::ROOT), suffix)
-                } else {
-                    (Directory::ROOT, s)
-                }
-            }
+            Some((prefix, suffix)) => match prefix.chars().next() {
+                Some(c) if prefix.len() == 1 => (c.try_into().unwrap_or(Directory::ROOT), suffix),
+                _ => (Directory::ROOT, s),
+            },
             _ => (Directory::ROOT, s),
         };
 
@@ -36,6 +29,17 @@ impl FromStr for DfsPath {
     }
 }
 
+impl Display for DfsPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{directory}.{file_name}",
+            directory = self.directory,
+            file_name = self.file_name
+        )
+    }
+}
+
 impl FileSpec for DfsPath {
     fn directory(&self) -> &Directory {
         &self.directory
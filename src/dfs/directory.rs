@@ -22,6 +22,15 @@ impl Directory {
     pub const fn to_char(self) -> char {
         self.0
     }
+
+    // Replaces an invalid directory with '?'
+    pub fn from_char_lossy(value: char) -> Self {
+        if is_file_name_char(value) {
+            Self(value)
+        } else {
+            Self('?')
+        }
+    }
 }
 
 impl PartialOrd for Directory {
@@ -1,27 +1,46 @@
-use crate::dfs::{CatalogueBytes, SECTOR_BYTES};
+use crate::dfs::{CatalogueBytes, CatalogueError, SECTOR_BYTES, START_SECTOR};
 use crate::u10;
-use anyhow::Result;
 use serde::de::Error as SerdeError;
 use serde::{Deserialize, Deserializer};
+use std::result::Result as StdResult;
 
 u10!(DiscSize);
 
 impl DiscSize {
-    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> Result<Self> {
-        let lo_bits = bytes[usize::from(SECTOR_BYTES) + 7];
+    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> StdResult<Self, CatalogueError> {
         let temp = bytes[usize::from(SECTOR_BYTES) + 6];
-        assert_eq!(0, temp & 0b1100_1100);
-        let hi_bits = u16::from(temp & 0b0000_0011) << 8;
-        let disc_size = hi_bits + u16::from(lo_bits);
-        assert!((2..=1023).contains(&disc_size));
-        disc_size.try_into()
+        if temp & 0b1100_1100 != 0 {
+            return Err(CatalogueError::ReservedBits { value: temp });
+        }
+        let disc_size = Self::read_bits(bytes);
+        if disc_size < START_SECTOR.to_u16() {
+            return Err(CatalogueError::InvalidDiscSize { value: disc_size });
+        }
+        Ok(Self(disc_size))
+    }
+
+    // Ignores reserved bits and falls back to default size if too small
+    pub fn from_catalogue_bytes_lossy(bytes: &CatalogueBytes) -> Self {
+        let disc_size = Self::read_bits(bytes);
+        if disc_size < START_SECTOR.to_u16() {
+            Self::default()
+        } else {
+            Self(disc_size)
+        }
     }
 
     pub fn write_to_catalogue(self, bytes: &mut [u8]) {
         let hi = (self.0 >> 8) as u8;
         let lo = (self.0 & 0xff) as u8;
         bytes[usize::from(SECTOR_BYTES) + 7] = lo;
-        bytes[usize::from(SECTOR_BYTES) + 6] |= hi;
+        let offset = usize::from(SECTOR_BYTES) + 6;
+        bytes[offset] = (bytes[offset] & !0b0000_0011) | hi;
+    }
+
+    fn read_bits(bytes: &CatalogueBytes) -> u16 {
+        let lo_bits = bytes[usize::from(SECTOR_BYTES) + 7];
+        let hi_bits = u16::from(bytes[usize::from(SECTOR_BYTES) + 6] & 0b0000_0011) << 8;
+        hi_bits + u16::from(lo_bits)
     }
 }
 
@@ -1,5 +1,6 @@
-use crate::dfs::{CatalogueBytes, SECTOR_BYTES, is_disc_title_char};
-use anyhow::{Error, Result, bail};
+use crate::dfs::{CatalogueBytes, CatalogueError, SECTOR_BYTES, is_disc_title_char};
+use crate::util::is_ascii_printable;
+use anyhow::{Error, bail};
 use serde::de::Error as SerdeError;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt::{Display, Formatter, Result as FmtResult};
@@ -10,19 +11,48 @@ use std::str::FromStr;
 pub struct DiscTitle(String);
 
 impl DiscTitle {
-    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> Result<Self> {
-        let mut title = String::with_capacity(12);
-        title.push_str(str::from_utf8(&bytes[0..8])?);
-        title.push_str(str::from_utf8(
-            &bytes[usize::from(SECTOR_BYTES)..usize::from(SECTOR_BYTES) + 4],
-        )?);
-        let s = title.trim_end_matches(' ').trim_end_matches('\0');
-        s.parse()
+    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> StdResult<Self, CatalogueError> {
+        let title_bytes = Self::read_bytes(bytes);
+        str::from_utf8(&title_bytes)
+            .ok()
+            .and_then(|s| Self::trim(s).parse().ok())
+            .ok_or(CatalogueError::InvalidTitle { bytes: title_bytes })
     }
 
+    // Replaces invalid characters with '?'
+    pub fn from_catalogue_bytes_lossy(bytes: &CatalogueBytes) -> Self {
+        let title = Self::read_bytes(bytes)
+            .into_iter()
+            .map(|b| {
+                if b == 0 || is_ascii_printable(b) {
+                    char::from(b)
+                } else {
+                    '?'
+                }
+            })
+            .collect::<String>();
+        Self(String::from(Self::trim(&title)))
+    }
+
+    // First eight characters are stored in sector 0 and the remaining four
+    // in sector 1, padded with NULs
     pub fn write_to_catalogue(&self, bytes: &mut [u8]) {
-        let temp = self.0.as_bytes();
-        bytes[0..temp.len()].copy_from_slice(temp);
+        let mut temp = [0u8; 12];
+        temp[0..self.0.len()].copy_from_slice(self.0.as_bytes());
+        bytes[0..8].copy_from_slice(&temp[0..8]);
+        bytes[usize::from(SECTOR_BYTES)..usize::from(SECTOR_BYTES) + 4]
+            .copy_from_slice(&temp[8..12]);
+    }
+
+    fn read_bytes(bytes: &CatalogueBytes) -> Vec<u8> {
+        let mut title_bytes = bytes[0..8].to_vec();
+        title_bytes
+            .extend_from_slice(&bytes[usize::from(SECTOR_BYTES)..usize::from(SECTOR_BYTES) + 4]);
+        title_bytes
+    }
+
+    fn trim(s: &str) -> &str {
+        s.trim_end_matches(' ').trim_end_matches('\0')
     }
 }
 
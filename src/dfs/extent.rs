@@ -0,0 +1,17 @@
+use crate::dfs::SectorSize;
+
+// A contiguous run of sectors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Extent {
+    pub start_sector: SectorSize,
+    pub sector_count: SectorSize,
+}
+
+impl Extent {
+    pub const fn new(start_sector: SectorSize, sector_count: SectorSize) -> Self {
+        Self {
+            start_sector,
+            sector_count,
+        }
+    }
+}
@@ -1,13 +1,23 @@
 use crate::dfs::{Address, Directory, FileName, FileSpec};
 use crate::metadata::{File, FileType};
+use serde::Serialize;
 use std::path::PathBuf;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct FileDescriptor {
+    #[serde(rename = "fileName")]
     pub file_name: FileName,
+
+    #[serde(rename = "directory")]
     pub directory: Directory,
+
+    #[serde(rename = "locked")]
     pub locked: bool,
+
+    #[serde(rename = "loadAddress")]
     pub load_address: Address,
+
+    #[serde(rename = "executionAddress")]
     pub execution_address: Address,
 }
 
@@ -13,6 +13,23 @@ impl FileName {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    // Replaces invalid characters with '?'
+    pub fn from_bytes_lossy(bytes: &[u8]) -> Self {
+        let s = bytes
+            .iter()
+            .take(7)
+            .map(|&b| {
+                let c = char::from(b);
+                if is_file_name_char(c) { c } else { '?' }
+            })
+            .collect::<String>();
+        if s.is_empty() {
+            Self(String::from("?"))
+        } else {
+            Self(s)
+        }
+    }
 }
 
 impl FromStr for FileName {
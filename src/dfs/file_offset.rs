@@ -1,6 +1,7 @@
-use crate::dfs::{CatalogueBytes, FileCount, SECTOR_BYTES};
+use crate::dfs::{CatalogueBytes, CatalogueError, FileCount, SECTOR_BYTES};
 use anyhow::{Result, bail};
 use std::convert::From;
+use std::result::Result as StdResult;
 
 #[derive(Debug)]
 pub struct FileOffset(u8);
@@ -20,9 +21,14 @@ impl FileOffset {
         Ok(Self(value))
     }
 
-    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> Result<Self> {
+    pub fn from_catalogue_bytes(bytes: &CatalogueBytes) -> StdResult<Self, CatalogueError> {
         let value = bytes[usize::from(SECTOR_BYTES) + 5];
-        Self::new(value)
+        Self::new(value).map_err(|_| CatalogueError::InvalidFileOffset { value })
+    }
+
+    // Rounds down to a whole number of entries
+    pub fn from_catalogue_bytes_lossy(bytes: &CatalogueBytes) -> Self {
+        Self(bytes[usize::from(SECTOR_BYTES) + 5] & !0b0000_0111)
     }
 
     pub fn write_to_catalogue(&self, bytes: &mut [u8]) {
@@ -17,4 +17,15 @@ pub trait FileSpec {
         }
         Ordering::Equal
     }
+
+    // DFS file names are case-insensitive
+    fn matches<T: FileSpec>(&self, other: &T) -> bool {
+        self.directory()
+            .to_char()
+            .eq_ignore_ascii_case(&other.directory().to_char())
+            && self
+                .file_name()
+                .as_str()
+                .eq_ignore_ascii_case(other.file_name().as_str())
+    }
 }
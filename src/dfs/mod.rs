@@ -3,12 +3,17 @@ mod boot_option;
 mod catalogue;
 mod catalogue_bytes;
 mod catalogue_entry;
+mod catalogue_error;
+mod catalogue_flavour;
+mod check;
 mod constants;
 mod cycle_number;
+mod ddos_volume;
 mod dfs_path;
 mod directory;
 mod disc_size;
 mod disc_title;
+mod extent;
 mod file_count;
 mod file_descriptor;
 mod file_name;
@@ -25,12 +30,17 @@ pub use boot_option::*;
 pub use catalogue::*;
 pub use catalogue_bytes::*;
 pub use catalogue_entry::*;
+pub use catalogue_error::*;
+pub use catalogue_flavour::*;
+pub use check::*;
 pub use constants::*;
 pub use cycle_number::*;
+pub use ddos_volume::*;
 pub use dfs_path::*;
 pub use directory::*;
 pub use disc_size::*;
 pub use disc_title::*;
+pub use extent::*;
 pub use file_count::*;
 pub use file_descriptor::*;
 pub use file_name::*;
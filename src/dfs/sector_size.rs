@@ -1,4 +1,6 @@
 use crate::u10;
+use serde::de::Error as SerdeError;
+use serde::{Deserialize, Deserializer};
 
 u10!(SectorSize);
 
@@ -6,4 +8,15 @@ impl SectorSize {
     pub const ZERO: Self = Self(0);
     pub const ONE: Self = Self(1);
     pub const TWO: Self = Self(2);
+    pub const FOUR: Self = Self(4);
+}
+
+impl<'de> Deserialize<'de> for SectorSize {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = u16::deserialize(deserializer)?;
+        value.try_into().map_err(SerdeError::custom)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::dfs::{SECTORS_PER_TRACK, SIDES_PER_DISC, SectorBytes, SectorSize, Side};
+use crate::dfs::{SIDES_PER_DISC, SectorBytes, SectorSize, Side};
 use crate::image_reader::ImageReader;
 use anyhow::Result;
 use std::io::{Read, Seek, SeekFrom};
@@ -6,15 +6,17 @@ use std::io::{Read, Seek, SeekFrom};
 pub struct DsdReader<R: Read + Seek> {
     stream_len: u64,
     sector_bytes: SectorBytes,
+    sectors_per_track: usize,
     reader: R,
 }
 
 impl<R: Read + Seek> DsdReader<R> {
-    pub fn new(mut reader: R, sector_bytes: SectorBytes) -> Result<Self> {
+    pub fn new(mut reader: R, sector_bytes: SectorBytes, sectors_per_track: usize) -> Result<Self> {
         let stream_len = reader.seek(SeekFrom::End(0))?;
         Ok(Self {
             stream_len,
             sector_bytes,
+            sectors_per_track,
             reader,
         })
     }
@@ -23,9 +25,12 @@ impl<R: Read + Seek> DsdReader<R> {
         let sector_bytes = usize::from(self.sector_bytes);
         assert!(buffer.len() <= sector_bytes);
 
-        let (track, r) = (sector / SECTORS_PER_TRACK, sector % SECTORS_PER_TRACK);
+        let (track, r) = (
+            sector / self.sectors_per_track,
+            sector % self.sectors_per_track,
+        );
         let track_offset =
-            sector_bytes * SECTORS_PER_TRACK * (track * SIDES_PER_DISC + usize::from(side));
+            sector_bytes * self.sectors_per_track * (track * SIDES_PER_DISC + usize::from(side));
         let sector_offset = track_offset + r * sector_bytes;
 
         self.reader
@@ -46,6 +51,21 @@ impl<R: Read + Seek> ImageReader for DsdReader<R> {
         self.stream_len
     }
 
+    fn side_len(&self, side: Side) -> u64 {
+        let track_bytes = u64::from(self.sector_bytes) * self.sectors_per_track as u64;
+        let mut len = 0;
+        let mut track = 0;
+        loop {
+            let offset = track_bytes * (track * SIDES_PER_DISC as u64 + u64::from(u8::from(side)));
+            if offset >= self.stream_len {
+                break;
+            }
+            len += track_bytes.min(self.stream_len - offset);
+            track += 1;
+        }
+        len
+    }
+
     fn sector_bytes(&self) -> SectorBytes {
         self.sector_bytes
     }
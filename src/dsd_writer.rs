@@ -0,0 +1,105 @@
+use crate::dfs::{SIDES_PER_DISC, SectorBytes, SectorSize, Side};
+use crate::image_writer::ImageWriter;
+use anyhow::{Result, bail};
+use std::io::{Seek, SeekFrom, Write};
+
+pub struct DsdWriter<W: Write + Seek> {
+    sector_bytes: SectorBytes,
+    sectors_per_track: usize,
+    writer: W,
+}
+
+impl<W: Write + Seek> DsdWriter<W> {
+    pub const fn new(writer: W, sector_bytes: SectorBytes, sectors_per_track: usize) -> Self {
+        Self {
+            sector_bytes,
+            sectors_per_track,
+            writer,
+        }
+    }
+
+    fn write_single_sector(&mut self, side: Side, sector: usize, buffer: &[u8]) -> Result<()> {
+        let sector_bytes = usize::from(self.sector_bytes);
+        if buffer.len() > sector_bytes {
+            bail!(
+                "buffer of {len} bytes does not fit in a sector",
+                len = buffer.len()
+            )
+        }
+
+        let (track, r) = (
+            sector / self.sectors_per_track,
+            sector % self.sectors_per_track,
+        );
+        let track_offset =
+            sector_bytes * self.sectors_per_track * (track * SIDES_PER_DISC + usize::from(side));
+        let sector_offset = track_offset + r * sector_bytes;
+
+        self.writer
+            .seek(SeekFrom::Start(u64::try_from(sector_offset)?))?;
+
+        self.writer.write_all(buffer)?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> ImageWriter for DsdWriter<W> {
+    fn write_bytes(&mut self, side: Side, start_sector: SectorSize, buffer: &[u8]) -> Result<()> {
+        let start_sector = usize::from(u16::from(start_sector));
+        let sector_bytes = usize::from(self.sector_bytes);
+        let (q, r) = (buffer.len() / sector_bytes, buffer.len() % sector_bytes);
+
+        let mut ptr = 0;
+        for sector in start_sector..start_sector + q {
+            self.write_single_sector(side, sector, &buffer[ptr..ptr + sector_bytes])?;
+            ptr += sector_bytes;
+        }
+
+        if r > 0 {
+            self.write_single_sector(side, start_sector + q, &buffer[ptr..ptr + r])?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dfs::{SECTOR_BYTES, SECTORS_PER_TRACK, SectorSize, Side};
+    use crate::dsd_reader::DsdReader;
+    use crate::dsd_writer::DsdWriter;
+    use crate::image_reader::ImageReader;
+    use crate::image_writer::ImageWriter;
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let sector_bytes = usize::from(SECTOR_BYTES);
+        let make_side = |sector_count: usize, tag: u8| {
+            (0..sector_count)
+                .flat_map(|sector| vec![tag ^ u8::try_from(sector).unwrap(); sector_bytes])
+                .collect::<Vec<_>>()
+        };
+        let side_0 = make_side(40, 0x00);
+        let side_1 = make_side(25, 0x80);
+
+        let mut bytes = Vec::new();
+        let mut writer = DsdWriter::new(Cursor::new(&mut bytes), SECTOR_BYTES, SECTORS_PER_TRACK);
+        writer.write_bytes(Side::Side0, SectorSize::ZERO, &side_0)?;
+        writer.write_bytes(Side::Side1, SectorSize::ZERO, &side_1)?;
+        assert_eq!(7 * 10 * sector_bytes, bytes.len());
+
+        let mut reader = DsdReader::new(Cursor::new(bytes), SECTOR_BYTES, SECTORS_PER_TRACK)?;
+        let mut buffer = vec![0; side_0.len()];
+        reader.read_bytes(Side::Side0, SectorSize::ZERO, &mut buffer)?;
+        assert_eq!(side_0, buffer);
+
+        let mut buffer = vec![0; side_1.len()];
+        reader.read_bytes(Side::Side1, SectorSize::ZERO, &mut buffer)?;
+        assert_eq!(side_1, buffer);
+
+        Ok(())
+    }
+}
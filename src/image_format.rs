@@ -0,0 +1,293 @@
+use crate::adfs::{ADFS_SECTORS_PER_TRACK, AdfsImage};
+use crate::dfs::{
+    Catalogue, DD_SECTORS_PER_TRACK, DDOS_VOLUME_TABLE_SECTOR, DdosVolume, SECTOR_BYTES,
+    SECTORS_PER_TRACK, Side, get_file_sector_count,
+};
+use anyhow::{Error, Result, bail};
+use std::ffi::OsStr;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Ssd,
+    Dsd,
+    // Opus DDOS double-density
+    Dds,
+    Ddd,
+    // ADFS old map
+    Adfs,
+}
+
+impl ImageFormat {
+    // Extensions commonly used for disc images in archives
+    pub const EXTENSIONS: [&str; 8] = ["ssd", "dsd", "dds", "ddd", "adf", "adl", "img", "bbc"];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(OsStr::to_str)
+            .and_then(|ext| ext.parse().ok())
+    }
+
+    pub const fn sides(self) -> u8 {
+        match self {
+            Self::Ssd | Self::Dds | Self::Adfs => 1,
+            Self::Dsd | Self::Ddd => 2,
+        }
+    }
+
+    pub const fn sectors_per_track(self) -> usize {
+        match self {
+            Self::Ssd | Self::Dsd => SECTORS_PER_TRACK,
+            Self::Dds | Self::Ddd => DD_SECTORS_PER_TRACK,
+            Self::Adfs => ADFS_SECTORS_PER_TRACK,
+        }
+    }
+
+    pub fn has_image_extension(path: &Path) -> bool {
+        path.extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|ext| Self::EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    }
+
+    // Use the format override if given, otherwise look for catalogues at
+    // the locations used by each format, falling back to the file extension
+    // when the content is ambiguous
+    pub fn detect<R: Read + Seek>(
+        reader: &mut R,
+        path: &Path,
+        format: Option<Self>,
+    ) -> Result<Self> {
+        if let Some(format) = format {
+            return Ok(format);
+        }
+
+        if AdfsImage::probe(reader) {
+            return Ok(Self::Adfs);
+        }
+
+        if let Some(format) = Self::probe_ddos(reader)? {
+            return Ok(format);
+        }
+
+        let from_path = Self::from_path(path);
+        Ok(match (Self::probe(reader)?, from_path) {
+            (Probe::Both, Some(Self::Ssd)) if !Self::is_oversized(reader)? => Self::Ssd,
+            (Probe::Both, _) | (Probe::Side0, Some(Self::Dsd)) => Self::Dsd,
+            (Probe::Side0, _) => Self::Ssd,
+            (Probe::None, Some(format)) => format,
+            (Probe::None, None) => bail!(
+                "could not detect format of disc image {path}: pass --format to specify it",
+                path = path.display()
+            ),
+        })
+    }
+
+    // Determine format from content alone
+    pub fn probe_only<R: Read + Seek>(reader: &mut R) -> Result<Option<Self>> {
+        if AdfsImage::probe(reader) {
+            return Ok(Some(Self::Adfs));
+        }
+
+        if let Some(format) = Self::probe_ddos(reader)? {
+            return Ok(Some(format));
+        }
+
+        Ok(match Self::probe(reader)? {
+            Probe::Both => Some(Self::Dsd),
+            Probe::Side0 => Some(Self::Ssd),
+            Probe::None => None,
+        })
+    }
+
+    fn probe<R: Read + Seek>(reader: &mut R) -> Result<Probe> {
+        if read_disc_size(reader, 0)?.is_none() {
+            return Ok(Probe::None);
+        }
+
+        // Side 1 of a .dsd file starts at the second track
+        let side_1_offset = u64::from(SECTOR_BYTES) * SECTORS_PER_TRACK as u64;
+        Ok(if read_disc_size(reader, side_1_offset)?.is_some() {
+            Probe::Both
+        } else {
+            Probe::Side0
+        })
+    }
+
+    // DDOS images hold a volume table in sector 16 of track 0 of each side
+    // and the catalogue of their first volume must be plausible
+    fn probe_ddos<R: Read + Seek>(reader: &mut R) -> Result<Option<Self>> {
+        let track_bytes = u64::from(SECTOR_BYTES) * DD_SECTORS_PER_TRACK as u64;
+        if !is_ddos_side(reader, 0)? {
+            return Ok(None);
+        }
+
+        Ok(Some(if is_ddos_side(reader, track_bytes)? {
+            Self::Ddd
+        } else {
+            Self::Dds
+        }))
+    }
+
+    // A single-sided image cannot be larger than the disc it holds
+    fn is_oversized<R: Read + Seek>(reader: &mut R) -> Result<bool> {
+        let stream_len = reader.seek(SeekFrom::End(0))?;
+        Ok(read_disc_size(reader, 0)?
+            .is_some_and(|disc_size| stream_len > u64::from(disc_size) * u64::from(SECTOR_BYTES)))
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "ssd" => Self::Ssd,
+            "dsd" => Self::Dsd,
+            "dds" => Self::Dds,
+            "ddd" => Self::Ddd,
+            "adf" | "adl" | "adfs" => Self::Adfs,
+            _ => bail!("invalid image format {s}"),
+        })
+    }
+}
+
+impl Display for ImageFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let s = match self {
+            Self::Ssd => "ssd",
+            Self::Dsd => "dsd",
+            Self::Dds => "dds",
+            Self::Ddd => "ddd",
+            Self::Adfs => "adfs",
+        };
+        Display::fmt(s, f)
+    }
+}
+
+enum Probe {
+    None,
+    Side0,
+    Both,
+}
+
+fn is_ddos_side<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<bool> {
+    let sector_bytes = usize::from(SECTOR_BYTES);
+    let mut bytes = vec![0; sector_bytes * (DDOS_VOLUME_TABLE_SECTOR + 1)];
+    reader.seek(SeekFrom::Start(offset))?;
+    if reader.read_exact(&mut bytes).is_err() {
+        return Ok(false);
+    }
+
+    let Ok(volumes) = DdosVolume::from_volume_table(
+        &bytes[DDOS_VOLUME_TABLE_SECTOR * sector_bytes..],
+        Side::Side0,
+    ) else {
+        return Ok(false);
+    };
+
+    let catalogue_offset = volumes[0].catalogue_sector() * sector_bytes;
+    Ok(Catalogue::is_valid_catalogue(
+        &bytes[catalogue_offset..catalogue_offset + sector_bytes * 2],
+    ))
+}
+
+// Returns disc size if the bytes at offset hold a plausible catalogue
+fn read_disc_size<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Option<u16>> {
+    let mut bytes = vec![0; usize::from(SECTOR_BYTES) * 2];
+    reader.seek(SeekFrom::Start(offset))?;
+    if reader.read_exact(&mut bytes).is_err() || !Catalogue::is_valid_catalogue(&bytes) {
+        return Ok(None);
+    }
+
+    let Ok(catalogue) = Catalogue::from_catalogue_bytes(&bytes) else {
+        return Ok(None);
+    };
+
+    let disc_size = u16::from(catalogue.disc_size);
+    for entry in &catalogue.entries {
+        let Ok(sector_count) = get_file_sector_count(entry.length) else {
+            return Ok(None);
+        };
+        if u16::from(entry.start_sector) + u16::from(sector_count) > disc_size {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(disc_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dfs::{
+        BootOption, Catalogue, CatalogueFlavour, CycleNumber, FileOffset, SECTOR_BYTES,
+        SECTORS_PER_TRACK,
+    };
+    use crate::image_format::ImageFormat;
+    use anyhow::Result;
+    use rstest::rstest;
+    use std::io::Cursor;
+    use std::path::Path;
+
+    fn write_catalogue(bytes: &mut [u8], offset: usize) -> Result<()> {
+        Catalogue::new(
+            "DISC".parse()?,
+            CycleNumber::default(),
+            FileOffset::new(0)?,
+            BootOption::None,
+            400u16.try_into()?,
+            CatalogueFlavour::Acorn,
+            Vec::new(),
+        )
+        .write_to_catalogue(&mut bytes[offset..])
+    }
+
+    fn make_image(sides: usize, len: usize) -> Result<Vec<u8>> {
+        let mut bytes = vec![0; len];
+        write_catalogue(&mut bytes, 0)?;
+        if sides > 1 {
+            write_catalogue(&mut bytes, usize::from(SECTOR_BYTES) * SECTORS_PER_TRACK)?;
+        }
+        Ok(bytes)
+    }
+
+    #[rstest]
+    #[case(ImageFormat::Ssd, 1, "a.img", None)]
+    #[case(ImageFormat::Ssd, 1, "a.SSD", None)]
+    #[case(ImageFormat::Dsd, 2, "a.bbc", None)]
+    #[case(ImageFormat::Dsd, 2, "a.ssd", None)]
+    #[case(ImageFormat::Dsd, 1, "a.dsd", None)]
+    #[case(ImageFormat::Ssd, 2, "a.dsd", Some(ImageFormat::Ssd))]
+    fn detect(
+        #[case] expected: ImageFormat,
+        #[case] sides: usize,
+        #[case] path: &str,
+        #[case] format: Option<ImageFormat>,
+    ) -> Result<()> {
+        let mut reader = Cursor::new(make_image(sides, 800 * usize::from(SECTOR_BYTES))?);
+        assert_eq!(
+            expected,
+            ImageFormat::detect(&mut reader, Path::new(path), format)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn detect_single_sided_with_catalogue_in_sector_10() -> Result<()> {
+        let mut reader = Cursor::new(make_image(2, 400 * usize::from(SECTOR_BYTES))?);
+        assert_eq!(
+            ImageFormat::Ssd,
+            ImageFormat::detect(&mut reader, Path::new("a.ssd"), None)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn detect_unknown() {
+        let mut reader = Cursor::new(vec![0xff; 1000]);
+        assert!(ImageFormat::detect(&mut reader, Path::new("a.img"), None).is_err());
+    }
+}
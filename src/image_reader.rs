@@ -5,6 +5,8 @@ use anyhow::Result;
 pub trait ImageReader {
     fn sides(&self) -> u8;
     fn stream_len(&self) -> u64;
+    // Number of bytes belonging to the given side present in the image
+    fn side_len(&self, side: Side) -> u64;
     fn sector_bytes(&self) -> SectorBytes;
     fn read_bytes(&mut self, side: Side, start_sector: SectorSize, buffer: &mut [u8])
     -> Result<()>;
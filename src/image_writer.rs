@@ -0,0 +1,6 @@
+use crate::dfs::{SectorSize, Side};
+use anyhow::Result;
+
+pub trait ImageWriter {
+    fn write_bytes(&mut self, side: Side, start_sector: SectorSize, buffer: &[u8]) -> Result<()>;
+}
@@ -1,18 +1,26 @@
+mod adfs;
 mod args;
 mod bbc_basic;
 mod commands;
 mod constants;
+mod ddos_reader;
 mod dfs;
 mod dsd_reader;
+mod dsd_writer;
+mod image_format;
 mod image_reader;
+mod image_writer;
 mod line_ending;
 mod metadata;
+mod mmb;
 mod ops;
 mod path_util;
 mod run;
 mod ssd_reader;
+mod ssd_writer;
 mod u10;
 mod u18;
+mod uef;
 mod util;
 
 fn main() -> anyhow::Result<()> {
@@ -1,22 +1,33 @@
-use crate::dfs::{CatalogueEntry, DfsPath, FileDescriptor};
+use crate::adfs::AdfsFile;
+use crate::dfs::{DfsPath, FileDescriptor, Length};
 use crate::util::open_for_write;
 use anyhow::{Result, bail};
 use std::fmt::Display;
-use std::fs::{File, read_to_string};
+use std::fs::read_to_string;
 use std::io::Write;
 use std::iter::Peekable;
 use std::path::Path;
 
 const LOCKED: u8 = 0x08;
 const NOT_LOCKED: u8 = 0x00;
+const READABLE: u8 = 0x01;
+const WRITABLE: u8 = 0x02;
 
 // Acorn File Server date word - always zero for now
 const DEFAULT_DATESTAMP: u16 = 0;
 
 // Reference: https://www.geraldholdsworth.co.uk/documents/DiscImage.pdf
-pub fn make_inf_file(inf_path: &Path, entry: &CatalogueEntry, overwrite: bool) -> Result<()> {
-    let mut writer = InfWriter::new(inf_path, overwrite)?;
-    let d = &entry.descriptor;
+pub fn make_inf_file(
+    inf_path: &Path,
+    d: &FileDescriptor,
+    length: Length,
+    overwrite: bool,
+) -> Result<()> {
+    write_inf_line(open_for_write(inf_path, overwrite)?, d, length)
+}
+
+pub fn write_inf_line<W: Write>(writer: W, d: &FileDescriptor, length: Length) -> Result<()> {
+    let mut writer = InfWriter::new(writer);
 
     writer.write_field(&format!("{dir}.{f}", dir = d.directory, f = d.file_name))?;
 
@@ -30,7 +41,7 @@ pub fn make_inf_file(inf_path: &Path, entry: &CatalogueEntry, overwrite: bool) -
         execution_address = d.execution_address
     ))?;
 
-    writer.write_field(&format!("{length:06X}", length = entry.length))?;
+    writer.write_field(&format!("{length:06X}"))?;
 
     writer.write_field(&format!(
         "{access:02X}",
@@ -44,18 +55,53 @@ pub fn make_inf_file(inf_path: &Path, entry: &CatalogueEntry, overwrite: bool) -
     Ok(())
 }
 
-struct InfWriter {
-    file: File,
+// ADFS addresses are 32 bits wide and files carry read and write attributes
+// as well as the locked attribute
+pub fn make_adfs_inf_file(inf_path: &Path, file: &AdfsFile, overwrite: bool) -> Result<()> {
+    let mut writer = InfWriter::new(open_for_write(inf_path, overwrite)?);
+    let entry = &file.entry;
+
+    writer.write_field(&file.adfs_path())?;
+
+    writer.write_field(&format!(
+        "{load_address:08X}",
+        load_address = entry.load_address
+    ))?;
+
+    writer.write_field(&format!(
+        "{execution_address:08X}",
+        execution_address = entry.execution_address
+    ))?;
+
+    writer.write_field(&format!("{length:06X}", length = entry.length))?;
+
+    let mut access = if entry.locked { LOCKED } else { NOT_LOCKED };
+    if entry.readable {
+        access |= READABLE;
+    }
+    if entry.writable {
+        access |= WRITABLE;
+    }
+    writer.write_field(&format!("{access:02X}"))?;
+
+    writer.write_field(&format!("{DEFAULT_DATESTAMP:04X}"))?;
+
+    writer.write_line_end()?;
+
+    Ok(())
+}
+
+struct InfWriter<W: Write> {
+    writer: W,
     field_count: usize,
 }
 
-impl InfWriter {
-    fn new(path: &Path, overwrite: bool) -> Result<Self> {
-        let file = open_for_write(path, overwrite)?;
-        Ok(Self {
-            file,
+impl<W: Write> InfWriter<W> {
+    const fn new(writer: W) -> Self {
+        Self {
+            writer,
             field_count: 0,
-        })
+        }
     }
 
     fn write_field<T: Display>(&mut self, value: &T) -> Result<()> {
@@ -70,13 +116,13 @@ impl InfWriter {
         }
 
         if self.field_count > 0 {
-            write!(self.file, " ")?;
+            write!(self.writer, " ")?;
         }
 
         if s.contains(' ') {
-            write!(self.file, "\"{s}\"")?;
+            write!(self.writer, "\"{s}\"")?;
         } else {
-            write!(self.file, "{s}")?;
+            write!(self.writer, "{s}")?;
         }
 
         self.field_count += 1;
@@ -85,7 +131,7 @@ impl InfWriter {
     }
 
     fn write_line_end(&mut self) -> Result<()> {
-        writeln!(self.file)?;
+        writeln!(self.writer)?;
         self.field_count = 0;
         Ok(())
     }
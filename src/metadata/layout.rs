@@ -0,0 +1,43 @@
+use crate::dfs::{Directory, FileName, FileSpec, SectorSize};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Physical layout of a disc side recorded by "extract --preserve" so that
+// "make" can rebuild a byte-identical image
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Layout {
+    #[serde(rename = "imageLength", alias = "image_length")]
+    pub image_length: u64,
+
+    // Slack file holding the content of sectors and parts of sectors not
+    // referenced by any file as well as any catalogue bytes that cannot be
+    // reconstructed from the manifest
+    #[serde(rename = "slackPath", alias = "slack_path")]
+    pub slack_path: PathBuf,
+
+    // Files in original catalogue order
+    #[serde(rename = "entries")]
+    pub entries: Vec<LayoutEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LayoutEntry {
+    #[serde(rename = "fileName", alias = "file_name")]
+    pub file_name: FileName,
+
+    #[serde(rename = "directory")]
+    pub directory: Directory,
+
+    #[serde(rename = "startSector", alias = "start_sector")]
+    pub start_sector: SectorSize,
+}
+
+impl FileSpec for LayoutEntry {
+    fn directory(&self) -> &Directory {
+        &self.directory
+    }
+
+    fn file_name(&self) -> &FileName {
+        &self.file_name
+    }
+}
@@ -1,5 +1,5 @@
-use crate::dfs::{BootOption, CycleNumber, DiscSize, DiscTitle};
-use crate::metadata::File;
+use crate::dfs::{BootOption, CatalogueFlavour, CycleNumber, DiscSize, DiscTitle};
+use crate::metadata::{File, Layout};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -24,6 +24,14 @@ pub struct Manifest {
     #[serde(rename = "cycleNumber", alias = "cycle_number", default)]
     pub cycle_number: CycleNumber,
 
+    #[serde(
+        rename = "catalogueFlavour",
+        alias = "catalogue_flavour",
+        skip_serializing_if = "CatalogueFlavour::is_acorn",
+        default
+    )]
+    pub catalogue_flavour: CatalogueFlavour,
+
     #[serde(
         rename = "infFiles",
         alias = "inf_files",
@@ -34,4 +42,7 @@ pub struct Manifest {
 
     #[serde(rename = "files", skip_serializing_if = "Vec::is_empty", default)]
     pub files: Vec<File>,
+
+    #[serde(rename = "layout", skip_serializing_if = "Option::is_none", default)]
+    pub layout: Option<Layout>,
 }
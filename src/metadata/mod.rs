@@ -1,9 +1,13 @@
 mod file;
 mod file_type;
 mod inf;
+mod layout;
 mod manifest;
+mod slack;
 
 pub use file::*;
 pub use file_type::*;
 pub use inf::*;
+pub use layout::*;
 pub use manifest::*;
+pub use slack::*;
@@ -0,0 +1,177 @@
+use anyhow::{Result, bail};
+use std::ops::Range;
+
+const SLACK_MAGIC: [u8; 8] = *b"DFSSLACK";
+
+const DATA_RUN: u8 = 0;
+
+const CATALOGUE_RUN: u8 = 1;
+
+// Bytes of an original disc side that cannot be regenerated from the
+// manifest, stored in the .slack file written by "extract --preserve"
+#[derive(Debug, Default, PartialEq)]
+pub struct Slack {
+    pub runs: Vec<SlackRun>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SlackRun {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+
+    // Catalogue bytes as regenerated at the time of extraction: the original
+    // bytes are only restored where the catalogue is regenerated the same way
+    // again, so that edited metadata is kept. Runs outside the catalogue hold
+    // the content of sectors and parts of sectors not used by any file and
+    // have no expected bytes.
+    pub expected: Option<Vec<u8>>,
+}
+
+impl Slack {
+    // Runs of bytes that differ between the original side and the side
+    // regenerated from the manifest, leaving out bytes belonging to files
+    pub fn diff(
+        original: &[u8],
+        regenerated: &[u8],
+        catalogue_len: usize,
+        file_extents: &[Range<usize>],
+    ) -> Self {
+        let mut runs = Vec::new();
+        let mut index = 0;
+        while index < original.len() {
+            let regenerated_byte = |i: usize| regenerated.get(i).copied().unwrap_or_default();
+            let is_slack = |i: usize| {
+                original[i] != regenerated_byte(i)
+                    && (i < catalogue_len || !file_extents.iter().any(|e| e.contains(&i)))
+            };
+            if !is_slack(index) {
+                index += 1;
+                continue;
+            }
+
+            let start = index;
+            let in_catalogue = start < catalogue_len;
+            while index < original.len()
+                && (index < catalogue_len) == in_catalogue
+                && is_slack(index)
+            {
+                index += 1;
+            }
+            runs.push(SlackRun {
+                offset: start,
+                bytes: original[start..index].to_vec(),
+                expected: in_catalogue.then(|| (start..index).map(regenerated_byte).collect()),
+            });
+        }
+        Self { runs }
+    }
+
+    // Restore slack bytes by overwriting, but never where a file now lives
+    // or where the catalogue no longer matches what was extracted
+    pub fn apply(&self, bytes: &mut Vec<u8>, catalogue_len: usize, file_extents: &[Range<usize>]) {
+        for run in &self.runs {
+            let end = run.offset + run.bytes.len();
+            if bytes.len() < end {
+                bytes.resize(end, 0);
+            }
+            match &run.expected {
+                Some(expected) => {
+                    if bytes[run.offset..end] == expected[..] {
+                        bytes[run.offset..end].copy_from_slice(&run.bytes);
+                    }
+                }
+                None => {
+                    for (i, b) in (run.offset..end).zip(&run.bytes) {
+                        if i >= catalogue_len && !file_extents.iter().any(|e| e.contains(&i)) {
+                            bytes[i] = *b;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let Some(mut rest) = bytes.strip_prefix(&SLACK_MAGIC) else {
+            bail!("slack file is not in a recognized format")
+        };
+
+        let mut runs = Vec::new();
+        while let Some((&kind, tail)) = rest.split_first() {
+            rest = tail;
+            let offset = usize::try_from(u32::from_le_bytes(take(&mut rest, 4)?.try_into()?))?;
+            let len = usize::try_from(u32::from_le_bytes(take(&mut rest, 4)?.try_into()?))?;
+            let bytes = take(&mut rest, len)?.to_vec();
+            let expected = match kind {
+                DATA_RUN => None,
+                CATALOGUE_RUN => Some(take(&mut rest, len)?.to_vec()),
+                _ => bail!("invalid slack run type {kind}"),
+            };
+            runs.push(SlackRun {
+                offset,
+                bytes,
+                expected,
+            });
+        }
+        Ok(Self { runs })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut output = SLACK_MAGIC.to_vec();
+        for run in &self.runs {
+            output.push(if run.expected.is_some() {
+                CATALOGUE_RUN
+            } else {
+                DATA_RUN
+            });
+            output.extend(u32::try_from(run.offset)?.to_le_bytes());
+            output.extend(u32::try_from(run.bytes.len())?.to_le_bytes());
+            output.extend(&run.bytes);
+            if let Some(expected) = &run.expected {
+                output.extend(expected);
+            }
+        }
+        Ok(output)
+    }
+}
+
+fn take<'a>(rest: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    let Some((head, tail)) = rest.split_at_checked(len) else {
+        bail!("slack file truncated")
+    };
+    *rest = tail;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::Slack;
+    use anyhow::Result;
+
+    #[test]
+    fn diff_and_apply() -> Result<()> {
+        let original = b"TITLE\0\0\0AAAA\x01\x02BBB\x03".to_vec();
+        let regenerated = b"TITLE   AAAA\0\0BBB\0".to_vec();
+        let file_extents = [8..12, 14..17];
+
+        let slack = Slack::diff(&original, &regenerated, 8, &file_extents);
+        let slack = Slack::from_bytes(&slack.to_bytes()?)?;
+        assert_eq!(3, slack.runs.len());
+
+        let mut bytes = regenerated.clone();
+        slack.apply(&mut bytes, 8, &file_extents);
+        assert_eq!(original, bytes);
+
+        // Edited title is kept and the first file now runs into slack space
+        let mut bytes = b"EDITED  AAAAAABBB\0".to_vec();
+        slack.apply(&mut bytes, 8, &[8..14, 14..17]);
+        assert_eq!(b"EDITED  AAAAAABBB\x03".to_vec(), bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(Slack::from_bytes(b"DFSSLACK\0\0\0\0\0\x02\0\0\0\x01").is_err());
+        assert!(Slack::from_bytes(b"DFSSLACX").is_err());
+    }
+}
@@ -0,0 +1,17 @@
+// Number of disc slots in a standard MMFS BEEB.MMB file
+pub const MMB_SLOT_COUNT: u16 = 511;
+
+// Disc table: 16-byte header followed by one 16-byte entry per slot
+pub const MMB_TABLE_BYTES: u64 = 0x2000;
+
+pub const MMB_ENTRY_BYTES: usize = 16;
+
+pub const MMB_TITLE_LEN: usize = 12;
+
+// Each slot holds a single-sided 80-track disc of 800 sectors
+pub const MMB_SLOT_BYTES: u64 = 0x32000;
+
+pub const MMB_DRIVE_COUNT: usize = 4;
+
+// MMFS expects the file to span every slot
+pub const MMB_FILE_BYTES: u64 = MMB_TABLE_BYTES + MMB_SLOT_COUNT as u64 * MMB_SLOT_BYTES;
@@ -0,0 +1,47 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+// Status byte at offset 15 of each disc table entry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiscStatus {
+    Locked,
+    Unlocked,
+    Unformatted,
+    Invalid,
+}
+
+impl DiscStatus {
+    // MMFS treats any unrecognized value as an invalid slot
+    pub const fn from_byte(value: u8) -> Self {
+        match value {
+            0x00 => Self::Locked,
+            0x0f => Self::Unlocked,
+            0xf0 => Self::Unformatted,
+            _ => Self::Invalid,
+        }
+    }
+
+    pub const fn to_byte(self) -> u8 {
+        match self {
+            Self::Locked => 0x00,
+            Self::Unlocked => 0x0f,
+            Self::Unformatted => 0xf0,
+            Self::Invalid => 0xff,
+        }
+    }
+
+    pub const fn is_formatted(self) -> bool {
+        matches!(self, Self::Locked | Self::Unlocked)
+    }
+}
+
+impl Display for DiscStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let s = match self {
+            Self::Locked => "locked",
+            Self::Unlocked => "unlocked",
+            Self::Unformatted => "unformatted",
+            Self::Invalid => "invalid",
+        };
+        Display::fmt(s, f)
+    }
+}
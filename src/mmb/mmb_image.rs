@@ -0,0 +1,187 @@
+use crate::mmb::{
+    DiscStatus, MMB_DRIVE_COUNT, MMB_ENTRY_BYTES, MMB_SLOT_BYTES, MMB_SLOT_COUNT, MMB_TABLE_BYTES,
+    MMB_TITLE_LEN,
+};
+use anyhow::{Result, bail};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+// MMFS stores up to 511 .ssd images back to back after an 8 KB disc table:
+// &00-&03 : low bytes of the slots loaded into drives 0-3 at boot
+// &04-&07 : high bytes of the same
+// &10-    : one entry per slot holding a 12-character title padded with
+//           zeros and a status byte at offset 15
+pub struct MmbImage<F: Read + Seek> {
+    pub drives: [u16; MMB_DRIVE_COUNT],
+    pub entries: Vec<MmbEntry>,
+    inner: F,
+}
+
+#[derive(Debug)]
+pub struct MmbEntry {
+    pub slot: u16,
+    pub title: String,
+    pub status: DiscStatus,
+}
+
+impl<F: Read + Seek> MmbImage<F> {
+    pub fn new(mut inner: F) -> Result<Self> {
+        let mut table = vec![0; usize::try_from(MMB_TABLE_BYTES)?];
+        inner.seek(SeekFrom::Start(0))?;
+        if inner.read_exact(&mut table).is_err() {
+            bail!("MMB disc table truncated")
+        }
+
+        let drives = std::array::from_fn(|i| u16::from_le_bytes([table[i], table[i + 4]]));
+        let entries = (0..MMB_SLOT_COUNT)
+            .map(|slot| {
+                let offset = entry_offset(slot);
+                let title_bytes = &table[offset..offset + MMB_TITLE_LEN];
+                let title_len = title_bytes
+                    .iter()
+                    .position(|&b| b == 0)
+                    .unwrap_or(MMB_TITLE_LEN);
+                MmbEntry {
+                    slot,
+                    title: title_bytes[..title_len]
+                        .iter()
+                        .map(|&b| char::from(b))
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string(),
+                    status: DiscStatus::from_byte(table[offset + MMB_ENTRY_BYTES - 1]),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            drives,
+            entries,
+            inner,
+        })
+    }
+
+    pub fn entry(&self, slot: u16) -> Result<&MmbEntry> {
+        match self.entries.get(usize::from(slot)) {
+            Some(entry) => Ok(entry),
+            None => bail!("invalid MMB slot {slot}"),
+        }
+    }
+
+    pub fn first_free_slot(&self) -> Option<u16> {
+        self.entries
+            .iter()
+            .find(|entry| !entry.status.is_formatted())
+            .map(|entry| entry.slot)
+    }
+
+    pub fn read_slot(&mut self, slot: u16) -> Result<Vec<u8>> {
+        let entry = self.entry(slot)?;
+        if !entry.status.is_formatted() {
+            bail!("MMB slot {slot} does not contain a disc")
+        }
+
+        let mut bytes = vec![0; usize::try_from(MMB_SLOT_BYTES)?];
+        self.inner.seek(SeekFrom::Start(slot_offset(slot)))?;
+        self.inner.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<F: Read + Write + Seek> MmbImage<F> {
+    // Make an MMB file with every slot unformatted and slots 0-3 loaded into
+    // drives 0-3
+    pub fn create(mut inner: F) -> Result<Self> {
+        let mut table = vec![0; usize::try_from(MMB_TABLE_BYTES)?];
+        for (drive, b) in table[..MMB_DRIVE_COUNT].iter_mut().enumerate() {
+            *b = u8::try_from(drive)?;
+        }
+        for slot in 0..MMB_SLOT_COUNT {
+            table[entry_offset(slot) + MMB_ENTRY_BYTES - 1] = DiscStatus::Unformatted.to_byte();
+        }
+        inner.seek(SeekFrom::Start(0))?;
+        inner.write_all(&table)?;
+        Self::new(inner)
+    }
+
+    // Images shorter than a slot are padded with zeros
+    pub fn write_slot(&mut self, slot: u16, title: &str, bytes: &[u8]) -> Result<()> {
+        self.entry(slot)?;
+        if bytes.len() as u64 > MMB_SLOT_BYTES {
+            bail!("disc image is too large for an MMB slot")
+        }
+
+        let mut slot_bytes = bytes.to_vec();
+        slot_bytes.resize(usize::try_from(MMB_SLOT_BYTES)?, 0);
+        self.inner.seek(SeekFrom::Start(slot_offset(slot)))?;
+        self.inner.write_all(&slot_bytes)?;
+
+        let entry = &mut self.entries[usize::from(slot)];
+        entry.title = title.chars().take(MMB_TITLE_LEN).collect();
+        entry.status = DiscStatus::Unlocked;
+        self.write_entry(slot)
+    }
+
+    pub fn set_status(&mut self, slot: u16, status: DiscStatus) -> Result<()> {
+        self.entry(slot)?;
+        let entry = &mut self.entries[usize::from(slot)];
+        entry.status = status;
+        if !status.is_formatted() {
+            entry.title.clear();
+        }
+        self.write_entry(slot)
+    }
+
+    fn write_entry(&mut self, slot: u16) -> Result<()> {
+        let entry = &self.entries[usize::from(slot)];
+        let mut bytes = [0; MMB_ENTRY_BYTES];
+        for (b, c) in bytes.iter_mut().zip(entry.title.chars()) {
+            *b = u8::try_from(c)?;
+        }
+        bytes[MMB_ENTRY_BYTES - 1] = entry.status.to_byte();
+        self.inner
+            .seek(SeekFrom::Start(u64::try_from(entry_offset(slot))?))?;
+        self.inner.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+const fn entry_offset(slot: u16) -> usize {
+    MMB_ENTRY_BYTES * (slot as usize + 1)
+}
+
+fn slot_offset(slot: u16) -> u64 {
+    MMB_TABLE_BYTES + u64::from(slot) * MMB_SLOT_BYTES
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mmb::{DiscStatus, MMB_SLOT_BYTES, MmbImage};
+    use anyhow::Result;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let mut mmb = MmbImage::create(Cursor::new(Vec::new()))?;
+        assert_eq!(Some(0), mmb.first_free_slot());
+        mmb.write_slot(0, "GAMES", &[1, 2, 3])?;
+        mmb.write_slot(2, "UTILITIES", &[4; 100])?;
+        mmb.set_status(2, DiscStatus::Locked)?;
+        mmb.set_status(0, DiscStatus::Unformatted)?;
+
+        let mut mmb = MmbImage::new(mmb.inner)?;
+        assert_eq!([0, 1, 2, 3], mmb.drives);
+        assert_eq!(Some(0), mmb.first_free_slot());
+        assert_eq!(DiscStatus::Unformatted, mmb.entry(0)?.status);
+        assert_eq!("", mmb.entry(0)?.title);
+        assert_eq!(DiscStatus::Locked, mmb.entry(2)?.status);
+        assert_eq!("UTILITIES", mmb.entry(2)?.title);
+        assert!(mmb.read_slot(0).is_err());
+        assert!(mmb.read_slot(511).is_err());
+
+        let bytes = mmb.read_slot(2)?;
+        assert_eq!(MMB_SLOT_BYTES, bytes.len() as u64);
+        assert_eq!(&[4; 100], &bytes[..100]);
+        assert_eq!(0, bytes[100]);
+        Ok(())
+    }
+}
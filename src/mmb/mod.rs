@@ -0,0 +1,7 @@
+mod constants;
+mod disc_status;
+mod mmb_image;
+
+pub use constants::*;
+pub use disc_status::*;
+pub use mmb_image::*;
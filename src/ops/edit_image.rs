@@ -0,0 +1,428 @@
+use crate::dfs::{Catalogue, DfsPath, SECTOR_BYTES, SectorSize, Side};
+use crate::dsd_reader::DsdReader;
+use crate::dsd_writer::DsdWriter;
+use crate::image_format::ImageFormat;
+use crate::image_reader::ImageReader;
+use crate::image_writer::ImageWriter;
+use crate::ssd_reader::SsdReader;
+use crate::ssd_writer::SsdWriter;
+use anyhow::{Result, bail};
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::path::Path;
+
+// Apply an edit to the catalogue of one side of an existing disc image and
+// write the catalogue back in place, bumping the cycle number as DFS does
+pub fn edit_image_file<F>(
+    path: &Path,
+    format: Option<ImageFormat>,
+    side: Side,
+    edit: F,
+) -> Result<()>
+where
+    F: FnOnce(&mut Catalogue, &mut dyn ImageReader, &mut dyn ImageWriter) -> Result<()>,
+{
+    let mut f = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            bail!("file {path} not found", path = path.display())
+        }
+        Err(e) => bail!(e),
+    };
+
+    let format = ImageFormat::detect(&mut f, path, format)?;
+    match format {
+        ImageFormat::Dsd => edit_image(
+            DsdReader::new(&f, SECTOR_BYTES, format.sectors_per_track())?,
+            DsdWriter::new(&f, SECTOR_BYTES, format.sectors_per_track()),
+            side,
+            edit,
+        ),
+        ImageFormat::Ssd => edit_image(
+            SsdReader::new(&f, SECTOR_BYTES)?,
+            SsdWriter::new(&f, SECTOR_BYTES),
+            side,
+            edit,
+        ),
+        ImageFormat::Dds | ImageFormat::Ddd => bail!("editing DDOS disc images is not supported"),
+        ImageFormat::Adfs => bail!("editing ADFS disc images is not supported"),
+    }
+}
+
+// Find a file in the catalogue, failing if it does not exist
+pub fn find_file(catalogue: &Catalogue, path: &DfsPath) -> Result<usize> {
+    let Some(index) = catalogue.find_entry(path) else {
+        bail!("file {path} not found")
+    };
+    Ok(index)
+}
+
+// Find a file in the catalogue, failing if it does not exist or is locked
+pub fn find_unlocked_file(catalogue: &Catalogue, path: &DfsPath) -> Result<usize> {
+    let index = find_file(catalogue, path)?;
+    if catalogue.entries[index].descriptor.locked {
+        bail!("file {path} is locked")
+    }
+    Ok(index)
+}
+
+fn edit_image<R: ImageReader, W: ImageWriter, F>(
+    mut reader: R,
+    mut writer: W,
+    side: Side,
+    edit: F,
+) -> Result<()>
+where
+    F: FnOnce(&mut Catalogue, &mut dyn ImageReader, &mut dyn ImageWriter) -> Result<()>,
+{
+    if u8::from(side) >= reader.sides() {
+        bail!(
+            "disc image does not have side {side}",
+            side = u8::from(side)
+        )
+    }
+
+    let mut bytes = Catalogue::read_catalogue_bytes(&mut reader, side)?;
+    let mut catalogue = Catalogue::from_catalogue_bytes(&bytes)?;
+
+    edit(&mut catalogue, &mut reader, &mut writer)?;
+
+    catalogue.sort_entries();
+    catalogue.update_file_offset()?;
+    catalogue.cycle_number = catalogue.cycle_number.next();
+    catalogue.write_to_catalogue(&mut bytes)?;
+    writer.write_bytes(side, SectorSize::ZERO, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{AddOpts, run_access, run_add, run_delete, run_rename, run_set_addr};
+    use crate::dfs::{
+        Address, BootOption, Catalogue, CatalogueEntry, CatalogueFlavour, CycleNumber,
+        FileDescriptor, FileOffset, SECTOR_BYTES, SECTORS_PER_TRACK, SectorSize, Side,
+    };
+    use crate::dsd_writer::DsdWriter;
+    use crate::image_writer::ImageWriter;
+    use crate::ssd_writer::SsdWriter;
+    use anyhow::Result;
+    use rstest::rstest;
+    use std::fs::{read, write};
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+    use tempfile::{TempDir, tempdir};
+
+    const DISC_SIZE: u16 = 200;
+
+    type Command = fn(&Path, Side) -> Result<()>;
+
+    fn make_entry(
+        name: &str,
+        locked: bool,
+        length: u32,
+        start_sector: u16,
+    ) -> Result<CatalogueEntry> {
+        let (directory, file_name) = name.split_once('.').unwrap();
+        Ok(CatalogueEntry::new(
+            FileDescriptor::new(
+                file_name.parse()?,
+                directory.chars().next().unwrap().try_into()?,
+                locked,
+                Address::try_from(0x1900)?,
+                Address::try_from(0x8023)?,
+            ),
+            length.try_into()?,
+            start_sector.try_into()?,
+        ))
+    }
+
+    // Write a catalogue and content for each of its files, in which every
+    // byte of a file holds its start sector
+    fn write_side(
+        writer: &mut dyn ImageWriter,
+        side: Side,
+        cycle_number: u8,
+        entries: Vec<CatalogueEntry>,
+    ) -> Result<()> {
+        for entry in &entries {
+            let start_sector = u16::from(entry.start_sector);
+            let content =
+                vec![u8::try_from(start_sector)?; usize::try_from(u32::from(entry.length))?];
+            writer.write_bytes(side, entry.start_sector, &content)?;
+        }
+
+        let mut catalogue = Catalogue::new(
+            "DISC".parse()?,
+            CycleNumber::new(cycle_number)?,
+            FileOffset::new(0)?,
+            BootOption::None,
+            DISC_SIZE.try_into()?,
+            CatalogueFlavour::Acorn,
+            entries,
+        );
+        catalogue.sort_entries();
+        catalogue.update_file_offset()?;
+        let mut bytes = vec![0; usize::from(SECTOR_BYTES) * 2];
+        catalogue.write_to_catalogue(&mut bytes)?;
+        writer.write_bytes(side, SectorSize::ZERO, &bytes)
+    }
+
+    fn default_entries() -> Result<Vec<CatalogueEntry>> {
+        Ok(vec![
+            make_entry("$.DATA", false, 0x100, 2)?,
+            make_entry("B.LOCKED", true, 0x1ff, 3)?,
+            make_entry("$.PROG", false, 0x80, 5)?,
+        ])
+    }
+
+    // A .ssd whose only side holds the given files, or a .dsd whose side 1
+    // holds them and whose side 0 holds a file of its own
+    fn make_image(
+        dsd: bool,
+        cycle_number: u8,
+        entries: Vec<CatalogueEntry>,
+    ) -> Result<(TempDir, PathBuf, Side)> {
+        let side_len = usize::from(DISC_SIZE) * usize::from(SECTOR_BYTES);
+        let dir = tempdir()?;
+        let (path, side) = if dsd {
+            let mut bytes = vec![0; side_len * 2];
+            let mut writer =
+                DsdWriter::new(Cursor::new(&mut bytes), SECTOR_BYTES, SECTORS_PER_TRACK);
+            write_side(
+                &mut writer,
+                Side::Side0,
+                42,
+                vec![make_entry("$.OTHER", false, 0x300, 2)?],
+            )?;
+            write_side(&mut writer, Side::Side1, cycle_number, entries)?;
+            let path = dir.path().join("disc.dsd");
+            write(&path, &bytes)?;
+            (path, Side::Side1)
+        } else {
+            let mut bytes = vec![0; side_len];
+            let mut writer = SsdWriter::new(Cursor::new(&mut bytes), SECTOR_BYTES);
+            write_side(&mut writer, Side::Side0, cycle_number, entries)?;
+            let path = dir.path().join("disc.ssd");
+            write(&path, &bytes)?;
+            (path, Side::Side0)
+        };
+        Ok((dir, path, side))
+    }
+
+    fn describe(catalogue: &Catalogue) -> Vec<String> {
+        catalogue
+            .entries
+            .iter()
+            .map(|entry| {
+                let d = &entry.descriptor;
+                format!(
+                    "{directory}.{file_name} {load_address:06X} {execution_address:06X} {length:06X} {start_sector:03X}{locked}",
+                    directory = d.directory,
+                    file_name = d.file_name,
+                    load_address = d.load_address,
+                    execution_address = d.execution_address,
+                    length = entry.length,
+                    start_sector = u16::from(entry.start_sector),
+                    locked = if d.locked { " L" } else { "" }
+                )
+            })
+            .collect()
+    }
+
+    fn read_catalogue(path: &Path, side: Side) -> Result<Catalogue> {
+        let mut catalogues = Catalogue::from_image_file(path, None, false)?;
+        Ok(catalogues.remove(usize::from(u8::from(side))))
+    }
+
+    // Byte offset in the image of a sector of a side
+    fn image_offset(dsd: bool, side: Side, sector: usize) -> usize {
+        let (track, r) = (sector / SECTORS_PER_TRACK, sector % SECTORS_PER_TRACK);
+        let track = if dsd {
+            track * 2 + usize::from(u8::from(side))
+        } else {
+            track
+        };
+        (track * SECTORS_PER_TRACK + r) * usize::from(SECTOR_BYTES)
+    }
+
+    fn add_new(path: &Path, side: Side) -> Result<()> {
+        let file_path = path.with_file_name("NEW");
+        write(&file_path, vec![0x44; 0x300])?;
+        run_add(path, &file_path, &add_opts(side, false))
+    }
+
+    fn add_opts(side: Side, overwrite: bool) -> AddOpts {
+        AddOpts {
+            name: None,
+            load_address: None,
+            execution_address: None,
+            locked: false,
+            side,
+            format: None,
+            overwrite,
+        }
+    }
+
+    fn delete_data(path: &Path, side: Side) -> Result<()> {
+        run_delete(path, &"$.DATA".parse()?, side, None)
+    }
+
+    fn rename_prog(path: &Path, side: Side) -> Result<()> {
+        run_rename(path, &"$.PROG".parse()?, &"C.GAME".parse()?, side, None)
+    }
+
+    fn lock_data(path: &Path, side: Side) -> Result<()> {
+        run_access(path, &"$.DATA".parse()?, true, side, None)
+    }
+
+    fn set_addr_locked(path: &Path, side: Side) -> Result<()> {
+        run_set_addr(
+            path,
+            &"B.LOCKED".parse()?,
+            Some(Address::try_from(0x2000)?),
+            Some(Address::try_from(0x3000)?),
+            side,
+            None,
+        )
+    }
+
+    fn delete_locked(path: &Path, side: Side) -> Result<()> {
+        run_delete(path, &"B.LOCKED".parse()?, side, None)
+    }
+
+    fn rename_locked(path: &Path, side: Side) -> Result<()> {
+        run_rename(path, &"B.LOCKED".parse()?, &"B.OPEN".parse()?, side, None)
+    }
+
+    fn overwrite_locked(path: &Path, side: Side) -> Result<()> {
+        let file_path = path.with_file_name("B.LOCKED");
+        write(&file_path, vec![0x44; 0x10])?;
+        run_add(path, &file_path, &add_opts(side, true))
+    }
+
+    #[rstest]
+    #[case(
+        add_new,
+        &[
+            "$.NEW 000000 000000 000300 006",
+            "$.PROG 001900 008023 000080 005",
+            "B.LOCKED 001900 008023 0001FF 003 L",
+            "$.DATA 001900 008023 000100 002",
+        ],
+    )]
+    #[case(
+        delete_data,
+        &[
+            "$.PROG 001900 008023 000080 005",
+            "B.LOCKED 001900 008023 0001FF 003 L",
+        ],
+    )]
+    #[case(
+        rename_prog,
+        &[
+            "C.GAME 001900 008023 000080 005",
+            "B.LOCKED 001900 008023 0001FF 003 L",
+            "$.DATA 001900 008023 000100 002",
+        ],
+    )]
+    #[case(
+        lock_data,
+        &[
+            "$.PROG 001900 008023 000080 005",
+            "B.LOCKED 001900 008023 0001FF 003 L",
+            "$.DATA 001900 008023 000100 002 L",
+        ],
+    )]
+    #[case(
+        set_addr_locked,
+        &[
+            "$.PROG 001900 008023 000080 005",
+            "B.LOCKED 002000 003000 0001FF 003 L",
+            "$.DATA 001900 008023 000100 002",
+        ],
+    )]
+    fn edit(
+        #[values(false, true)] dsd: bool,
+        #[case] command: Command,
+        #[case] expected: &[&str],
+    ) -> Result<()> {
+        let (_dir, path, side) = make_image(dsd, 7, default_entries()?)?;
+        let before = read(&path)?;
+
+        command(&path, side)?;
+
+        let catalogue = read_catalogue(&path, side)?;
+        assert_eq!(expected, describe(&catalogue));
+        assert_eq!(8, u8::from(catalogue.cycle_number));
+
+        // Only the catalogue and any new file's sectors change
+        let mut after = read(&path)?;
+        let catalogue_offset = image_offset(dsd, side, 0);
+        after[catalogue_offset..catalogue_offset + usize::from(SECTOR_BYTES) * 2].copy_from_slice(
+            &before[catalogue_offset..catalogue_offset + usize::from(SECTOR_BYTES) * 2],
+        );
+        let new_offset = image_offset(dsd, side, 6);
+        if after[new_offset..new_offset + 0x300] == [0x44; 0x300] {
+            after[new_offset..new_offset + 0x300]
+                .copy_from_slice(&before[new_offset..new_offset + 0x300]);
+        }
+        assert_eq!(before, after);
+
+        if dsd {
+            assert_eq!(
+                vec!["$.OTHER 001900 008023 000300 002"],
+                describe(&read_catalogue(&path, Side::Side0)?)
+            );
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    fn locked(
+        #[values(false, true)] dsd: bool,
+        #[values(delete_locked, rename_locked, overwrite_locked)] command: Command,
+    ) -> Result<()> {
+        let (_dir, path, side) = make_image(dsd, 7, default_entries()?)?;
+        let before = read(&path)?;
+        assert!(command(&path, side).is_err());
+        assert_eq!(before, read(&path)?);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(0x10, 9)]
+    #[case(0x00, 99)]
+    fn cycle_number_wraps(
+        #[values(false, true)] dsd: bool,
+        #[case] expected: u8,
+        #[case] cycle_number: u8,
+    ) -> Result<()> {
+        let (_dir, path, side) = make_image(dsd, cycle_number, default_entries()?)?;
+        lock_data(&path, side)?;
+        let bytes = read(&path)?;
+        assert_eq!(expected, bytes[image_offset(dsd, side, 1) + 4]);
+        Ok(())
+    }
+
+    #[rstest]
+    fn add_empty_file_to_full_disc(#[values(false, true)] dsd: bool) -> Result<()> {
+        let length = u32::from(DISC_SIZE - 2) * u32::from(SECTOR_BYTES);
+        let (_dir, path, side) = make_image(dsd, 0, vec![make_entry("$.BIG", false, length, 2)?])?;
+
+        let file_path = path.with_file_name("EMPTY");
+        write(&file_path, [])?;
+        run_add(&path, &file_path, &add_opts(side, false))?;
+
+        assert_eq!(
+            vec![
+                "$.EMPTY 000000 000000 000000 0C8",
+                "$.BIG 001900 008023 00C600 002",
+            ],
+            describe(&read_catalogue(&path, side)?)
+        );
+
+        let file_path = file_path.with_file_name("FULL");
+        write(&file_path, [0])?;
+        assert!(run_add(&path, &file_path, &add_opts(side, false)).is_err());
+        Ok(())
+    }
+}
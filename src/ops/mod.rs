@@ -1,3 +1,7 @@
+mod edit_image;
 mod new_image;
+mod new_tape;
 
+pub use edit_image::*;
 pub use new_image::*;
+pub use new_tape::*;
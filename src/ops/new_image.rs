@@ -1,99 +1,234 @@
 use crate::dfs::{
-    BootOption, Catalogue, CatalogueEntry, FileCount, FileDescriptor, FileSpec, Length,
-    SECTOR_BYTES, START_SECTOR, SectorSize, get_file_sector_count,
+    Catalogue, CatalogueEntry, CatalogueFlavour, DiscSize, FileDescriptor, FileOffset, FileSpec,
+    Length, SECTOR_BYTES, SECTORS_PER_TRACK, SectorSize, Side, get_file_sector_count,
 };
-use crate::metadata::{Manifest, read_inf_file};
+use crate::dsd_writer::DsdWriter;
+use crate::image_writer::ImageWriter;
+use crate::metadata::{File as ManifestFile, Layout, Manifest, Slack, read_inf_file};
 use crate::path_util::strip_extension;
+use crate::ssd_writer::SsdWriter;
 use crate::util::open_for_write;
 use anyhow::{Result, anyhow, bail};
 use path_absolutize::Absolutize;
-use std::fs::{File, create_dir_all, metadata};
-use std::io::{Read, Write};
-use std::path::Path;
+use std::fs::{File, create_dir_all, read};
+use std::io::Cursor;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 pub fn new_image_file(
     output_path: &Path,
     overwrite: bool,
     manifest_dir: &Path,
-    mut manifest: Manifest,
+    manifest: &Manifest,
 ) -> Result<()> {
-    manifest.inf_files.sort();
-    manifest.files.sort_by(FileSpec::compare);
+    let bytes = make_side(manifest_dir, manifest)?;
+    let f = create_image_file(output_path, overwrite)?;
+    SsdWriter::new(&f, SECTOR_BYTES).write_bytes(Side::Side0, SectorSize::ZERO, &bytes)?;
+    set_image_length(&f, &[manifest])
+}
+
+pub fn new_dsd_image_file(
+    output_path: &Path,
+    overwrite: bool,
+    side_0: (&Path, &Manifest),
+    side_1: (&Path, &Manifest),
+) -> Result<()> {
+    let side_0_bytes = make_side(side_0.0, side_0.1)?;
+    let side_1_bytes = make_side(side_1.0, side_1.1)?;
+    let f = create_image_file(output_path, overwrite)?;
+    let mut writer = DsdWriter::new(&f, SECTOR_BYTES, SECTORS_PER_TRACK);
+    writer.write_bytes(Side::Side0, SectorSize::ZERO, &side_0_bytes)?;
+    writer.write_bytes(Side::Side1, SectorSize::ZERO, &side_1_bytes)?;
+    set_image_length(&f, &[side_0.1, side_1.1])
+}
+
+// Lay out files and catalogue for a single side of a disc in memory along
+// with the byte ranges occupied by each file. If the manifest has a preserved
+// layout, files are placed at their original start sectors and catalogue
+// order and no slack is applied
+pub fn layout_side(
+    manifest_dir: &Path,
+    manifest: &Manifest,
+) -> Result<(Vec<u8>, Vec<Range<usize>>)> {
+    if manifest.catalogue_flavour == CatalogueFlavour::Ddos {
+        bail!(
+            "making DDOS disc images is not supported: set catalogueFlavour to acorn to make an .ssd file instead"
+        )
+    }
+
+    let disc_size = manifest.disc_size;
+    let mut inf_files = manifest.inf_files.iter().collect::<Vec<_>>();
+    inf_files.sort();
+    let mut files = manifest.files.iter().collect::<Vec<_>>();
+    files.sort_by(|a, b| FileSpec::compare(*a, *b));
+    let contents = read_contents(manifest_dir, &inf_files, &files)?;
+    let placed = match &manifest.layout {
+        Some(layout) => place_from_layout(layout, contents)?,
+        None => place_sequentially(contents, manifest.catalogue_flavour, disc_size)?,
+    };
+
+    let mut bytes = vec![0u8; usize::from(disc_size) * usize::from(SECTOR_BYTES)];
+    {
+        let mut writer = SsdWriter::new(Cursor::new(&mut bytes), SECTOR_BYTES);
+        for (entry, content) in &placed {
+            writer.write_bytes(Side::Side0, entry.start_sector, content)?;
+        }
+    }
+
+    // Content may have been edited since the layout was recorded, so make
+    // sure that no file has been clobbered by another
+    if manifest.layout.is_some() {
+        for (entry, content) in &placed {
+            let offset = usize::from(entry.start_sector) * usize::from(SECTOR_BYTES);
+            if bytes[offset..offset + content.len()] != content[..] {
+                bail!(
+                    "file {directory}.{file_name} overlaps another file at its preserved start sector",
+                    directory = entry.descriptor.directory,
+                    file_name = entry.descriptor.file_name
+                )
+            }
+        }
+    }
+
+    let file_extents = placed
+        .iter()
+        .map(|(entry, content)| {
+            let offset = usize::from(entry.start_sector) * usize::from(SECTOR_BYTES);
+            offset..offset + content.len()
+        })
+        .collect::<Vec<_>>();
+
+    let entries = placed
+        .into_iter()
+        .map(|(entry, _)| entry)
+        .collect::<Vec<_>>();
+
+    let mut catalogue = Catalogue::new(
+        manifest
+            .disc_title
+            .clone()
+            .unwrap_or_else(|| "".parse().unwrap()),
+        manifest.cycle_number,
+        FileOffset::new(0)?,
+        manifest.boot_option,
+        disc_size,
+        manifest.catalogue_flavour,
+        entries,
+    );
+    catalogue.update_file_offset()?;
+
+    catalogue.write_to_catalogue(&mut bytes)?;
 
-    let mut bytes = vec![0u8; u16::from(manifest.disc_size) as usize * usize::from(SECTOR_BYTES)];
+    Ok((bytes, file_extents))
+}
+
+fn make_side(manifest_dir: &Path, manifest: &Manifest) -> Result<Vec<u8>> {
+    let (mut bytes, file_extents) = layout_side(manifest_dir, manifest)?;
+    if let Some(layout) = &manifest.layout {
+        let slack = Slack::from_bytes(&read(layout.slack_path.absolutize_from(manifest_dir)?)?)?;
+        slack.apply(
+            &mut bytes,
+            manifest.catalogue_flavour.catalogue_len(),
+            &file_extents,
+        );
+    }
+    Ok(bytes)
+}
 
-    let mut start_sector = START_SECTOR;
-    let mut entries = Vec::new();
+// Read file metadata and content in the order given
+pub fn read_contents(
+    manifest_dir: &Path,
+    inf_files: &[&PathBuf],
+    files: &[&ManifestFile],
+) -> Result<Vec<(FileDescriptor, Vec<u8>)>> {
+    let mut contents = Vec::with_capacity(inf_files.len() + files.len());
 
-    for inf_file in manifest.inf_files {
+    for inf_file in inf_files {
         let p = inf_file.absolutize_from(manifest_dir)?;
         let descriptor = read_inf_file(&p)?;
         let content_path = strip_extension(&p)?;
-        let (entry, sector_count) =
-            write_content(&mut bytes, &content_path, descriptor, start_sector)?;
-        entries.push(entry);
-        start_sector += sector_count;
+        contents.push((descriptor, read(content_path)?));
     }
 
-    for file in manifest.files {
+    for file in files {
         let content_path = file.content_path.absolutize_from(manifest_dir)?;
-        let (entry, sector_count) = write_content(
-            &mut bytes,
-            &content_path,
-            file.to_file_descriptor(),
-            start_sector,
-        )?;
-        entries.push(entry);
-        start_sector += sector_count;
+        contents.push((file.to_file_descriptor(), read(content_path)?));
     }
 
-    let disc_size = manifest.disc_size;
+    Ok(contents)
+}
 
-    if u16::from(start_sector) > u16::from(disc_size) {
-        bail!("exceeded capacity of disc")
+fn place_sequentially(
+    contents: Vec<(FileDescriptor, Vec<u8>)>,
+    flavour: CatalogueFlavour,
+    disc_size: DiscSize,
+) -> Result<Vec<(CatalogueEntry, Vec<u8>)>> {
+    let mut start_sector = flavour.start_sector();
+    let mut placed = Vec::with_capacity(contents.len());
+    for (descriptor, content) in contents {
+        let length = Length::try_from(u32::try_from(content.len())?)?;
+        let end_sector = start_sector + get_file_sector_count(length)?;
+        if u16::from(end_sector) > u16::from(disc_size) {
+            bail!("exceeded capacity of disc")
+        }
+
+        placed.push((
+            CatalogueEntry::new(descriptor, length, start_sector),
+            content,
+        ));
+        start_sector = end_sector;
     }
+    Ok(placed)
+}
 
-    let file_count: FileCount = u8::try_from(entries.len())?.try_into()?;
-    let file_offset = file_count.into();
-    let boot_option = BootOption::Exec;
+fn place_from_layout(
+    layout: &Layout,
+    mut contents: Vec<(FileDescriptor, Vec<u8>)>,
+) -> Result<Vec<(CatalogueEntry, Vec<u8>)>> {
+    let mut placed = Vec::with_capacity(layout.entries.len());
+    for layout_entry in &layout.entries {
+        let Some(index) = contents.iter().position(|(d, _)| d.matches(layout_entry)) else {
+            bail!(
+                "file {directory}.{file_name} in layout not found in manifest",
+                directory = layout_entry.directory,
+                file_name = layout_entry.file_name
+            )
+        };
+        let (descriptor, content) = contents.remove(index);
+        let length = Length::try_from(u32::try_from(content.len())?)?;
+        placed.push((
+            CatalogueEntry::new(descriptor, length, layout_entry.start_sector),
+            content,
+        ));
+    }
 
-    let catalogue = Catalogue::new(
-        manifest.disc_title.unwrap_or_else(|| "".parse().unwrap()),
-        manifest.cycle_number,
-        file_offset,
-        boot_option,
-        disc_size,
-        entries,
-    );
+    if let Some((d, _)) = contents.first() {
+        bail!(
+            "file {directory}.{file_name} in manifest not found in layout",
+            directory = d.directory,
+            file_name = d.file_name
+        )
+    }
 
-    catalogue.write_to_catalogue(&mut bytes)?;
+    Ok(placed)
+}
 
+pub fn create_image_file(output_path: &Path, overwrite: bool) -> Result<File> {
     let output_dir = output_path
         .parent()
         .ok_or_else(|| anyhow!("cannot get parent"))?;
     create_dir_all(output_dir)?;
-
-    let mut output_file = open_for_write(output_path, overwrite)?;
-    output_file.write_all(&bytes)?;
-
-    Ok(())
+    open_for_write(output_path, overwrite)
 }
 
-fn write_content(
-    bytes: &mut [u8],
-    path: &Path,
-    descriptor: FileDescriptor,
-    start_sector: SectorSize,
-) -> Result<(CatalogueEntry, SectorSize)> {
-    let m = metadata(path)?;
-    let length = Length::try_from(u32::try_from(m.len())?)?;
-    let mut f = File::open(path)?;
-    let start_offset = usize::from(start_sector) * usize::from(SECTOR_BYTES);
-    let end_offset = start_offset + usize::try_from(m.len())?;
-    f.read_exact(&mut bytes[start_offset..end_offset])?;
-
-    Ok((
-        CatalogueEntry::new(descriptor, length, start_sector),
-        get_file_sector_count(length)?,
-    ))
+// Restore original length of image, which may be truncated or padded
+fn set_image_length(f: &File, manifests: &[&Manifest]) -> Result<()> {
+    if let Some(image_length) = manifests
+        .iter()
+        .filter_map(|manifest| manifest.layout.as_ref().map(|layout| layout.image_length))
+        .max()
+    {
+        f.set_len(image_length)?;
+    }
+    Ok(())
 }
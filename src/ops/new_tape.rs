@@ -0,0 +1,28 @@
+use crate::metadata::Manifest;
+use crate::ops::{create_image_file, read_contents};
+use crate::uef::{TapeFile, write_uef};
+use anyhow::{Result, bail};
+use std::io::BufWriter;
+use std::path::Path;
+
+// Files are written to tape in manifest order, .inf files first
+pub fn new_uef_file(
+    output_path: &Path,
+    overwrite: bool,
+    manifest_dir: &Path,
+    manifest: &Manifest,
+) -> Result<()> {
+    if manifest.layout.is_some() {
+        bail!("manifests with a preserved layout cannot be written to tape")
+    }
+
+    let inf_files = manifest.inf_files.iter().collect::<Vec<_>>();
+    let files = manifest.files.iter().collect::<Vec<_>>();
+    let tape_files = read_contents(manifest_dir, &inf_files, &files)?
+        .into_iter()
+        .map(|(descriptor, content)| TapeFile::from_file_descriptor(&descriptor, content))
+        .collect::<Vec<_>>();
+
+    let f = create_image_file(output_path, overwrite)?;
+    write_uef(BufWriter::new(f), &tape_files)
+}
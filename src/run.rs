@@ -1,19 +1,76 @@
-use crate::args::{Args, Command};
+use crate::args::{Args, Command, MmbCommand};
 use crate::commands::{
-    ExtractOpts, run_detokenize, run_extract, run_make, run_manifest, run_new, run_show,
-    run_tokenize,
+    AddOpts, ExtractOpts, run_access, run_add, run_check, run_compact, run_crunch, run_delete,
+    run_detokenize, run_diff, run_extract, run_lint, run_make, run_manifest, run_map,
+    run_mmb_extract, run_mmb_insert, run_mmb_list, run_mmb_lock, run_mmb_remove, run_new,
+    run_rename, run_renumber, run_set_addr, run_show, run_textconv, run_tokenize, run_xref,
 };
 use anyhow::Result;
 use clap::Parser;
 
+#[allow(clippy::too_many_lines)]
 pub fn run() -> Result<()> {
     match Args::parse().command {
+        Command::Access {
+            image_path,
+            name,
+            lock,
+            unlock: _,
+            side,
+            format,
+        } => run_access(&image_path, &name, lock, side, format)?,
+        Command::Add {
+            image_path,
+            path,
+            name,
+            load_address,
+            execution_address,
+            locked,
+            side,
+            format,
+            overwrite,
+        } => run_add(
+            &image_path,
+            &path,
+            &AddOpts {
+                name,
+                load_address,
+                execution_address,
+                locked,
+                side,
+                format,
+                overwrite,
+            },
+        )?,
+        Command::Check { path, json, format } => run_check(&path, format, json)?,
+        Command::Compact {
+            image_path,
+            side,
+            format,
+        } => run_compact(&image_path, side, format)?,
+        Command::Crunch {
+            path,
+            output_path,
+            overwrite,
+            shorten_names,
+        } => run_crunch(&path, output_path.as_ref(), overwrite, shorten_names)?,
+        Command::Delete {
+            image_path,
+            name,
+            side,
+            format,
+        } => run_delete(&image_path, &name, side, format)?,
         Command::Detokenize {
             path,
             output_path,
             overwrite,
             lossless,
         } => run_detokenize(&path, output_path.as_ref(), overwrite, lossless)?,
+        Command::Diff {
+            path_a,
+            path_b,
+            format,
+        } => run_diff(&path_a, &path_b, format)?,
         Command::Extract {
             path,
             output_dir,
@@ -21,6 +78,8 @@ pub fn run() -> Result<()> {
             no_detokenize,
             lossless,
             inf,
+            preserve,
+            format,
         } => run_extract(
             &path,
             &output_dir,
@@ -29,30 +88,122 @@ pub fn run() -> Result<()> {
                 detokenize: !no_detokenize,
                 lossless,
                 inf,
+                preserve,
+                format,
             },
         )?,
+        Command::Lint { path } => run_lint(&path)?,
         Command::Make {
             path,
             side_1_path,
             output_path,
             overwrite,
-        } => run_make(&path, side_1_path.as_ref(), &output_path, overwrite)?,
+            boot_option,
+        } => run_make(
+            &path,
+            side_1_path.as_ref(),
+            &output_path,
+            overwrite,
+            boot_option,
+        )?,
+        Command::Map { path, format } => run_map(&path, format)?,
+        Command::Mmb { command } => run_mmb(command)?,
         Command::New {
             output_path,
             disc_size,
             overwrite,
-        } => run_new(&output_path, disc_size, overwrite)?,
+            boot_option,
+            format,
+        } => run_new(&output_path, disc_size, overwrite, boot_option, format)?,
         Command::Manifest {
             dir,
             output_path,
             overwrite,
         } => run_manifest(&dir, output_path.as_ref(), overwrite)?,
-        Command::Show { path } => run_show(&path)?,
+        Command::Rename {
+            image_path,
+            name,
+            new_name,
+            side,
+            format,
+        } => run_rename(&image_path, &name, &new_name, side, format)?,
+        Command::SetAddr {
+            image_path,
+            name,
+            load_address,
+            execution_address,
+            side,
+            format,
+        } => run_set_addr(
+            &image_path,
+            &name,
+            load_address,
+            execution_address,
+            side,
+            format,
+        )?,
+        Command::Renumber {
+            path,
+            output_path,
+            overwrite,
+            start,
+            step,
+            from,
+        } => run_renumber(&path, output_path.as_ref(), overwrite, start, step, from)?,
+        Command::Show {
+            path,
+            lenient,
+            output_format,
+            style,
+            format,
+        } => run_show(&path, format, lenient, output_format, style)?,
+        Command::Textconv { path, format } => run_textconv(&path, format)?,
         Command::Tokenize {
             path,
             output_path,
             overwrite,
         } => run_tokenize(&path, &output_path, overwrite)?,
+        Command::Xref { path, json } => run_xref(&path, json)?,
+    }
+    Ok(())
+}
+
+fn run_mmb(command: MmbCommand) -> Result<()> {
+    match command {
+        MmbCommand::List { mmb_path } => run_mmb_list(&mmb_path)?,
+        MmbCommand::Extract {
+            mmb_path,
+            slot,
+            output_dir,
+            overwrite,
+            no_detokenize,
+            lossless,
+            inf,
+        } => run_mmb_extract(
+            &mmb_path,
+            slot,
+            &output_dir,
+            &ExtractOpts {
+                overwrite,
+                detokenize: !no_detokenize,
+                lossless,
+                inf,
+                preserve: false,
+                format: None,
+            },
+        )?,
+        MmbCommand::Insert {
+            mmb_path,
+            image_path,
+            slot,
+            overwrite,
+        } => run_mmb_insert(&mmb_path, &image_path, slot, overwrite)?,
+        MmbCommand::Remove { mmb_path, slot } => run_mmb_remove(&mmb_path, slot)?,
+        MmbCommand::Lock {
+            mmb_path,
+            slot,
+            unlock,
+        } => run_mmb_lock(&mmb_path, slot, !unlock)?,
     }
     Ok(())
 }
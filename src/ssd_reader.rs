@@ -29,6 +29,14 @@ impl<R: Read + Seek> ImageReader for SsdReader<R> {
         self.stream_len
     }
 
+    fn side_len(&self, side: Side) -> u64 {
+        if u8::from(side) > 0 {
+            0
+        } else {
+            self.stream_len
+        }
+    }
+
     fn sector_bytes(&self) -> SectorBytes {
         self.sector_bytes
     }
@@ -0,0 +1,34 @@
+use crate::dfs::{SectorBytes, SectorSize, Side};
+use crate::image_writer::ImageWriter;
+use anyhow::{Result, bail};
+use std::io::{Seek, SeekFrom, Write};
+
+pub struct SsdWriter<W: Write + Seek> {
+    sector_bytes: SectorBytes,
+    writer: W,
+}
+
+impl<W: Write + Seek> SsdWriter<W> {
+    pub const fn new(writer: W, sector_bytes: SectorBytes) -> Self {
+        Self {
+            sector_bytes,
+            writer,
+        }
+    }
+}
+
+impl<W: Write + Seek> ImageWriter for SsdWriter<W> {
+    fn write_bytes(&mut self, side: Side, start_sector: SectorSize, buffer: &[u8]) -> Result<()> {
+        if u8::from(side) > 0 {
+            bail!("SSD writer supports one side only")
+        }
+
+        self.writer.seek(SeekFrom::Start(
+            u64::from(start_sector) * u64::from(self.sector_bytes),
+        ))?;
+
+        self.writer.write_all(buffer)?;
+
+        Ok(())
+    }
+}
@@ -9,6 +9,12 @@ macro_rules! u10 {
             pub const fn to_u16(self) -> u16 {
                 self.0
             }
+
+            // Keep only the low 10 bits, as stored in a catalogue entry
+            #[allow(unused)]
+            pub const fn from_bits_truncate(value: u16) -> Self {
+                Self(value & 0x3ff)
+            }
         }
 
         impl std::ops::Add for $name {
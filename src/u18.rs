@@ -9,6 +9,12 @@ macro_rules! u18 {
             pub const fn to_u32(self) -> u32 {
                 self.0
             }
+
+            // Keep only the low 18 bits, as stored in a catalogue entry
+            #[allow(unused)]
+            pub const fn from_bits_truncate(value: u32) -> Self {
+                Self(value & 0x3ffff)
+            }
         }
 
         impl std::ops::Add for $name {
@@ -29,7 +35,7 @@ macro_rules! u18 {
             type Error = anyhow::Error;
 
             fn try_from(value: u32) -> std::result::Result<Self, Self::Error> {
-                if value > 0x3fffff {
+                if value > 0x3ffff {
                     anyhow::bail!("cannot convert {value} to u18")
                 }
                 Ok(Self(value))
@@ -0,0 +1,29 @@
+pub const UEF_EXT: &str = "uef";
+
+pub const UEF_MAGIC: &[u8; 10] = b"UEF File!\0";
+
+// Minor version followed by major version
+pub const UEF_VERSION: [u8; 2] = [10, 0];
+
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+pub const CHUNK_DATA: u16 = 0x0100;
+
+pub const CHUNK_DEFINED_FORMAT_DATA: u16 = 0x0104;
+
+pub const CHUNK_CARRIER_TONE: u16 = 0x0110;
+
+// Cycles of 2400 Hz carrier tone written before the first block of a file
+// and before each subsequent block
+pub const FILE_LEADER_CYCLES: u16 = 12000;
+pub const BLOCK_LEADER_CYCLES: u16 = 2160;
+
+pub const CFS_SYNC_BYTE: u8 = 0x2a;
+
+pub const CFS_BLOCK_BYTES: usize = 256;
+
+pub const CFS_MAX_NAME_LEN: usize = 10;
+
+pub const BLOCK_FLAG_LOCKED: u8 = 0x01;
+pub const BLOCK_FLAG_EMPTY: u8 = 0x40;
+pub const BLOCK_FLAG_LAST: u8 = 0x80;
@@ -0,0 +1,26 @@
+// CRC-16/XMODEM as used by the cassette filing system: polynomial &1021,
+// initial value zero, stored high byte first
+pub fn cfs_crc(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &b in bytes {
+        crc ^= u16::from(b) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x1021
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::uef::cfs_crc;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(0x31c3, cfs_crc(b"123456789"));
+    }
+}
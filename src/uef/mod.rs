@@ -0,0 +1,11 @@
+mod constants;
+mod crc;
+mod tape_file;
+mod uef_reader;
+mod uef_writer;
+
+pub use constants::*;
+pub use crc::*;
+pub use tape_file::*;
+pub use uef_reader::*;
+pub use uef_writer::*;
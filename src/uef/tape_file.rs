@@ -0,0 +1,43 @@
+use crate::dfs::{Address, DfsPath, Directory, FileDescriptor, FileName};
+
+// File reassembled from the blocks written by the cassette filing system
+#[derive(Debug)]
+pub struct TapeFile {
+    pub name: String,
+    pub load_address: u32,
+    pub execution_address: u32,
+    pub locked: bool,
+    pub content: Vec<u8>,
+}
+
+impl TapeFile {
+    // Tape names of the form D.NAME keep their directory. Names that are not
+    // valid DFS names are truncated and have invalid characters replaced.
+    pub fn to_file_descriptor(&self) -> FileDescriptor {
+        let (file_name, directory) = match self.name.parse::<DfsPath>() {
+            Ok(p) => (p.file_name, p.directory),
+            Err(_) => (
+                FileName::from_bytes_lossy(self.name.as_bytes()),
+                Directory::ROOT,
+            ),
+        };
+        FileDescriptor::new(
+            file_name,
+            directory,
+            self.locked,
+            Address::from_bits_truncate(self.load_address),
+            Address::from_bits_truncate(self.execution_address),
+        )
+    }
+
+    pub fn from_file_descriptor(descriptor: &FileDescriptor, content: Vec<u8>) -> Self {
+        let name = descriptor.content_path().to_string_lossy().into_owned();
+        Self {
+            name,
+            load_address: descriptor.load_address.to_cfs_address(),
+            execution_address: descriptor.execution_address.to_cfs_address(),
+            locked: descriptor.locked,
+            content,
+        }
+    }
+}
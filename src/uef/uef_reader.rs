@@ -0,0 +1,341 @@
+use crate::uef::{
+    BLOCK_FLAG_EMPTY, BLOCK_FLAG_LAST, BLOCK_FLAG_LOCKED, CFS_MAX_NAME_LEN, CFS_SYNC_BYTE,
+    CHUNK_DATA, CHUNK_DEFINED_FORMAT_DATA, GZIP_MAGIC, TapeFile, UEF_MAGIC, cfs_crc,
+};
+use anyhow::{Result, bail};
+use flate2::read::GzDecoder;
+use std::fs::read;
+use std::io::Read;
+use std::path::Path;
+
+// http://electrem.emuunlim.com/UEFSpecs.html
+pub fn read_uef_file(path: &Path) -> Result<Vec<TapeFile>> {
+    let bytes = read(path)?;
+    let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+        decompressed
+    } else {
+        bytes
+    };
+    read_uef(&bytes)
+}
+
+pub fn read_uef(bytes: &[u8]) -> Result<Vec<TapeFile>> {
+    if !bytes.starts_with(UEF_MAGIC) {
+        bail!("not a UEF file")
+    }
+
+    // Magic is followed by a two-byte version number
+    let Some(chunks) = bytes.get(UEF_MAGIC.len() + 2..) else {
+        bail!("UEF header truncated")
+    };
+
+    let stream = read_data_stream(chunks)?;
+    let blocks = read_blocks(&stream);
+    assemble_files(blocks)
+}
+
+// Concatenate the bytes recorded in every data chunk, ignoring tone, gaps
+// and chunks describing other kinds of data
+fn read_data_stream(mut bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = Vec::new();
+    while !bytes.is_empty() {
+        if bytes.len() < 6 {
+            bail!("UEF chunk header truncated")
+        }
+
+        let id = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let len = usize::try_from(u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]))?;
+        let Some(data) = bytes.get(6..6 + len) else {
+            bail!("UEF chunk &{id:04X} truncated")
+        };
+
+        match id {
+            CHUNK_DATA => stream.extend_from_slice(data),
+            // Only 8 data bits, no parity and one stop bit as used by CFS
+            CHUNK_DEFINED_FORMAT_DATA if data.len() >= 3 && data[0..3] == [8, b'N', 1] => {
+                stream.extend_from_slice(&data[3..]);
+            }
+            _ => {}
+        }
+
+        bytes = &bytes[6 + len..];
+    }
+    Ok(stream)
+}
+
+struct Block {
+    name: String,
+    load_address: u32,
+    execution_address: u32,
+    number: u16,
+    flag: u8,
+    data: Vec<u8>,
+}
+
+// CFS block:
+// &2A sync byte
+// file name of up to 10 characters terminated by zero
+// load address (4 bytes), execution address (4 bytes)
+// block number (2 bytes), data length (2 bytes), block flag
+// address of next file (4 bytes)
+// header CRC (2 bytes, high byte first)
+// data followed by data CRC (2 bytes, high byte first) if length is not zero
+fn read_blocks(stream: &[u8]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < stream.len() {
+        if stream[i] == CFS_SYNC_BYTE
+            && let Some((block, len)) = read_block(&stream[i + 1..])
+        {
+            blocks.push(block);
+            i += 1 + len;
+        } else {
+            i += 1;
+        }
+    }
+    blocks
+}
+
+// Returns None if the bytes do not start with a block with valid CRCs
+fn read_block(bytes: &[u8]) -> Option<(Block, usize)> {
+    let name_len = bytes
+        .iter()
+        .take(CFS_MAX_NAME_LEN + 1)
+        .position(|&b| b == 0)?;
+    let header_len = name_len + 1 + 19;
+    let header = bytes.get(..header_len - 2)?;
+    let header_crc = bytes.get(header_len - 2..header_len)?;
+    if cfs_crc(header) != u16::from_be_bytes([header_crc[0], header_crc[1]]) {
+        return None;
+    }
+
+    let fields = &header[name_len + 1..];
+    let u32_at =
+        |i: usize| u32::from_le_bytes([fields[i], fields[i + 1], fields[i + 2], fields[i + 3]]);
+    let data_len = usize::from(u16::from_le_bytes([fields[10], fields[11]]));
+    let flag = fields[12];
+
+    let mut len = header_len;
+    let data = if data_len == 0 {
+        Vec::new()
+    } else {
+        let data = bytes.get(header_len..header_len + data_len)?;
+        let data_crc = bytes.get(header_len + data_len..header_len + data_len + 2)?;
+        if cfs_crc(data) != u16::from_be_bytes([data_crc[0], data_crc[1]]) {
+            return None;
+        }
+        len += data_len + 2;
+        data.to_vec()
+    };
+
+    Some((
+        Block {
+            name: header[..name_len].iter().map(|&b| char::from(b)).collect(),
+            load_address: u32_at(0),
+            execution_address: u32_at(4),
+            number: u16::from_le_bytes([fields[8], fields[9]]),
+            flag,
+            data,
+        },
+        len,
+    ))
+}
+
+// Tapes often repeat blocks that failed to load the first time, so blocks
+// already seen are skipped
+fn assemble_files(blocks: Vec<Block>) -> Result<Vec<TapeFile>> {
+    let mut files = Vec::new();
+    let mut current: Option<(TapeFile, u16)> = None;
+    for block in blocks {
+        if block.number == 0 {
+            match &current {
+                Some((file, _)) if file.name == block.name => continue,
+                Some((file, _)) => bail!("file {name} is incomplete", name = file.name),
+                None => {}
+            }
+            current = Some((
+                TapeFile {
+                    name: block.name.clone(),
+                    load_address: block.load_address,
+                    execution_address: block.execution_address,
+                    locked: false,
+                    content: Vec::new(),
+                },
+                0,
+            ));
+        }
+
+        let Some((file, next)) = current.as_mut() else {
+            bail!(
+                "block {number} of file {name} found without block 0",
+                number = block.number,
+                name = block.name
+            )
+        };
+
+        if block.name != file.name || block.number > *next {
+            bail!(
+                "expected block {next} of file {expected} but found block {number} of file {name}",
+                expected = file.name,
+                number = block.number,
+                name = block.name
+            )
+        }
+
+        if block.number < *next {
+            continue;
+        }
+
+        if block.flag & BLOCK_FLAG_EMPTY == 0 {
+            file.content.extend_from_slice(&block.data);
+        }
+        file.locked |= block.flag & BLOCK_FLAG_LOCKED != 0;
+        *next += 1;
+
+        if block.flag & BLOCK_FLAG_LAST != 0
+            && let Some((file, _)) = current.take()
+        {
+            files.push(file);
+        }
+    }
+
+    if let Some((file, _)) = current {
+        bail!("file {name} is incomplete", name = file.name)
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::uef::{
+        BLOCK_FLAG_EMPTY, BLOCK_FLAG_LAST, CHUNK_CARRIER_TONE, CHUNK_DATA,
+        CHUNK_DEFINED_FORMAT_DATA, TapeFile, UEF_MAGIC, UEF_VERSION, make_block, read_uef,
+    };
+    use anyhow::Result;
+    use rstest::rstest;
+
+    fn make_file(name: &str) -> TapeFile {
+        TapeFile {
+            name: String::from(name),
+            load_address: 0x1900,
+            execution_address: 0x8023,
+            locked: false,
+            content: Vec::new(),
+        }
+    }
+
+    // Block of a file whose block n holds 256 bytes of value n
+    fn block(name: &str, number: u16, last: bool) -> Vec<u8> {
+        let flag = if last { BLOCK_FLAG_LAST } else { 0 };
+        let data = vec![u8::try_from(number).unwrap(); 256];
+        make_block(&make_file(name), number, flag, &data).unwrap()
+    }
+
+    fn make_tape(chunks: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = UEF_MAGIC.to_vec();
+        bytes.extend_from_slice(&UEF_VERSION);
+        for (id, data) in chunks {
+            bytes.extend_from_slice(&id.to_le_bytes());
+            bytes.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+            bytes.extend_from_slice(data);
+        }
+        bytes
+    }
+
+    fn data_chunks(blocks: &[Vec<u8>]) -> Vec<(u16, Vec<u8>)> {
+        blocks
+            .iter()
+            .map(|block| (CHUNK_DATA, block.clone()))
+            .collect()
+    }
+
+    fn contents(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+        Ok(read_uef(bytes)?
+            .into_iter()
+            .map(|file| (file.name, file.content))
+            .collect())
+    }
+
+    #[test]
+    fn truncated_header() {
+        let e = read_uef(UEF_MAGIC).unwrap_err();
+        assert_eq!("UEF header truncated", e.to_string());
+    }
+
+    #[rstest]
+    // Chunk header cut short
+    #[case(3)]
+    // Chunk data cut short
+    #[case(20)]
+    fn truncated_chunk(#[case] cut: usize) {
+        let mut bytes = make_tape(&data_chunks(&[block("FILE", 0, true)]));
+        bytes.truncate(UEF_MAGIC.len() + UEF_VERSION.len() + cut);
+        assert!(read_uef(&bytes).is_err());
+    }
+
+    #[rstest]
+    // Block 1 without block 0
+    #[case(vec![block("FILE", 1, true)])]
+    // Block 2 before block 1
+    #[case(vec![block("FILE", 0, false), block("FILE", 2, true)])]
+    // Block of another file before the last block
+    #[case(vec![block("FILE", 0, false), block("OTHER", 1, true)])]
+    // New file before the last block
+    #[case(vec![block("FILE", 0, false), block("OTHER", 0, true)])]
+    // Last block missing
+    #[case(vec![block("FILE", 0, false), block("FILE", 1, false)])]
+    fn invalid_block_order(#[case] blocks: Vec<Vec<u8>>) {
+        assert!(read_uef(&make_tape(&data_chunks(&blocks))).is_err());
+    }
+
+    #[test]
+    fn repeated_and_corrupt_blocks() -> Result<()> {
+        let mut corrupt = block("FILE", 1, false);
+        corrupt[100] ^= 0xff;
+        let bytes = make_tape(&data_chunks(&[
+            block("FILE", 0, false),
+            block("FILE", 1, false),
+            block("FILE", 0, false),
+            corrupt,
+            block("FILE", 1, false),
+            block("FILE", 2, true),
+        ]));
+
+        let expected = [vec![0; 256], vec![1; 256], vec![2; 256]].concat();
+        assert_eq!(vec![(String::from("FILE"), expected)], contents(&bytes)?);
+        Ok(())
+    }
+
+    #[rstest]
+    // 8N1 as used by CFS
+    #[case(vec![(String::from("FILE"), vec![0; 256])], [8, b'N', 1])]
+    // Other framings are ignored
+    #[case(Vec::new(), [7, b'E', 1])]
+    fn defined_format_data(
+        #[case] expected: Vec<(String, Vec<u8>)>,
+        #[case] format: [u8; 3],
+    ) -> Result<()> {
+        let mut data = format.to_vec();
+        data.extend(block("FILE", 0, true));
+        let bytes = make_tape(&[
+            (CHUNK_CARRIER_TONE, 2160u16.to_le_bytes().to_vec()),
+            (CHUNK_DEFINED_FORMAT_DATA, data),
+        ]);
+        assert_eq!(expected, contents(&bytes)?);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_file() -> Result<()> {
+        let file = make_file("EMPTY");
+        let bytes = make_tape(&[(
+            CHUNK_DATA,
+            make_block(&file, 0, BLOCK_FLAG_LAST | BLOCK_FLAG_EMPTY, &[])?,
+        )]);
+        assert_eq!(vec![(String::from("EMPTY"), Vec::new())], contents(&bytes)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::uef::{
+    BLOCK_FLAG_EMPTY, BLOCK_FLAG_LAST, BLOCK_FLAG_LOCKED, BLOCK_LEADER_CYCLES, CFS_BLOCK_BYTES,
+    CFS_MAX_NAME_LEN, CFS_SYNC_BYTE, CHUNK_CARRIER_TONE, CHUNK_DATA, FILE_LEADER_CYCLES, TapeFile,
+    UEF_MAGIC, UEF_VERSION, cfs_crc,
+};
+use anyhow::{Result, bail};
+use std::io::Write;
+
+// Writes each file as a sequence of 256-byte CFS blocks, one per data chunk,
+// each preceded by carrier tone as the cassette filing system does
+pub fn write_uef<W: Write>(mut writer: W, files: &[TapeFile]) -> Result<()> {
+    writer.write_all(UEF_MAGIC)?;
+    writer.write_all(&UEF_VERSION)?;
+
+    for file in files {
+        if file.name.is_empty() || file.name.len() > CFS_MAX_NAME_LEN || !file.name.is_ascii() {
+            bail!("invalid tape file name {name}", name = file.name)
+        }
+
+        let chunks = file.content.chunks(CFS_BLOCK_BYTES).collect::<Vec<_>>();
+        let block_count = chunks.len().max(1);
+        for number in 0..block_count {
+            let data = chunks.get(number).copied().unwrap_or_default();
+            let mut flag = if file.locked { BLOCK_FLAG_LOCKED } else { 0 };
+            if number + 1 == block_count {
+                flag |= BLOCK_FLAG_LAST;
+            }
+            if data.is_empty() {
+                flag |= BLOCK_FLAG_EMPTY;
+            }
+
+            let leader = if number == 0 {
+                FILE_LEADER_CYCLES
+            } else {
+                BLOCK_LEADER_CYCLES
+            };
+            write_chunk(&mut writer, CHUNK_CARRIER_TONE, &leader.to_le_bytes())?;
+            write_chunk(
+                &mut writer,
+                CHUNK_DATA,
+                &make_block(file, u16::try_from(number)?, flag, data)?,
+            )?;
+        }
+    }
+
+    write_chunk(
+        &mut writer,
+        CHUNK_CARRIER_TONE,
+        &BLOCK_LEADER_CYCLES.to_le_bytes(),
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+// CFS block as described in uef_reader.rs
+pub fn make_block(file: &TapeFile, number: u16, flag: u8, data: &[u8]) -> Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(CFS_MAX_NAME_LEN + 20);
+    header.extend_from_slice(file.name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&file.load_address.to_le_bytes());
+    header.extend_from_slice(&file.execution_address.to_le_bytes());
+    header.extend_from_slice(&number.to_le_bytes());
+    header.extend_from_slice(&u16::try_from(data.len())?.to_le_bytes());
+    header.push(flag);
+    header.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut block = Vec::with_capacity(header.len() + data.len() + 5);
+    block.push(CFS_SYNC_BYTE);
+    block.extend_from_slice(&header);
+    block.extend_from_slice(&cfs_crc(&header).to_be_bytes());
+    if !data.is_empty() {
+        block.extend_from_slice(data);
+        block.extend_from_slice(&cfs_crc(data).to_be_bytes());
+    }
+    Ok(block)
+}
+
+fn write_chunk<W: Write>(writer: &mut W, id: u16, data: &[u8]) -> Result<()> {
+    writer.write_all(&id.to_le_bytes())?;
+    writer.write_all(&u32::try_from(data.len())?.to_le_bytes())?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::uef::{TapeFile, write_uef};
+    use anyhow::Result;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use rstest::rstest;
+    use std::io::Write;
+
+    fn make_files() -> Vec<TapeFile> {
+        vec![
+            TapeFile {
+                name: String::from("LOADER"),
+                load_address: 0xffff_0e00,
+                execution_address: 0xffff_8023,
+                locked: false,
+                content: (0..600u16).map(|i| i.to_le_bytes()[0]).collect(),
+            },
+            TapeFile {
+                name: String::from("EMPTY"),
+                load_address: 0x1900,
+                execution_address: 0x1900,
+                locked: true,
+                content: Vec::new(),
+            },
+        ]
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn round_trip(#[case] gzip: bool) -> Result<()> {
+        let files = make_files();
+        let mut bytes = Vec::new();
+        write_uef(&mut bytes, &files)?;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("tape.uef");
+        if gzip {
+            let mut encoder = GzEncoder::new(std::fs::File::create(&path)?, Compression::default());
+            encoder.write_all(&bytes)?;
+            encoder.finish()?;
+        } else {
+            std::fs::write(&path, &bytes)?;
+        }
+
+        let read_files = crate::uef::read_uef_file(&path)?;
+        assert_eq!(files.len(), read_files.len());
+        for (expected, actual) in files.iter().zip(&read_files) {
+            assert_eq!(expected.name, actual.name);
+            assert_eq!(expected.load_address, actual.load_address);
+            assert_eq!(expected.execution_address, actual.execution_address);
+            assert_eq!(expected.locked, actual.locked);
+            assert_eq!(expected.content, actual.content);
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{Result, bail};
+use flate2::Crc;
 use std::fs::File;
 use std::io::ErrorKind;
 use std::path::Path;
@@ -25,3 +26,9 @@ pub fn open_for_write(path: &Path, overwrite: bool) -> Result<File> {
 pub fn is_ascii_printable(value: u8) -> bool {
     (32..=126).contains(&value)
 }
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(bytes);
+    crc.sum()
+}